@@ -14,6 +14,7 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 let mut agent = Self::default();
                 agent.agent.objective = objective;
                 agent.agent.position = position;
+                agent.agent.llm = LlmConfig::for_agent(&agent.agent.position);
                 agent
             }
 
@@ -21,6 +22,10 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 self.agent.update(status);
             }
 
+            fn try_update(&mut self, status: Status) -> Result<()> {
+                self.agent.try_update(status)
+            }
+
             fn objective(&self) -> &std::borrow::Cow<'static, str> {
                 &self.agent.objective
             }
@@ -94,6 +99,64 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
         }
 
+        impl #name {
+            /// Replaces the client used to reach the model provider.
+            ///
+            /// # Arguments
+            ///
+            /// * `client` - The client to use for every subsequent model call.
+            ///
+            /// # Returns
+            ///
+            /// (`Self`): The agent using the given client.
+            pub fn with_client(mut self, client: ClientType) -> Self {
+                self.client = client;
+                self
+            }
+
+            /// Replaces the model and sampling settings sent with each request.
+            ///
+            /// # Arguments
+            ///
+            /// * `config` - The settings to use for every subsequent model call.
+            ///
+            /// # Returns
+            ///
+            /// (`Self`): The agent using the given settings.
+            pub fn with_config(mut self, config: LlmConfig) -> Self {
+                self.agent.llm = config;
+                self
+            }
+
+            /// Replaces the cache of model replies.
+            ///
+            /// # Arguments
+            ///
+            /// * `cache` - The cache to check before every subsequent model call.
+            ///
+            /// # Returns
+            ///
+            /// (`Self`): The agent using the given cache.
+            pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+                self.agent.cache = Some(cache);
+                self
+            }
+
+            /// Replaces the tools offered to the model.
+            ///
+            /// # Arguments
+            ///
+            /// * `tools` - The tools the model may call during every subsequent generation.
+            ///
+            /// # Returns
+            ///
+            /// (`Self`): The agent using the given tools.
+            pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+                self.agent.tools = tools;
+                self
+            }
+        }
+
         impl Functions for #name {
             fn get_agent(&self) -> &AgentGPT {
                 &self.agent
@@ -109,7 +172,13 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 browse: bool,
                 max_tries: u64,
             ) -> Result<()> {
-                <#name as Executor>::execute(self, tasks, execute, browse, max_tries).await
+                self.agent.start_run()?;
+                let result =
+                    <#name as Executor>::execute(self, tasks, execute, browse, max_tries).await;
+                if let Err(err) = &result {
+                    self.agent.fail(err);
+                }
+                result
             }
 
             /// Saves a communication to long-term memory for the agent.
@@ -125,11 +194,13 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             /// # Business Logic
             ///
             /// - This method uses the `save_long_term_memory` util function to save the communication into the agent's long-term memory.
-            /// - The communication is embedded and stored using the agent's unique ID as the namespace.
+            /// - The communication is embedded and stored in the agent's own namespace, `agent-<id>` as returned by `namespace`, and expires after the agent's `memory_ttl` if set. Memories shared with other agents are written with `save_shared_memory` instead.
             /// - It handles the embedding and metadata for the communication, ensuring it's stored correctly.
             #[cfg(feature = "mem")]
             async fn save_ltm(&mut self, communication: Communication) -> Result<()> {
-                save_long_term_memory(&mut self.client, self.agent.id.clone(), communication).await
+                save_long_term_memory(&mut self.agent, &mut self.client, communication)
+                .await?;
+                Ok(())
             }
 
             /// Retrieves all communications stored in the agent's long-term memory.
@@ -141,208 +212,84 @@ pub fn derive_agent(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             /// # Business Logic
             ///
             /// - This method fetches the stored communications for the agent by interacting with the `load_long_term_memory` function.
-            /// - The function will return a list of communications that are indexed by the agent's unique ID.
+            /// - The function will return the communications of the agent's own namespace, `agent-<id>`, without its shared memories.
             /// - It handles the retrieval of the stored metadata and content for each communication.
             #[cfg(feature = "mem")]
             async fn get_ltm(&self) -> Result<Vec<Communication>> {
-                load_long_term_memory(self.agent.id.clone()).await
+                load_long_term_memory(self.agent.ltm.as_ref(), self.agent.id.clone()).await
             }
 
-            /// Retrieves the concatenated context of all communications in the agent's long-term memory.
+            /// Retrieves the memories most relevant to a query from the agent's long-term memory.
+            ///
+            /// # Arguments
+            ///
+            /// * `query` - The text to find memories about, such as the current request.
+            /// * `k` - The maximum number of memories to recall.
             ///
             /// # Returns
             ///
-            /// (`String`): A string containing the concatenated role and content of all communications stored in the agent's long-term memory.
+            /// (`String`): The recalled communications as `role: content` lines, the oldest first.
             ///
             /// # Business Logic
             ///
-            /// - This method calls the `long_term_memory_context` function to generate a string representation of the agent's entire long-term memory.
-            /// - The context string is composed of each communication's role and content, joined by new lines.
-            /// - It provides a quick overview of the agent's memory in a human-readable format.
+            /// - This method calls the `relevant_memory_context` util function, which embeds the query and searches the agent's namespace and its shared memories.
+            /// - The closest records are ranked by the agent's `recall` policy, which may favour recent memories and skip near duplicates.
+            /// - An empty string is returned if the memory cannot be read.
             #[cfg(feature = "mem")]
-            async fn ltm_context(&self) -> String {
-                long_term_memory_context(self.agent.id.clone()).await
+            async fn ltm_context(&mut self, query: &str, k: usize) -> String {
+                relevant_memory_context(&mut self.agent, &mut self.client, query, k)
+                .await
             }
 
             async fn generate(&mut self, request: &str) -> Result<String> {
-                match &mut self.client {
-                    #[cfg(feature = "gem")]
-                    ClientType::Gemini(gem_client) => {
-                        let parameters = ChatBuilder::default()
-                            .messages(vec![Message::User {
-                                content: Content::Text(request.to_string()),
-                                name: None,
-                            }])
-                            .build()?;
-
-                        let result = gem_client.chat().generate(parameters).await;
-                        Ok(result.unwrap_or_default())
-                    }
-
-                    #[cfg(feature = "oai")]
-                    ClientType::OpenAI(oai_client) => {
-                        let parameters = ChatCompletionParametersBuilder::default()
-                            .model(FlagshipModel::Gpt4O.to_string())
-                            .messages(vec![ChatMessage::User {
-                                content: ChatMessageContent::Text(request.to_string()),
-                                name: None,
-                            }])
-                            .response_format(ChatCompletionResponseFormat::Text)
-                            .build()?;
-
-                        let result = oai_client.chat().create(parameters).await?;
-                        let message = &result.choices[0].message;
-
-                        Ok(match message {
-                            ChatMessage::Assistant {
-                                content: Some(chat_content),
-                                ..
-                            } => chat_content.to_string(),
-                            ChatMessage::User { content, .. } => content.to_string(),
-                            ChatMessage::System { content, .. } => content.to_string(),
-                            ChatMessage::Developer { content, .. } => content.to_string(),
-                            ChatMessage::Tool { content, .. } => content.clone(),
-                            _ => String::new(),
-                        })
-                    }
-
-                    #[cfg(feature = "cld")]
-                    ClientType::Anthropic(client) => {
-                        let body = CreateMessageParams::new(RequiredMessageParams {
-                            model: "claude-3-7-sonnet-latest".to_string(),
-                            messages: vec![AnthMessage::new_text(Role::User, request.to_string())],
-                            max_tokens: 1024,
-                        });
-
-                        let chat_response = client.create_message(Some(&body)).await?;
-                        Ok(chat_response
-                            .content
-                            .iter()
-                            .filter_map(|block| match block {
-                                ContentBlock::Text { text, .. } => Some(text.as_str()),
-                                _ => None,
-                            })
-                            .collect::<Vec<_>>()
-                            .join("\n"))
-                    }
-
-                    #[cfg(feature = "xai")]
-                    ClientType::Xai(xai_client) => {
-                        let messages = vec![XaiMessage {
-                            role: "user".into(),
-                            content: request.to_string(),
-                        }];
-
-                        let rb = ChatCompletionsRequestBuilder::new(
-                            xai_client.clone(),
-                            "grok-beta".into(),
-                            messages,
-                        )
-                        .temperature(0.0)
-                        .stream(false);
-
-                        let req = rb.clone().build()?;
-                        let chat = rb.create_chat_completion(req).await?;
-                        Ok(chat.choices[0].message.content.clone())
-                    }
+                let messages = [Communication::user(request.to_string())];
+                self.generate_messages_with(&messages, &LlmConfig::default()).await
+            }
 
+            async fn generate_messages(&mut self, messages: &[Communication]) -> Result<String> {
+                self.generate_messages_with(messages, &LlmConfig::default()).await
+            }
 
-                    #[allow(unreachable_patterns)]
-                    _ => {
-                        return Err(anyhow!(
-                            "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                        ));
-                    }
-                }
+            async fn generate_messages_with(
+                &mut self,
+                messages: &[Communication],
+                config: &LlmConfig,
+            ) -> Result<String> {
+                self.agent
+                    .generate_messages_with(&mut self.client, messages, config)
+                    .await
             }
 
             async fn imagen(&mut self, request: &str) -> Result<Vec<u8>> {
-                match &mut self.client {
-                    #[cfg(feature = "gem")]
-                    ClientType::Gemini(gem_client) => {
-                        gem_client.set_model(Model::FlashExpImage);
-
-                        let input = Message::User {
-                            content: Content::Text(request.into()),
-                            name: None,
-                        };
-
-                        let params = ImageGenBuilder::default()
-                            .model(Model::FlashExpImage)
-                            .input(input)
-                            .build()?;
-
-                        let image_bytes = gem_client.images().generate(params).await;
-                        Ok(image_bytes.unwrap_or_default())
-                    }
-
-                    #[cfg(feature = "oai")]
-                    ClientType::OpenAI(oai_client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-                    #[cfg(feature = "cld")]
-                    ClientType::Anthropic(client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-                    #[cfg(feature = "xai")]
-                    ClientType::Xai(xai_client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-
-                    #[allow(unreachable_patterns)]
-                    _ => {
-                        return Err(anyhow!(
-                            "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                        ));
+                let retry = self.agent.retry;
+                let mut attempt = 1;
+                loop {
+                    match LlmProvider::image(&mut self.client, request).await {
+                        Ok(value) => return Ok(value),
+                        Err(err) => {
+                            attempt = retry
+                                .backoff("imagen", attempt, err)
+                                .await?;
+                        }
                     }
                 }
             }
 
-            async fn stream(&mut self, request: &str) -> Result<ReqResponse> {
-                match &mut self.client {
-                    #[cfg(feature = "gem")]
-                    ClientType::Gemini(gem_client) => {
-                        let parameters = StreamBuilder::default()
-                            .model(Model::Flash20)
-                            .input(Message::User {
-                                content: Content::Text(request.into()),
-                                name: None,
-                            })
-                            .build()?;
-
-                        Ok(ReqResponse(Some(gem_client.stream().generate(parameters).await?)))
-                    }
-
-                    #[cfg(feature = "oai")]
-                    ClientType::OpenAI(oai_client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-                    #[cfg(feature = "cld")]
-                    ClientType::Anthropic(client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-                    #[cfg(feature = "xai")]
-                    ClientType::Xai(xai_client) => {
-                        // TODO: Implement this
-                        Ok(Default::default())
-                    }
-
-
-                    #[allow(unreachable_patterns)]
-                    _ => {
-                        return Err(anyhow!(
-                            "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                        ));
+            async fn stream(&mut self, request: &str) -> Result<TextStream> {
+                let config = self.agent.llm.clone();
+                let retry = self.agent.retry;
+                let mut attempt = 1;
+                loop {
+                    match LlmProvider::stream_with(&mut self.client, request, &config).await {
+                        Ok(deltas) => {
+                            let model = config.model_or(self.client.name());
+                            return Ok(self.agent.usage.meter(model, request, deltas));
+                        }
+                        Err(err) => {
+                            attempt = retry
+                                .backoff("stream", attempt, err)
+                                .await?;
+                        }
                     }
                 }
             }
@@ -0,0 +1,110 @@
+use autogpt::common::memory::{HnswConfig, HnswIndex, Metric};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::time::Instant;
+
+const VECTORS: usize = 10_000;
+const DIMENSIONS: usize = 128;
+const QUERIES: usize = 100;
+const K: usize = 10;
+
+/// Deterministic xorshift generator, so that every run indexes the same vectors.
+struct Vectors(u64);
+
+impl Vectors {
+    fn next(&mut self) -> Vec<f32> {
+        (0..DIMENSIONS)
+            .map(|_| {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+            })
+            .collect()
+    }
+}
+
+/// Returns the `K` exact nearest neighbours of `query`.
+fn exact(metric: Metric, vectors: &[Vec<f32>], query: &[f32]) -> Vec<usize> {
+    let mut scored: Vec<(f32, usize)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, vector)| (metric.score(query, vector), i))
+        .collect();
+    scored.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(K).map(|(_, i)| i).collect()
+}
+
+fn bench_memory(c: &mut Criterion) {
+    let mut generator = Vectors(0x9e3779b97f4a7c15);
+    let vectors: Vec<Vec<f32>> = (0..VECTORS).map(|_| generator.next()).collect();
+    let queries: Vec<Vec<f32>> = (0..QUERIES).map(|_| generator.next()).collect();
+
+    let mut cosine = None;
+    let mut group = c.benchmark_group("memory_search");
+    for metric in [Metric::Cosine, Metric::Dot, Metric::Euclidean] {
+        let start = Instant::now();
+        let mut index = HnswIndex::new(HnswConfig::new().metric(metric));
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(i.to_string(), vector).unwrap();
+        }
+        let build = start.elapsed();
+
+        let mut hits = 0;
+        for query in &queries {
+            let truth = exact(metric, &vectors, query);
+            hits += index
+                .search(query, K)
+                .unwrap()
+                .iter()
+                .filter(|(id, _)| truth.contains(&id.parse().unwrap()))
+                .count();
+        }
+        println!(
+            "{metric:?}: indexed {VECTORS} vectors of {DIMENSIONS} dimensions in {build:?}, recall@{K} {:.3}",
+            hits as f64 / (QUERIES * K) as f64
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("hnsw", format!("{metric:?}")),
+            &index,
+            |b, index| {
+                let mut i = 0;
+                b.iter(|| {
+                    i = (i + 1) % QUERIES;
+                    black_box(index.search(&queries[i], K).unwrap());
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("exact", format!("{metric:?}")),
+            &vectors,
+            |b, vectors| {
+                let mut i = 0;
+                b.iter(|| {
+                    i = (i + 1) % QUERIES;
+                    black_box(exact(metric, vectors, &queries[i]));
+                });
+            },
+        );
+
+        if metric == Metric::Cosine {
+            cosine = Some(index);
+        }
+    }
+    group.finish();
+
+    let mut index = cosine.expect("the cosine index is built");
+    let mut extra = 0;
+    c.bench_function("memory_insert", |b| {
+        b.iter(|| {
+            extra += 1;
+            index
+                .insert(format!("extra-{extra}"), &queries[extra % QUERIES])
+                .unwrap();
+        });
+    });
+}
+
+criterion_group!(benches, bench_memory);
+criterion_main!(benches);
@@ -7,6 +7,8 @@ pub mod agent;
 #[cfg(feature = "gpt")]
 pub mod architect;
 #[cfg(feature = "gpt")]
+pub mod autonomous;
+#[cfg(feature = "gpt")]
 pub mod backend;
 #[cfg(feature = "gpt")]
 pub mod designer;
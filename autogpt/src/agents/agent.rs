@@ -1,28 +1,85 @@
 //! # `AgentGPT` agent.
 //!
 
+use crate::common::cache::ResponseCache;
+use crate::common::compaction::CompactionPolicy;
+use crate::common::config::LlmConfig;
+use crate::common::lifecycle::{AgentEvent, EVENT_CAPACITY};
+use crate::common::reflection::Evaluation;
+use crate::common::retry::RetryPolicy;
+use crate::common::tokens::TokenBudget;
+use crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, find_tool, tool_steps};
+use crate::common::usage::UsageTracker;
 use crate::common::utils::{
-    Capability, Communication, ContextManager, Knowledge, Persona, Planner, Reflection, Status,
-    Task, TaskScheduler, Tool, default_eval_fn,
+    Capability, ClientType, Communication, ContextManager, Knowledge, Persona, Planner, Reflection,
+    Status, Task, TaskScheduler, Tool, default_eval_fn,
 };
+use crate::tools::{builtin, workspace};
 use crate::traits::agent::Agent;
+use crate::traits::provider::LlmProvider;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use derivative::Derivative;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
 use uuid::Uuid;
 #[cfg(feature = "net")]
 use {
     crate::collaboration::{AgentNet, Collaborator, RemoteAgent, delegate_task},
     crate::common::utils::AgentMessage,
     crate::traits::functions::Collaborate,
-    anyhow::{Result, anyhow},
-    async_trait::async_trait,
     iac_rs::prelude::*,
     std::collections::VecDeque,
-    std::sync::Arc,
     std::time::Duration,
     tokio::sync::Mutex,
 };
+#[cfg(feature = "mem")]
+use {
+    crate::common::memory::{
+        RecallPolicy, default_store, namespace, save_long_term_memory, shared_namespace,
+    },
+    crate::traits::memory::MemoryStore,
+};
+
+/// A client whose replies to `chat` are retried with an agent's policy and recorded in its
+/// usage, for the model calls made on its behalf outside of `generate`, such as summaries.
+#[derive(Debug)]
+pub(crate) struct Metered<'a> {
+    pub(crate) client: &'a mut ClientType,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) usage: &'a UsageTracker,
+}
+
+#[async_trait]
+impl LlmProvider for Metered<'_> {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        let messages = [Communication::user(request.to_string())];
+        let mut attempt = 1;
+        loop {
+            match self
+                .client
+                .completion(&messages, &LlmConfig::default())
+                .await
+            {
+                Ok(completion) => {
+                    self.usage
+                        .record_completion(self.client.name(), &messages, &completion);
+                    return Ok(completion.text);
+                }
+                Err(err) => {
+                    attempt = self.retry.backoff("summarize", attempt, err).await?;
+                }
+            }
+        }
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        self.client.context_window(config).await
+    }
+}
 
 /// Represents an agent with memory, tools, and other autonomous capabilities.
 #[derive(Derivative)]
@@ -43,6 +100,24 @@ pub struct AgentGPT {
     /// Hot memory containing past communications.
     pub memory: Vec<Communication>,
 
+    /// Limits how much of the memory is sent with each request.
+    pub budget: TokenBudget,
+
+    /// Summarizes the older part of the memory once it grows too long, if set.
+    pub compaction: Option<CompactionPolicy>,
+
+    /// Retries model calls that fail with rate limits or transient errors.
+    pub retry: RetryPolicy,
+
+    /// Model and sampling settings sent with each request.
+    pub llm: LlmConfig,
+
+    /// Tokens used by the agent's model calls.
+    pub usage: UsageTracker,
+
+    /// Cache of model replies, if enabled.
+    pub cache: Option<ResponseCache>,
+
     /// Tools available to the agent.
     pub tools: Vec<Tool>,
 
@@ -120,6 +195,29 @@ pub struct AgentGPT {
     /// Round-robin index used to evenly distribute workload among peers.
     #[cfg(feature = "net")]
     pub rr_idx: usize,
+
+    /// Store of the long-term memory of the agent.
+    #[cfg(feature = "mem")]
+    #[derivative(PartialEq = "ignore")]
+    pub ltm: Arc<dyn MemoryStore>,
+
+    /// How memories relevant to a request are recalled from the long-term memory.
+    #[cfg(feature = "mem")]
+    pub recall: RecallPolicy,
+
+    /// How long the memories saved by the agent are kept, forever if unset.
+    #[cfg(feature = "mem")]
+    pub memory_ttl: Option<std::time::Duration>,
+
+    /// Names of the shared memories the agent recalls from besides its own, such as the name
+    /// of its project.
+    #[cfg(feature = "mem")]
+    pub shared_memory: Vec<Cow<'static, str>>,
+
+    /// Channel publishing the events of the agent to its subscribers. Clones of the agent
+    /// publish to the same subscribers.
+    #[derivative(PartialEq = "ignore", Debug = "ignore")]
+    pub events: broadcast::Sender<AgentEvent>,
 }
 
 impl Default for AgentGPT {
@@ -130,6 +228,12 @@ impl Default for AgentGPT {
             position: Cow::Borrowed(""),
             status: Status::default(),
             memory: vec![],
+            budget: TokenBudget::default(),
+            compaction: CompactionPolicy::from_env(),
+            retry: RetryPolicy::from_env(),
+            llm: LlmConfig::from_env(),
+            usage: UsageTracker::default(),
+            cache: ResponseCache::from_env(),
             tools: vec![],
             knowledge: Knowledge::default(),
             planner: None,
@@ -168,6 +272,15 @@ impl Default for AgentGPT {
             cap_index: HashMap::new(),
             #[cfg(feature = "net")]
             rr_idx: 0,
+            #[cfg(feature = "mem")]
+            ltm: default_store(),
+            #[cfg(feature = "mem")]
+            recall: RecallPolicy::from_env(),
+            #[cfg(feature = "mem")]
+            memory_ttl: None,
+            #[cfg(feature = "mem")]
+            shared_memory: Vec::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 }
@@ -179,9 +292,361 @@ impl AgentGPT {
     ///
     /// * `communication` - The communication to be added to the memory.
     pub fn add_communication(&mut self, communication: Communication) {
+        self.emit(|agent| AgentEvent::MessageAdded {
+            agent,
+            message: communication.clone(),
+        });
         self.memory.push(communication);
     }
 
+    /// Compacts the memory of the agent if its `compaction` policy asks for it, archiving the
+    /// compacted communications to long-term memory if the policy says so.
+    ///
+    /// The summary is retried according to the agent's `retry` policy and recorded in its
+    /// `usage`. A failure is logged and leaves the memory as it is, so that the next call tries
+    /// again.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client writing the summary.
+    pub async fn compact_memory(&mut self, client: &mut ClientType) {
+        let Some(policy) = self.compaction else {
+            return;
+        };
+        if !policy.needs_compaction(&self.memory) {
+            return;
+        }
+        let mut metered = Metered {
+            client: &mut *client,
+            retry: self.retry,
+            usage: &self.usage,
+        };
+        let compacted = match policy.compact(&mut metered, &mut self.memory).await {
+            Ok(compacted) => compacted,
+            Err(err) => {
+                warn!("Failed to compact the memory of {}: {err:#}", self.id);
+                return;
+            }
+        };
+        if compacted.is_empty() {
+            return;
+        }
+        debug!(
+            "Compacted {} communications of {}",
+            compacted.len(),
+            self.id
+        );
+
+        #[cfg(feature = "mem")]
+        if policy.archive {
+            for communication in compacted {
+                let saved = save_long_term_memory(self, client, communication).await;
+                if let Err(err) = saved {
+                    warn!("Failed to archive a communication of {}: {err:#}", self.id);
+                }
+            }
+        }
+    }
+
+    /// Returns the namespaces the agent recalls memories from: its own, then its shared
+    /// memories.
+    #[cfg(feature = "mem")]
+    pub fn memory_namespaces(&self) -> Vec<String> {
+        std::iter::once(namespace(&self.id))
+            .chain(self.shared_memory.iter().map(|name| shared_namespace(name)))
+            .collect()
+    }
+
+    /// Deletes memories from the long-term memory of the agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The identifiers of the records to delete, as returned by
+    ///   `save_long_term_memory` or found with the `ltm` store.
+    #[cfg(feature = "mem")]
+    pub async fn forget(&self, ids: &[String]) -> Result<()> {
+        self.ltm.delete(&namespace(&self.id), ids).await
+    }
+
+    /// Deletes the whole long-term memory of the agent. Its shared memories are kept.
+    #[cfg(feature = "mem")]
+    pub async fn wipe_memory(&self) -> Result<()> {
+        self.ltm.delete_namespace(&namespace(&self.id)).await
+    }
+
+    /// Prepares the agent for a new run: blocked, paused and cancelled agents refuse to start,
+    /// and failed ones start over from `Idle`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status of the agent does not let it run, as checked by
+    /// `Status::ensure_runnable`.
+    pub fn start_run(&mut self) -> Result<()> {
+        self.status.ensure_runnable()?;
+        if let Status::Failed(_) = self.status {
+            self.update(Status::Idle);
+        }
+        Ok(())
+    }
+
+    /// Subscribes to the events of the agent: its status changes, the messages added to its
+    /// memory and the tools it invokes, from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publishes an event built from the identifier of the agent, if anyone is subscribed.
+    pub fn emit(&self, event: impl FnOnce(Cow<'static, str>) -> AgentEvent) {
+        if self.events.receiver_count() > 0 {
+            // Sending only fails once every subscriber is gone.
+            let _ = self.events.send(event(self.id.clone()));
+        }
+    }
+
+    /// Runs the tool a call names, and publishes its invocation.
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - The call made by the model.
+    ///
+    /// # Returns
+    ///
+    /// The result of the tool, or its error, or `None` if the agent has no such tool.
+    pub async fn invoke_tool(&self, call: &ToolCall) -> Option<String> {
+        let tool = find_tool(&self.tools, call)?;
+        debug!("[*] {:?}: Calling tool `{}`", self.position, call.name);
+        let result = tool.respond(call.arguments.clone()).await;
+        self.emit(|agent| AgentEvent::ToolInvoked {
+            agent,
+            tool: call.name.clone(),
+            arguments: call.arguments.clone(),
+            result: result.clone(),
+        });
+        Some(result)
+    }
+
+    /// Attaches the built-in tools to the agent, scoped to the `AUTOGPT_WORKSPACE` directory.
+    ///
+    /// Tools the agent already has are kept, and take precedence over built-in tools of the
+    /// same name.
+    pub fn add_builtin_tools(&mut self) {
+        for tool in builtin(workspace()) {
+            if !self.tools.iter().any(|existing| existing.name == tool.name) {
+                self.tools.push(tool);
+            }
+        }
+    }
+
+    /// Evaluates the agent's recent work with the evaluator of its `reflection`, and keeps
+    /// the lessons of the evaluation for the next attempt.
+    ///
+    /// Each lesson is added to the reflection logs, and all of them are added to the memory in
+    /// a single message, so that they are part of the next conversations of the agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client the evaluator may call the model through.
+    ///
+    /// # Returns
+    ///
+    /// The evaluation, or an empty one if the agent has no reflection.
+    pub async fn reflect(&mut self, client: &mut ClientType) -> Result<Evaluation> {
+        let Some(evaluator) = self
+            .reflection
+            .as_ref()
+            .map(|reflection| Arc::clone(&reflection.evaluator))
+        else {
+            return Ok(Evaluation::default());
+        };
+        let evaluation = evaluator.evaluate(self, client).await?;
+
+        if let Some(reflection) = self.reflection.as_mut() {
+            for lesson in &evaluation.lessons {
+                reflection
+                    .recent_logs
+                    .push(Cow::Owned(format!("Lesson: {lesson}")));
+            }
+        }
+        if !evaluation.lessons.is_empty() {
+            self.add_communication(Communication::assistant(format!(
+                "Lessons for the next attempt:\n- {}",
+                evaluation.lessons.join("\n- ")
+            )));
+        }
+        Ok(evaluation)
+    }
+
+    /// Builds the messages of a model request from the agent's memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - Instructions sent as the leading `system` message.
+    /// * `request` - The new `user` message, appended after the remembered conversation.
+    ///
+    /// # Returns
+    ///
+    /// The `system` message, followed by the memory in order, followed by the request. The
+    /// agent's `budget` is applied when the messages are sent with `generate_messages`.
+    pub fn conversation(&self, system: &str, request: &str) -> Vec<Communication> {
+        let mut messages = Vec::with_capacity(self.memory.len() + 2);
+        messages.push(Communication::system(system.to_string()));
+        messages.extend(self.memory.iter().cloned());
+        messages.push(Communication::user(request.to_string()));
+        messages
+    }
+
+    /// Shortens a conversation to fit the agent's `budget` and the context window of the model
+    /// `client` sends it to with `config`, keeping `config.max_tokens` free for the reply.
+    ///
+    /// When the budget summarizes the overflow, the summary is retried according to the
+    /// agent's `retry` policy and recorded in its `usage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client the conversation is sent to, which writes the summary.
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - The model and sampling settings the conversation is sent with.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Vec<Communication>>`): The messages to send.
+    pub async fn fit_to_budget(
+        &mut self,
+        client: &mut ClientType,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Vec<Communication>> {
+        let mut metered = Metered {
+            client,
+            retry: self.retry,
+            usage: &self.usage,
+        };
+        self.budget.fit(&mut metered, messages, config).await
+    }
+
+    /// Sends a conversation to the model along with the agent's `tools`, runs every tool the
+    /// model calls and sends back the results, until the model answers with text.
+    ///
+    /// The messages are fitted to the agent's `budget` first, every call is retried according
+    /// to its `retry` policy and recorded in its `usage`. Replies are never cached, since tools
+    /// may have side effects.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to send the requests through.
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - The model and sampling settings.
+    ///
+    /// # Returns
+    ///
+    /// The final answer of the model, or an error once `tool_steps()` rounds of calls were made
+    /// without one.
+    pub async fn call_tools(
+        &mut self,
+        client: &mut ClientType,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        let messages = self.fit_to_budget(client, messages, config).await?;
+        let tools: Vec<ToolDefinition> = self.tools.iter().map(Tool::definition).collect();
+        let mut exchanges: Vec<ToolExchange> = Vec::new();
+        let steps = tool_steps();
+
+        for _ in 0..=steps {
+            let retry = self.retry;
+            let mut attempt = 1;
+            let reply = loop {
+                match client
+                    .complete_with_tools(&messages, &exchanges, &tools, config)
+                    .await
+                {
+                    Ok(reply) => break reply,
+                    Err(err) => {
+                        attempt = retry.backoff("generate", attempt, err).await?;
+                    }
+                }
+            };
+            self.usage
+                .record_completion(client.name(), &messages, &reply.completion);
+
+            if reply.calls.is_empty() {
+                return Ok(reply.completion.text);
+            }
+
+            let mut results = Vec::with_capacity(reply.calls.len());
+            for call in &reply.calls {
+                let result = match self.invoke_tool(call).await {
+                    Some(result) => result,
+                    None => format!("Error: Unknown tool `{}`.", call.name),
+                };
+                results.push(result);
+            }
+            exchanges.push(ToolExchange {
+                text: reply.completion.text,
+                calls: reply.calls,
+                results,
+            });
+        }
+
+        Err(anyhow!(
+            "The model kept calling tools after {steps} rounds without answering."
+        ))
+    }
+
+    /// Sends a conversation to the model and returns its reply, the way agents generate text.
+    ///
+    /// The memory is compacted first if the `compaction` policy asks for it, and `config` is
+    /// applied over the agent's `llm` settings. With `tools`, the conversation goes through
+    /// `call_tools`. Otherwise the reply is looked up in the `cache`, and on a miss the
+    /// messages are fitted to the `budget`, the call is retried according to the `retry` policy,
+    /// recorded in `usage` and its reply cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to send the request through.
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - The model and sampling settings overriding the agent's.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The reply of the model.
+    pub async fn generate_messages_with(
+        &mut self,
+        client: &mut ClientType,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        self.compact_memory(client).await;
+        let config = self.llm.clone().merge(config);
+        if !self.tools.is_empty() {
+            return self.call_tools(client, messages, &config).await;
+        }
+        let cached = self
+            .cache
+            .as_ref()
+            .map(|cache| cache.entry("generate", client.name(), &config, messages));
+        if let Some(text) = cached.as_ref().and_then(|entry| entry.load()) {
+            return Ok(text);
+        }
+        let messages = self.fit_to_budget(client, messages, &config).await?;
+        let mut attempt = 1;
+        loop {
+            match client.completion(&messages, &config).await {
+                Ok(completion) => {
+                    self.usage
+                        .record_completion(client.name(), &messages, &completion);
+                    if let Some(entry) = &cached {
+                        entry.store(&completion.text);
+                    }
+                    return Ok(completion.text);
+                }
+                Err(err) => {
+                    attempt = self.retry.backoff("generate", attempt, err).await?;
+                }
+            }
+        }
+    }
+
     /// Creates a new instance of `AgentGPT` with owned strings.
     ///
     /// # Arguments
@@ -201,6 +666,17 @@ impl AgentGPT {
 
             memory: vec![],
 
+            budget: TokenBudget::default(),
+            compaction: CompactionPolicy::from_env(),
+
+            retry: RetryPolicy::from_env(),
+
+            llm: LlmConfig::for_agent(&position),
+
+            usage: UsageTracker::default(),
+
+            cache: ResponseCache::from_env(),
+
             tools: vec![],
 
             knowledge: Knowledge {
@@ -217,14 +693,9 @@ impl AgentGPT {
                 behavior_script: None,
             },
 
-            reflection: Some(Reflection {
-                recent_logs: vec![],
-                evaluation_fn: default_eval_fn,
-            }),
+            reflection: Some(Reflection::from_fn(default_eval_fn)),
 
-            scheduler: Some(TaskScheduler {
-                scheduled_tasks: vec![],
-            }),
+            scheduler: Some(TaskScheduler::default()),
 
             capabilities: HashSet::default(),
 
@@ -256,6 +727,15 @@ impl AgentGPT {
             cap_index: HashMap::new(),
             #[cfg(feature = "net")]
             rr_idx: 0,
+            #[cfg(feature = "mem")]
+            ltm: default_store(),
+            #[cfg(feature = "mem")]
+            recall: RecallPolicy::from_env(),
+            #[cfg(feature = "mem")]
+            memory_ttl: None,
+            #[cfg(feature = "mem")]
+            shared_memory: Vec::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
@@ -278,6 +758,17 @@ impl AgentGPT {
 
             memory: vec![],
 
+            budget: TokenBudget::default(),
+            compaction: CompactionPolicy::from_env(),
+
+            retry: RetryPolicy::from_env(),
+
+            llm: LlmConfig::for_agent(position),
+
+            usage: UsageTracker::default(),
+
+            cache: ResponseCache::from_env(),
+
             tools: vec![],
 
             knowledge: Knowledge {
@@ -294,14 +785,9 @@ impl AgentGPT {
                 behavior_script: None,
             },
 
-            reflection: Some(Reflection {
-                recent_logs: vec![],
-                evaluation_fn: default_eval_fn,
-            }),
+            reflection: Some(Reflection::from_fn(default_eval_fn)),
 
-            scheduler: Some(TaskScheduler {
-                scheduled_tasks: vec![],
-            }),
+            scheduler: Some(TaskScheduler::default()),
 
             capabilities: HashSet::default(),
 
@@ -333,6 +819,15 @@ impl AgentGPT {
             cap_index: HashMap::new(),
             #[cfg(feature = "net")]
             rr_idx: 0,
+            #[cfg(feature = "mem")]
+            ltm: default_store(),
+            #[cfg(feature = "mem")]
+            recall: RecallPolicy::from_env(),
+            #[cfg(feature = "mem")]
+            memory_ttl: None,
+            #[cfg(feature = "mem")]
+            shared_memory: Vec::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
@@ -409,6 +904,17 @@ impl Agent for AgentGPT {
 
             memory: vec![],
 
+            budget: TokenBudget::default(),
+            compaction: CompactionPolicy::from_env(),
+
+            retry: RetryPolicy::from_env(),
+
+            llm: LlmConfig::for_agent(&position),
+
+            usage: UsageTracker::default(),
+
+            cache: ResponseCache::from_env(),
+
             tools: vec![],
 
             knowledge: Knowledge {
@@ -425,14 +931,9 @@ impl Agent for AgentGPT {
                 behavior_script: None,
             },
 
-            reflection: Some(Reflection {
-                recent_logs: vec![],
-                evaluation_fn: default_eval_fn,
-            }),
+            reflection: Some(Reflection::from_fn(default_eval_fn)),
 
-            scheduler: Some(TaskScheduler {
-                scheduled_tasks: vec![],
-            }),
+            scheduler: Some(TaskScheduler::default()),
 
             capabilities: HashSet::default(),
 
@@ -464,12 +965,35 @@ impl Agent for AgentGPT {
             cap_index: HashMap::new(),
             #[cfg(feature = "net")]
             rr_idx: 0,
+            #[cfg(feature = "mem")]
+            ltm: default_store(),
+            #[cfg(feature = "mem")]
+            recall: RecallPolicy::from_env(),
+            #[cfg(feature = "mem")]
+            memory_ttl: None,
+            #[cfg(feature = "mem")]
+            shared_memory: Vec::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
-    /// Updates the agent's operational status.
+    /// Updates the agent's operational status, and publishes the change. Transitions not
+    /// allowed by `Status::can_become` are logged, and keep the current status.
     fn update(&mut self, status: Status) {
-        self.status = status;
+        if let Err(err) = self.try_update(status) {
+            warn!("[*] {:?}: {err}", self.position);
+        }
+    }
+
+    /// Updates the agent's operational status if the transition is allowed, and publishes the
+    /// change.
+    fn try_update(&mut self, status: Status) -> Result<()> {
+        let from = self.status.transition(status)?;
+        if from != self.status {
+            let to = self.status.clone();
+            self.emit(|agent| AgentEvent::StatusChanged { agent, from, to });
+        }
+        Ok(())
     }
 
     /// Returns the agent's objective.
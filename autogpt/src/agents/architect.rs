@@ -39,11 +39,15 @@
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
+use crate::common::lifecycle::AgentEvent;
+use crate::common::tools::tool_name;
 #[allow(unused_imports)]
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, GenerationOutput, Goal, Knowledge,
     OutputKind, Persona, Planner, Reflection, Scope, Status, Task, TaskScheduler, Tool,
-    extract_array, extract_json_string, strip_code_blocks,
+    strip_code_blocks,
 };
 use crate::prompts::architect::{
     ARCHITECT_DIAGRAM_PROMPT, ARCHITECT_ENDPOINTS_PROMPT, ARCHITECT_SCOPE_PROMPT,
@@ -56,6 +60,7 @@ use colored::*;
 // use duckduckgo::browser::Browser;
 // use duckduckgo::user_agents::get;
 use reqwest::Client as ReqClient;
+use serde_json::json;
 use std::borrow::Cow;
 use std::env::var;
 use std::process::Stdio;
@@ -68,37 +73,11 @@ use tracing::{debug, error, info, warn};
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
-    crate::common::memory::save_long_term_memory,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
+    crate::common::memory::save_long_term_memory, crate::common::memory::with_recalled_memory,
 };
 
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
+use crate::traits::provider::{LlmProvider, TextStream};
 
 use auto_derive::Auto;
 
@@ -235,19 +214,16 @@ impl ArchitectGPT {
         tasks: &mut Task,
         output_type: OutputKind,
     ) -> Result<GenerationOutput> {
-        #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
         let current_code = fs::read_to_string(&format!("{}/diagram.py", self.workspace)).await?;
         let request: String = format!(
-            "{}\n\nTask Description: {}\nPrevious Conversation: {:?}\nCurrent Architecture: {:?}",
-            prompt,
-            tasks.description,
-            self.agent.memory(),
-            current_code
+            "Task Description: {}\nCurrent Architecture: {:?}",
+            tasks.description, current_code
         );
+        #[cfg(feature = "mem")]
+        let recalled = self.ltm_context(&request, self.agent.recall.k).await;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -264,13 +240,27 @@ impl ArchitectGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let (response_text, output) = match output_type {
+            OutputKind::Text => {
+                let text = self.generate_messages(&messages).await?;
+                let output = GenerationOutput::Text(strip_code_blocks(&text));
+                (text, output)
+            }
+            OutputKind::UrlList => {
+                let urls: Vec<Cow<'static, str>> = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&urls)?,
+                    GenerationOutput::UrlList(urls),
+                )
+            }
+            OutputKind::Scope => {
+                let scope: Scope = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&scope)?,
+                    GenerationOutput::Scope(scope),
+                )
+            }
+        };
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -289,20 +279,11 @@ impl ArchitectGPT {
 
         debug!("[*] {:?}: {:?}", self.agent.position(), self.agent);
 
-        match output_type {
-            OutputKind::Text => Ok(GenerationOutput::Text(strip_code_blocks(&response_text))),
-            OutputKind::UrlList => {
-                let urls: Vec<Cow<'static, str>> =
-                    serde_json::from_str(&extract_array(&response_text).unwrap_or_default())?;
-                tasks.urls = Some(urls.clone());
-                self.agent.update(Status::InUnitTesting);
-                Ok(GenerationOutput::UrlList(urls))
-            }
-            OutputKind::Scope => {
-                let scope: Scope = serde_json::from_str(&strip_code_blocks(&response_text))?;
-                Ok(GenerationOutput::Scope(scope))
-            }
+        if let GenerationOutput::UrlList(urls) = &output {
+            tasks.urls = Some(urls.clone());
+            self.agent.update(Status::InUnitTesting);
         }
+        Ok(output)
     }
 
     /// Retrieves the scope based on tasks description and logs the interaction in agent memory.
@@ -425,16 +406,19 @@ impl ArchitectGPT {
                 description: "Identify system components".into(),
                 priority: 1,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Determine communication between components".into(),
                 priority: 2,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Generate diagram for architecture".into(),
                 priority: 3,
                 completed: false,
+                dependencies: Vec::new(),
             },
         ];
 
@@ -456,10 +440,11 @@ impl ArchitectGPT {
             description: format!("Default task from context: {context}"),
             priority: 1,
             completed: false,
+            dependencies: Vec::new(),
         }
     }
 
-    pub fn act(&mut self, goal: Goal) {
+    pub async fn act(&mut self, goal: Goal) {
         info!(
             "{}",
             format!(
@@ -471,13 +456,19 @@ impl ArchitectGPT {
             .bold()
         );
 
-        for tool in self.agent.tools() {
+        for tool in self.agent.tools().clone() {
             if goal
                 .description
                 .to_lowercase()
                 .contains(&format!("{:?}", tool.name).to_lowercase())
             {
-                let result = (tool.invoke)(&goal.description);
+                let result = tool.run(&goal.description).await;
+                self.agent.emit(|agent| AgentEvent::ToolInvoked {
+                    agent,
+                    tool: tool_name(&tool.name),
+                    arguments: json!({ "input": goal.description }),
+                    result: result.clone(),
+                });
                 info!(
                     "{}",
                     format!(
@@ -488,10 +479,8 @@ impl ArchitectGPT {
                     )
                     .green()
                 );
-                self.agent.memory_mut().push(Communication {
-                    role: goal.description.into(),
-                    content: result.into(),
-                });
+                self.agent
+                    .add_communication(Communication::assistant(result));
                 return;
             }
         }
@@ -507,10 +496,10 @@ impl ArchitectGPT {
         );
     }
 
-    pub fn reflect(&mut self) {
+    pub async fn reflect(&mut self) {
         let entry = format!("Reflection on step toward '{}'", self.agent.objective());
 
-        self.agent.memory_mut().push(Communication {
+        self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
             content: entry.clone().into(),
         });
@@ -523,17 +512,20 @@ impl ArchitectGPT {
                 content: entry.into(),
             });
 
-        if let Some(reflection) = self.agent.reflection() {
-            let feedback = (reflection.evaluation_fn)(&self.agent);
-            info!(
+        match self.agent.reflect(&mut self.client).await {
+            Ok(evaluation) => info!(
                 "{}",
                 format!(
                     "[*] {:?}: Self Reflection: {}",
                     self.agent.position(),
-                    feedback
+                    evaluation.summary
                 )
                 .blue()
-            );
+            ),
+            Err(err) => warn!(
+                "[*] {:?}: Self Reflection failed: {err}",
+                self.agent.position()
+            ),
         }
     }
     pub fn has_completed_objective(&self) -> bool {
@@ -865,7 +857,7 @@ impl Executor for ArchitectGPT {
 
             self.mark_goal_complete(goal);
 
-            self.reflect();
+            self.reflect().await;
 
             if self.has_completed_objective() {
                 info!(
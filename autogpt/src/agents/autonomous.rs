@@ -0,0 +1,609 @@
+//! # `AutonomousGPT` agent.
+//!
+//! This module provides a generic agent running a ReAct-style loop: at every step the model
+//! thinks about the objective, chooses one of the agent's tools and observes its result, until
+//! it finishes with an answer. Instead of a hand-coded pipeline, the behavior of the agent
+//! comes from its configuration: its tools, its plan, its persona, the maximum number of steps
+//! and an optional stopping criterion.
+//!
+//! Every decision is a structured reply, so the loop works with every provider, including
+//! those without native function calling, and malformed decisions are repaired like any other
+//! structured output.
+//!
+//! # Example - Answering with a calculator and a scratchpad:
+//!
+//! ```rust
+//! use autogpt::agents::autonomous::AutonomousGPT;
+//! use autogpt::tools::builtin;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut agent = AutonomousGPT::new("Answer questions with tools", "AutonomousGPT")
+//!         .await
+//!         .with_tools(builtin("workspace"))
+//!         .with_goals(["Compute the area", "Report it in square meters"])
+//!         .with_max_steps(6);
+//!
+//!     match agent.run("What is the area of a circle of radius 3 m?").await {
+//!         Ok(answer) => println!("{answer}"),
+//!         Err(err) => eprintln!("Error running the agent: {err:?}"),
+//!     }
+//! }
+//! ```
+//!
+//! # Key Features:
+//!
+//! - **Think, act, observe**: Each step records the thought of the model, the tool it called
+//!   with its arguments, and the observation, in `steps`.
+//!
+//! - **Planning**: Goals of the agent's planner are shown to the model, which marks them as
+//!   completed along the way. With `pursue`, the model first decomposes the objective into
+//!   goals with priorities and dependencies, which are then run one by one in topological
+//!   order, and re-planned when one of them fails.
+//!
+//! - **Stopping**: A run ends when the model finishes, when the stopping criterion set with
+//!   `with_stop` accepts a step, or with an error once `max_steps` steps were taken.
+//!
+//! - **Reflection**: Each step is logged in the agent's reflection, when it has one. After a
+//!   failed run, the agent reflects with its evaluator, such as a `Critic`, and the lessons
+//!   are part of the next runs.
+
+use crate::agents::agent::AgentGPT;
+#[cfg(feature = "net")]
+use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::compaction::CompactionPolicy;
+use crate::common::config::LlmConfig;
+use crate::common::planning::GoalPlan;
+use crate::common::structured::repairs;
+use crate::common::tools::ToolCall;
+use crate::common::utils::{
+    Capability, ClientType, Communication, ContextManager, Goal, Knowledge, Persona, Planner,
+    Reflection, Status, Task, TaskScheduler, Tool,
+};
+use crate::prompts::autonomous::{AUTONOMOUS_PROMPT, PLAN_PROMPT, REPLAN_PROMPT};
+use crate::traits::agent::Agent;
+use crate::traits::functions::{AsyncFunctions, Executor, Functions};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use auto_derive::Auto;
+use colored::*;
+use derivative::Derivative;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use crate::traits::provider::{LlmProvider, TextStream};
+#[cfg(feature = "mem")]
+use {
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
+    crate::common::memory::save_long_term_memory, crate::common::memory::with_recalled_memory,
+};
+
+/// Number of steps used when none is configured.
+pub const DEFAULT_MAX_STEPS: usize = 10;
+
+/// Number of times `pursue` re-plans after a failed goal when none is configured.
+pub const DEFAULT_MAX_REPLANS: usize = 2;
+
+/// Action with which the model ends a run.
+pub const FINISH: &str = "finish";
+
+/// Criterion deciding whether a run ends after a step.
+pub type StopCondition = Arc<dyn Fn(&Step) -> bool + Send + Sync>;
+
+/// A decision of the model at one step of the loop.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Decision {
+    /// Reasoning about what is known so far and what to do next.
+    pub thought: String,
+    /// Name of the tool to call, or `finish` once the objective is met.
+    pub action: String,
+    /// Arguments of the tool call, following its schema.
+    #[serde(default)]
+    pub input: Value,
+    /// The final answer, when the action is `finish`.
+    #[serde(default)]
+    pub answer: Option<String>,
+    /// Numbers of the plan goals completed by this step.
+    #[serde(default)]
+    pub completed_goals: Vec<usize>,
+}
+
+/// One step of a run: a decision of the model and what came of it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Step {
+    /// The decision of the model.
+    pub decision: Decision,
+    /// The result of the tool call, or the answer when the model finished.
+    pub observation: String,
+}
+
+impl Step {
+    /// Returns whether the model finished with this step.
+    pub fn is_finish(&self) -> bool {
+        self.decision.action == FINISH
+    }
+}
+
+/// Struct representing an `AutonomousGPT`, which works towards an objective by reasoning and
+/// calling tools in a loop.
+#[derive(Derivative, Auto)]
+#[derivative(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct AutonomousGPT {
+    /// Represents the GPT agent holding the tools, plan, persona and memory of the loop.
+    agent: AgentGPT,
+
+    /// Represents the client for interacting with the model provider.
+    client: ClientType,
+
+    /// Upper bound on the steps of a run.
+    #[derivative(Default(value = "DEFAULT_MAX_STEPS"))]
+    pub max_steps: usize,
+
+    /// Upper bound on the re-plans of a `pursue` after failed goals.
+    #[derivative(Default(value = "DEFAULT_MAX_REPLANS"))]
+    pub max_replans: usize,
+
+    /// Ends a run early when it accepts a step.
+    #[derivative(Debug = "ignore")]
+    pub stop: Option<StopCondition>,
+
+    /// Steps of the last run, in order.
+    pub steps: Vec<Step>,
+}
+
+impl AutonomousGPT {
+    /// Constructs a new instance of `AutonomousGPT`.
+    ///
+    /// # Arguments
+    ///
+    /// * `objective` - A static string describing the agent's main purpose or mission.
+    /// * `position` - A static string indicating the role or position of the agent.
+    ///
+    /// # Returns
+    ///
+    /// (`AutonomousGPT`): An agent without tools or goals, running up to `DEFAULT_MAX_STEPS`
+    /// steps.
+    pub async fn new(objective: &'static str, position: &'static str) -> Self {
+        let mut agent = AgentGPT::new_borrowed(objective, position);
+        agent.id = agent.position().to_string().into();
+
+        info!(
+            "{}",
+            format!("[*] {:?}: 🔁 Autonomous agent ready!", agent.position())
+                .bright_white()
+                .bold()
+        );
+
+        Self {
+            agent,
+            client: ClientType::from_env(),
+            max_steps: DEFAULT_MAX_STEPS,
+            max_replans: DEFAULT_MAX_REPLANS,
+            stop: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Replaces the goals of the agent's plan, in the order they should be worked on.
+    pub fn with_goals(mut self, goals: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let current_plan = goals
+            .into_iter()
+            .enumerate()
+            .map(|(i, description)| Goal {
+                description: description.into(),
+                priority: u8::try_from(i + 1).unwrap_or(u8::MAX),
+                completed: false,
+                dependencies: Vec::new(),
+            })
+            .collect();
+        self.agent.planner = Some(Planner { current_plan });
+        self
+    }
+
+    /// Sets the upper bound on the steps of a run.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets the upper bound on the re-plans of a `pursue` after failed goals.
+    pub fn with_max_replans(mut self, max_replans: usize) -> Self {
+        self.max_replans = max_replans;
+        self
+    }
+
+    /// Replaces the reflection of the agent, such as with one evaluated by a `Critic`.
+    pub fn with_reflection(mut self, reflection: Reflection) -> Self {
+        self.agent.reflection = Some(reflection);
+        self
+    }
+
+    /// Sets the policy compacting the memory of the agent once it grows too long.
+    pub fn with_compaction(mut self, compaction: CompactionPolicy) -> Self {
+        self.agent.compaction = Some(compaction);
+        self
+    }
+
+    /// Sets a criterion ending a run early, such as a tool returning a given result. The
+    /// observation of the accepted step becomes the answer of the run.
+    pub fn with_stop(mut self, stop: impl Fn(&Step) -> bool + Send + Sync + 'static) -> Self {
+        self.stop = Some(Arc::new(stop));
+        self
+    }
+
+    /// Runs the loop until the model finishes or the run is stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - What the agent should achieve in this run.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The answer of the model, or the observation of the step accepted by
+    /// the stopping criterion.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the agent is blocked, paused or cancelled, if a model call fails, if
+    /// a decision still does not parse after its repairs, or if the run takes `max_steps` steps
+    /// without finishing.
+    ///
+    /// # Business Logic
+    ///
+    /// - Shows the model the objective, the persona, the plan and the tools at every step,
+    ///   followed by the decisions and observations of the run so far.
+    /// - With the `mem` feature, adds the long-term memories most relevant to the request.
+    /// - Runs the chosen tool and records its result, or its error, as the observation.
+    /// - Marks the goals completed by each step in the planner.
+    /// - Saves the request and the answer in the agent's memory once the run is over.
+    /// - Refuses to start while the agent is blocked, paused or cancelled.
+    /// - Marks the agent as `Failed` with the error when the run fails.
+    pub async fn run(&mut self, request: &str) -> Result<String> {
+        self.agent.start_run()?;
+        let result = self.run_steps(request).await;
+        if let Err(err) = &result {
+            self.agent.fail(err);
+        }
+        result
+    }
+
+    /// Runs the loop of `run` until the model finishes or the step limit is reached.
+    async fn run_steps(&mut self, request: &str) -> Result<String> {
+        self.steps.clear();
+        self.agent.update(Status::Active);
+        let system = self.system_prompt();
+        #[cfg(feature = "mem")]
+        let system = {
+            let recalled = self.ltm_context(request, self.agent.recall.k).await;
+            with_recalled_memory(&system, &recalled)
+        };
+        let mut messages = self.agent.conversation(&system, request);
+
+        for number in 1..=self.max_steps {
+            messages.push(Communication::user(self.state()));
+            let decision = self.decide(&messages).await?;
+            messages.pop();
+            debug!(
+                "[*] {:?}: Step {number}: {} ({})",
+                self.agent.position, decision.action, decision.thought
+            );
+
+            let observation = if decision.action == FINISH {
+                decision.answer.clone().unwrap_or_default()
+            } else {
+                self.act(&decision).await
+            };
+            self.complete_goals(&decision.completed_goals);
+
+            let step = Step {
+                decision,
+                observation,
+            };
+            messages.push(Communication::assistant(serde_json::to_string(
+                &step.decision,
+            )?));
+            messages.push(Communication::user(format!(
+                "Observation: {}",
+                step.observation
+            )));
+            if let Some(reflection) = self.agent.reflection.as_mut() {
+                reflection.recent_logs.push(Cow::Owned(format!(
+                    "Step {number}: {} -> {}",
+                    step.decision.action, step.observation
+                )));
+            }
+
+            let done = step.is_finish() || self.stop.as_ref().is_some_and(|stop| stop(&step));
+            let answer = step.observation.clone();
+            self.steps.push(step);
+            if done {
+                self.agent
+                    .add_communication(Communication::user(request.to_string()));
+                self.agent
+                    .add_communication(Communication::assistant(answer.clone()));
+                self.agent.update(Status::Completed);
+                return Ok(answer);
+            }
+        }
+
+        Err(anyhow!(
+            "The agent did not finish within {} steps",
+            self.max_steps
+        ))
+    }
+
+    /// Asks the model to decompose an objective into goals, and replaces the agent's plan with
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a model call fails, or if the plan is still invalid, for instance
+    /// because its dependencies form a cycle, after `repairs()` corrections.
+    pub async fn plan(&mut self, objective: &str) -> Result<()> {
+        let messages = vec![
+            Communication::system(format!("{}\nObjective: {objective}", PLAN_PROMPT.trim())),
+            Communication::user(self.tool_list()),
+        ];
+        let plan = self.propose(messages, Planner::from_plan).await?;
+        info!(
+            "[*] {:?}: Planned {} goals:\n{}",
+            self.agent.position,
+            plan.current_plan.len(),
+            plan.describe()
+        );
+        self.agent.planner = Some(plan);
+        Ok(())
+    }
+
+    /// Plans an objective with `plan`, then runs its goals one by one in topological order,
+    /// marking each as completed when its run finishes.
+    ///
+    /// When a goal fails, the model is shown the plan and the error and replaces the goals not
+    /// yet completed, up to `max_replans` times.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The answer of the last goal.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if planning fails, or if a goal fails once `max_replans` re-plans were
+    /// made.
+    pub async fn pursue(&mut self, objective: &str) -> Result<String> {
+        self.plan(objective).await?;
+        let mut replans = 0;
+        let mut answer = String::new();
+
+        while let Some((position, goal)) = self.agent.planner.as_ref().and_then(|planner| {
+            let position = planner.next_goal()?;
+            Some((position, planner.current_plan[position].description.clone()))
+        }) {
+            let request = format!("Overall objective: {objective}\nCurrent goal: {goal}");
+            match self.run(&request).await {
+                Ok(result) => {
+                    if let Some(planner) = self.agent.planner.as_mut() {
+                        planner.complete(position);
+                    }
+                    answer = result;
+                }
+                Err(err) if replans < self.max_replans => {
+                    replans += 1;
+                    debug!(
+                        "[*] {:?}: Goal {} failed, re-planning: {err}",
+                        self.agent.position,
+                        position + 1
+                    );
+                    self.reflect().await;
+                    self.replan(objective, position, &err).await?;
+                }
+                Err(err) => {
+                    return Err(err.context(format!("Goal {} `{goal}` failed", position + 1)));
+                }
+            }
+        }
+        Ok(answer)
+    }
+
+    /// Asks the model to replace the goals not yet completed after the goal at `failed`
+    /// failed with `error`.
+    async fn replan(
+        &mut self,
+        objective: &str,
+        failed: usize,
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        let Some(current) = self.agent.planner.clone() else {
+            return self.plan(objective).await;
+        };
+        let messages = vec![
+            Communication::system(format!("{}\nObjective: {objective}", REPLAN_PROMPT.trim())),
+            Communication::user(format!(
+                "{}\nPlan:\n{}\nGoal {} failed: {error}",
+                self.tool_list(),
+                current.describe(),
+                failed + 1
+            )),
+        ];
+        let plan = self
+            .propose(messages, |plan| {
+                let mut replanned = current.clone();
+                replanned.replan(plan)?;
+                Ok(replanned)
+            })
+            .await?;
+        self.agent.planner = Some(plan);
+        Ok(())
+    }
+
+    /// Asks the model for a plan until `build` accepts it, sending back why it was rejected up
+    /// to `repairs()` times.
+    async fn propose(
+        &mut self,
+        mut messages: Vec<Communication>,
+        build: impl Fn(GoalPlan) -> Result<Planner>,
+    ) -> Result<Planner> {
+        let mut attempt = 0;
+        loop {
+            let tools = std::mem::take(&mut self.agent.tools);
+            let plan = self.generate_structured::<GoalPlan>(&messages).await;
+            self.agent.tools = tools;
+            let plan = plan?;
+
+            match build(plan.clone()) {
+                Ok(planner) => return Ok(planner),
+                Err(err) if attempt < repairs() => {
+                    attempt += 1;
+                    messages.push(Communication::assistant(serde_json::to_string(&plan)?));
+                    messages.push(Communication::user(format!(
+                        "The plan is invalid: {err} Reply again with the corrected plan."
+                    )));
+                }
+                Err(err) => return Err(err.context("The model proposed an invalid plan")),
+            }
+        }
+    }
+
+    /// Reflects on a failed run, so that the lessons of the evaluation are part of the next
+    /// runs. A failed evaluation is only logged.
+    async fn reflect(&mut self) {
+        match self.agent.reflect(&mut self.client).await {
+            Ok(evaluation) => debug!(
+                "[*] {:?}: Self Reflection: {}",
+                self.agent.position, evaluation.summary
+            ),
+            Err(err) => warn!(
+                "[*] {:?}: Self Reflection failed: {err}",
+                self.agent.position
+            ),
+        }
+    }
+
+    /// Asks the model for its next decision, without offering the tools natively so that it
+    /// answers with a decision rather than calling them.
+    async fn decide(&mut self, messages: &[Communication]) -> Result<Decision> {
+        let tools = std::mem::take(&mut self.agent.tools);
+        let decision = self.generate_structured::<Decision>(messages).await;
+        self.agent.tools = tools;
+        decision
+    }
+
+    /// Runs the tool chosen by a decision and returns its result or error.
+    async fn act(&self, decision: &Decision) -> String {
+        let call = ToolCall::new("", decision.action.clone(), decision.input.clone());
+        match self.agent.invoke_tool(&call).await {
+            Some(result) => result,
+            None => format!(
+                "Error: Unknown tool `{}`. Use one of the listed tools, or `{FINISH}`.",
+                decision.action
+            ),
+        }
+    }
+
+    /// Marks goals of the plan as completed, by their number.
+    fn complete_goals(&mut self, numbers: &[usize]) {
+        if let Some(planner) = self.agent.planner.as_mut() {
+            for number in numbers {
+                if let Some(goal) = number
+                    .checked_sub(1)
+                    .and_then(|i| planner.current_plan.get_mut(i))
+                {
+                    goal.completed = true;
+                }
+            }
+        }
+    }
+
+    /// Builds the instructions of the loop: the objective, the persona and the tools.
+    fn system_prompt(&self) -> String {
+        let mut prompt = format!(
+            "{}\nObjective: {}\n",
+            AUTONOMOUS_PROMPT.trim(),
+            self.agent.objective
+        );
+        if !self.agent.persona.traits.is_empty() {
+            prompt.push_str(&format!(
+                "Persona: {}, {}\n",
+                self.agent.persona.name,
+                self.agent.persona.traits.join(", ")
+            ));
+        }
+
+        prompt.push('\n');
+        prompt.push_str(&self.tool_list());
+        prompt
+    }
+
+    /// Lists the tools of the agent with their arguments.
+    fn tool_list(&self) -> String {
+        let mut list = String::from("Tools:\n");
+        if self.agent.tools.is_empty() {
+            list.push_str("None, finish with your own knowledge.\n");
+        }
+        for tool in &self.agent.tools {
+            let definition = tool.definition();
+            list.push_str(&format!(
+                "- `{}`: {} Arguments: {}\n",
+                definition.name, definition.description, definition.parameters
+            ));
+        }
+        list
+    }
+
+    /// Describes the progress of the run, shown before every decision.
+    fn state(&self) -> String {
+        let mut state = format!("Step {} of {}.", self.steps.len() + 1, self.max_steps);
+        if let Some(planner) = &self.agent.planner
+            && !planner.current_plan.is_empty()
+        {
+            state.push_str(&format!("\nPlan:\n{}", planner.describe()));
+        }
+        state.push_str("\nDecide on the next step.");
+        state
+    }
+}
+
+/// Implementation of the `Executor` trait for the `AutonomousGPT` struct.
+///
+/// Runs the loop on the description of the task. The answer is saved in the agent's memory.
+#[async_trait]
+impl Executor for AutonomousGPT {
+    /// Asynchronously runs the loop on a task.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - Mutable reference to the `Task` whose description is the request of the run.
+    /// * `_execute` - Unused, tools are always run.
+    /// * `_browse` - Unused.
+    /// * `max_tries` - Number of runs attempted before giving up, at least one.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<()>`): Result indicating whether a run finished.
+    async fn execute<'a>(
+        &'a mut self,
+        tasks: &'a mut Task,
+        _execute: bool,
+        _browse: bool,
+        max_tries: u64,
+    ) -> Result<()> {
+        let request = tasks.description.to_string();
+        let mut attempt = 1;
+        loop {
+            match self.run(&request).await {
+                Ok(_) => return Ok(()),
+                Err(err) if attempt < max_tries => {
+                    debug!("[*] {:?}: Run {attempt} failed: {err}", self.agent.position);
+                    self.reflect().await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
@@ -41,13 +41,15 @@
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
 #[cfg(feature = "cli")]
 use crate::common::utils::spinner;
 #[allow(unused_imports)]
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, GenerationOutput, Goal, Knowledge,
     OutputKind, Persona, Planner, Reflection, Route, Scope, Status, Task, TaskScheduler, Tool,
-    extract_array, strip_code_blocks,
+    strip_code_blocks,
 };
 use crate::prompts::backend::{
     API_ENDPOINTS_PROMPT, FIX_CODE_PROMPT, IMPROVED_WEBSERVER_CODE_PROMPT, WEBSERVER_CODE_PROMPT,
@@ -75,37 +77,11 @@ use webbrowser::{Browser, BrowserOptions, open_browser_with_options};
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
-    crate::common::memory::save_long_term_memory,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
+    crate::common::memory::save_long_term_memory, crate::common::memory::with_recalled_memory,
 };
 
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
+use crate::traits::provider::{LlmProvider, TextStream};
 
 /// Struct representing a BackendGPT, which manages backend development tasks using GPT.
 #[derive(Debug, Clone, Default, Auto)]
@@ -273,17 +249,12 @@ impl BackendGPT {
         tasks: &mut Task,
         output_type: OutputKind,
     ) -> Result<GenerationOutput> {
+        let request: String = format!("Task Description: {}", tasks.description);
         #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
-        let request: String = format!(
-            "{}\n\nTask Description: {}\nPrevious Conversation: {:?}",
-            prompt,
-            tasks.description,
-            self.agent.memory(),
-        );
+        let recalled = self.ltm_context(&request, self.agent.recall.k).await;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -300,13 +271,27 @@ impl BackendGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let (response_text, output) = match output_type {
+            OutputKind::Text => {
+                let text = self.generate_messages(&messages).await?;
+                let output = GenerationOutput::Text(strip_code_blocks(&text));
+                (text, output)
+            }
+            OutputKind::UrlList => {
+                let urls: Vec<Cow<'static, str>> = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&urls)?,
+                    GenerationOutput::UrlList(urls),
+                )
+            }
+            OutputKind::Scope => {
+                let scope: Scope = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&scope)?,
+                    GenerationOutput::Scope(scope),
+                )
+            }
+        };
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
             content: Cow::Owned(response_text.clone()),
@@ -324,20 +309,11 @@ impl BackendGPT {
 
         debug!("[*] {:?}: {:?}", self.agent.position(), self.agent);
 
-        match output_type {
-            OutputKind::Text => Ok(GenerationOutput::Text(strip_code_blocks(&response_text))),
-            OutputKind::UrlList => {
-                let urls: Vec<Cow<'static, str>> =
-                    serde_json::from_str(&extract_array(&response_text).unwrap_or_default())?;
-                tasks.urls = Some(urls.clone());
-                self.agent.update(Status::InUnitTesting);
-                Ok(GenerationOutput::UrlList(urls))
-            }
-            OutputKind::Scope => {
-                let scope: Scope = serde_json::from_str(&strip_code_blocks(&response_text))?;
-                Ok(GenerationOutput::Scope(scope))
-            }
+        if let GenerationOutput::UrlList(urls) = &output {
+            tasks.urls = Some(urls.clone());
+            self.agent.update(Status::InUnitTesting);
         }
+        Ok(output)
     }
 
     /// Asynchronously generates backend code based on tasks and logs the interaction.
@@ -417,22 +393,26 @@ impl BackendGPT {
     /// # Business Logic
     ///
     /// - Constructs a request based on the existing backend code and project description.
+    /// - With the `mem` feature, adds the long-term memories most relevant to the project.
     /// - Logs the user's request as a `Communication`.
     /// - Sends the request to the Gemini or OpenAI API to generate improved code.
     /// - Logs the AI's response as a `Communication`.
     /// - Writes the improved backend code to the appropriate file.
     /// - Updates tasks and agent status accordingly.
     pub async fn improve_backend_code(&mut self, tasks: &mut Task) -> Result<String> {
-        #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
         let code_template = tasks.backend_code.clone().unwrap_or_default();
         let request = format!(
-            "{}\n\nCode Template: {}\nProject Description: {}",
-            IMPROVED_WEBSERVER_CODE_PROMPT, code_template, tasks.description
+            "Code Template: {}\nProject Description: {}",
+            code_template, tasks.description
         );
+        #[cfg(feature = "mem")]
+        let recalled = self
+            .ltm_context(&tasks.description, self.agent.recall.k)
+            .await;
+        let prompt = IMPROVED_WEBSERVER_CODE_PROMPT;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -449,13 +429,7 @@ impl BackendGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let response_text = self.generate_messages(&messages).await?;
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -516,21 +490,24 @@ impl BackendGPT {
     /// # Business Logic
     ///
     /// - Constructs a request based on the buggy backend code and project description.
+    /// - With the `mem` feature, adds the long-term memories most relevant to the project.
     /// - Logs the request as a user `Communication`.
     /// - Sends the request to the Gemini or OpenAI API to generate content for fixing bugs.
     /// - Logs the response or any errors as assistant `Communication`s.
     /// - Writes the fixed backend code to the appropriate file.
     /// - Updates tasks and agent status accordingly.
     pub async fn fix_code_bugs(&mut self, tasks: &mut Task) -> Result<String> {
-        #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
         let buggy_code = tasks.backend_code.clone().unwrap_or_default();
         let bugs = self.bugs.clone().unwrap_or_default();
-        let request =
-            format!("{FIX_CODE_PROMPT}\n\nBuggy Code: {buggy_code}\nBugs: {bugs}\n\nFix all bugs.");
+        let request = format!("Buggy Code: {buggy_code}\nBugs: {bugs}\n\nFix all bugs.");
+        #[cfg(feature = "mem")]
+        let recalled = self
+            .ltm_context(&tasks.description, self.agent.recall.k)
+            .await;
+        let prompt = FIX_CODE_PROMPT;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -547,13 +524,7 @@ impl BackendGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let response_text = self.generate_messages(&messages).await?;
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -611,16 +582,13 @@ impl BackendGPT {
     ///
     /// - Reads the backend code from the appropriate file.
     /// - Constructs a request with the backend code.
+    /// - With the `mem` feature, adds the long-term memories most relevant to the objective of
+    ///   the agent.
     /// - Logs the user's request as a `Communication`.
     /// - Sends the request to the Gemini or OpenAI API to generate content for routes JSON.
     /// - Logs the AI's response as a `Communication`.
     /// - Updates agent status accordingly.
     pub async fn get_routes_json(&mut self) -> Result<String> {
-        #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
         let path = self.workspace.clone();
         let full_path = match self.language {
             "rust" => format!("{path}/src/main.rs"),
@@ -636,9 +604,16 @@ impl BackendGPT {
         );
 
         let backend_code = fs::read_to_string(full_path).await?;
-        let request = format!(
-            "{API_ENDPOINTS_PROMPT}\n\nHere is the backend code with all routes:{backend_code}"
-        );
+        let request = format!("Here is the backend code with all routes:{backend_code}");
+        #[cfg(feature = "mem")]
+        let recalled = {
+            let objective = self.agent.objective().to_string();
+            self.ltm_context(&objective, self.agent.recall.k).await
+        };
+        let prompt = API_ENDPOINTS_PROMPT;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -654,13 +629,7 @@ impl BackendGPT {
                 })
                 .await;
         }
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let response_text = self.generate_messages(&messages).await?;
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -694,16 +663,19 @@ impl BackendGPT {
                 description: "Generate backend code".into(),
                 priority: 1,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Fix code bugs if any".into(),
                 priority: 2,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Run unit tests and backend server".into(),
                 priority: 3,
                 completed: false,
+                dependencies: Vec::new(),
             },
         ];
 
@@ -725,6 +697,7 @@ impl BackendGPT {
             description: "Default backend task".into(),
             priority: 1,
             completed: false,
+            dependencies: Vec::new(),
         }
     }
 
@@ -780,13 +753,13 @@ impl BackendGPT {
         Ok(())
     }
 
-    pub fn reflect(&mut self) {
+    pub async fn reflect(&mut self) {
         let entry = format!(
             "Reflection on backend task for '{}'",
             self.agent.objective()
         );
 
-        self.agent.memory_mut().push(Communication {
+        self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
             content: entry.clone().into(),
         });
@@ -799,17 +772,20 @@ impl BackendGPT {
                 content: entry.into(),
             });
 
-        if let Some(reflection) = self.agent.reflection() {
-            let feedback = (reflection.evaluation_fn)(&self.agent);
-            info!(
+        match self.agent.reflect(&mut self.client).await {
+            Ok(evaluation) => info!(
                 "{}",
                 format!(
                     "[*] {:?}: Self Reflection: {}",
                     self.agent.position(),
-                    feedback
+                    evaluation.summary
                 )
                 .blue()
-            );
+            ),
+            Err(err) => warn!(
+                "[*] {:?}: Self Reflection failed: {err}",
+                self.agent.position()
+            ),
         }
     }
 
@@ -1273,7 +1249,7 @@ impl Executor for BackendGPT {
 
             #[cfg(feature = "cli")]
             let pb = spinner("Reflecting...");
-            self.reflect();
+            self.reflect().await;
             #[cfg(feature = "cli")]
             pb.finish_with_message("Reflection complete!");
 
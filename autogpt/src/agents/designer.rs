@@ -39,6 +39,8 @@
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, Knowledge, Persona, Planner, Reflection,
     Status, Task, TaskScheduler, Tool, similarity,
@@ -47,7 +49,7 @@ use crate::common::utils::{
 use crate::prompts::designer::{IMGGET_PROMPT, WEB_DESIGNER_PROMPT};
 use crate::traits::agent::Agent;
 use crate::traits::functions::{AsyncFunctions, Executor, Functions};
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
 use auto_derive::Auto;
 use colored::*;
@@ -62,39 +64,11 @@ use tracing::{debug, error, info};
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
 };
 
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-    utils::load_and_encode_image,
-    vision::VisionBuilder,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
+use crate::traits::provider::{LlmProvider, TextStream};
 
 use derivative::Derivative;
 
@@ -265,13 +239,7 @@ impl DesignerGPT {
         #[cfg(feature = "img")]
         save_image(&text_response, &img_path).unwrap();
 
-        #[allow(unused)]
-        let mut image_data = vec![0];
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            image_data = self.imagen(&text_prompt).await?;
-        }
+        let image_data = self.imagen(&text_prompt).await?;
 
         std::fs::write(&img_path, &image_data)?;
 
@@ -314,9 +282,9 @@ impl DesignerGPT {
     ///
     /// # Business Logic
     ///
-    /// - Loads and encodes the image from the specified file path.
+    /// - Loads the image from the specified file path.
     /// - Logs communication between the user, assistant, and system.
-    /// - Sends the image data to the Gemini or OpenAI API to generate text.
+    /// - Sends the image to the configured client, which must support `describe_image`.
     /// - Returns the generated text description of the image.
     pub async fn generate_text_from_image(&mut self, image_path: &str) -> Result<String> {
         self.agent.add_communication(Communication {
@@ -338,35 +306,29 @@ impl DesignerGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut base64_image_data = String::new();
+        let image = match fs::read(image_path).await {
+            Ok(image) => image,
+            Err(_) => {
+                let error_msg = format!("Failed to load image at path: {image_path}");
 
-        #[cfg(feature = "gem")]
-        {
-            base64_image_data = match load_and_encode_image(image_path) {
-                Ok(data) => data,
-                Err(_) => {
-                    let error_msg = format!("Failed to load or encode image at path: {image_path}");
-
-                    self.agent.add_communication(Communication {
-                        role: Cow::Borrowed("system"),
-                        content: Cow::Owned(error_msg.clone()),
-                    });
-
-                    #[cfg(feature = "mem")]
-                    {
-                        let _ = self
-                            .save_ltm(Communication {
-                                role: Cow::Borrowed("system"),
-                                content: Cow::Owned(error_msg.clone()),
-                            })
-                            .await;
-                    }
-                    debug!("[*] {:?}: Error loading image!", self.agent.position());
-                    return Ok("".to_string());
+                self.agent.add_communication(Communication {
+                    role: Cow::Borrowed("system"),
+                    content: Cow::Owned(error_msg.clone()),
+                });
+
+                #[cfg(feature = "mem")]
+                {
+                    let _ = self
+                        .save_ltm(Communication {
+                            role: Cow::Borrowed("system"),
+                            content: Cow::Owned(error_msg.clone()),
+                        })
+                        .await;
                 }
-            };
-        }
+                debug!("[*] {:?}: Error loading image!", self.agent.position());
+                return Ok("".to_string());
+            }
+        };
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -384,251 +346,38 @@ impl DesignerGPT {
                 })
                 .await;
         }
-        let response: String = match &mut self.client {
-            #[cfg(feature = "gem")]
-            ClientType::Gemini(gem_client) => {
-                let params = VisionBuilder::default()
-                    .input(Message::User {
-                        content: Content::Text(WEB_DESIGNER_PROMPT.to_string()),
-                        name: None,
-                    })
-                    .image(Message::Tool {
-                        content: base64_image_data,
-                    })
-                    .build()?;
-
-                let result = gem_client.vision().generate(params).await;
-
-                match result {
-                    Ok(response) => {
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(format!("Generated image description: {response}")),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(format!(
-                                        "Generated image description: {response}"
-                                    )),
-                                })
-                                .await;
-                        }
-
-                        debug!(
-                            "[*] {:?}: Got Image Description: {:?}",
-                            self.agent.position(),
-                            response
-                        );
-
-                        response
-                    }
-
-                    Err(err) => {
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(format!(
-                                "Error generating image description: {err}"
-                            )),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(format!(
-                                        "Error generating image description: {err}"
-                                    )),
-                                })
-                                .await;
-                        }
-
-                        Default::default()
-                    }
-                }
-            }
 
-            #[cfg(feature = "oai")]
-            ClientType::OpenAI(oai_client) => {
-                let parameters = ChatCompletionParametersBuilder::default()
-                    .model(FlagshipModel::Gpt4O.to_string())
-                    .messages(vec![
-                        ChatMessage::User {
-                            content: ChatMessageContent::Text("What is in this image?".to_string()),
-                            name: None,
-                        },
-                        ChatMessage::User {
-                            content: ChatMessageContent::ContentPart(vec![
-                                ChatMessageContentPart::Image(ChatMessageImageContentPart {
-                                    r#type: "image_url".to_string(),
-                                    image_url: ImageUrlType {
-                                        url: base64_image_data.to_string(),
-                                        detail: None,
-                                    },
-                                }),
-                            ]),
-                            name: None,
-                        },
-                    ])
-                    .build()?;
-
-                let result = oai_client.chat().create(parameters).await;
-
-                match result {
-                    Ok(chat_response) => {
-                        let message = &chat_response.choices[0].message;
-
-                        let response_text = match message {
-                            ChatMessage::Assistant {
-                                content: Some(chat_content),
-                                ..
-                            } => chat_content.to_string(),
-                            ChatMessage::User { content, .. } => content.to_string(),
-                            ChatMessage::System { content, .. } => content.to_string(),
-                            ChatMessage::Developer { content, .. } => content.to_string(),
-                            ChatMessage::Tool { content, .. } => content.clone(),
-                            _ => String::from(""),
-                        };
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(format!(
-                                "Generated image description: {response_text}"
-                            )),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(format!(
-                                        "Generated image description: {response_text}"
-                                    )),
-                                })
-                                .await;
-                        }
-
-                        debug!(
-                            "[*] {:?}: Got Image Description: {:?}",
-                            self.agent.position(),
-                            response_text
-                        );
-
-                        response_text
-                    }
-
-                    Err(err) => {
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(format!(
-                                "Error generating image description: {err}"
-                            )),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(format!(
-                                        "Error generating image description: {err}"
-                                    )),
-                                })
-                                .await;
-                        }
-
-                        Default::default()
-                    }
-                }
-            }
+        let config = self.agent.llm.clone();
+        let result = self
+            .client
+            .describe_image(WEB_DESIGNER_PROMPT, &image, &config)
+            .await;
 
-            #[cfg(feature = "xai")]
-            ClientType::Xai(xai_client) => {
-                let messages = vec![XaiMessage {
-                    role: "user".into(),
-                    content: "What is in this image?".to_string(),
-                }];
-
-                let rb = ChatCompletionsRequestBuilder::new(
-                    xai_client.clone(),
-                    "grok-beta".into(),
-                    messages,
-                )
-                .temperature(0.0)
-                .stream(false);
-
-                let req = rb.clone().build()?;
-                let resp = rb.create_chat_completion(req).await;
-
-                match resp {
-                    Ok(chat) => {
-                        let response_text = chat.choices[0].message.content.clone();
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(
-                                "Generated image description: ".to_string() + &response_text,
-                            ),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(
-                                        "Generated image description: ".to_string()
-                                            + &response_text,
-                                    ),
-                                })
-                                .await;
-                        }
-
-                        #[cfg(debug_assertions)]
-                        debug!(
-                            "[*] {:?}: Got XAI Output: {:?}",
-                            self.agent.position(),
-                            response_text
-                        );
-
-                        response_text
-                    }
-
-                    Err(err) => {
-                        let err_msg = format!("ERROR_MESSAGE: {err}");
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(err_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(err_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(err_msg));
-                    }
-                }
-            }
-
-            #[allow(unreachable_patterns)]
-            _ => {
-                return Err(anyhow!(
-                    "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                ));
-            }
+        let message = match &result {
+            Ok(response) => format!("Generated image description: {response}"),
+            Err(err) => format!("Error generating image description: {err}"),
         };
+        self.agent.add_communication(Communication {
+            role: Cow::Borrowed("assistant"),
+            content: Cow::Owned(message.clone()),
+        });
+
+        #[cfg(feature = "mem")]
+        {
+            let _ = self
+                .save_ltm(Communication {
+                    role: Cow::Borrowed("assistant"),
+                    content: Cow::Owned(message),
+                })
+                .await;
+        }
+
+        let response = result?;
+        debug!(
+            "[*] {:?}: Got Image Description: {:?}",
+            self.agent.position(),
+            response
+        );
 
         Ok(response)
     }
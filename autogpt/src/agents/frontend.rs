@@ -41,13 +41,15 @@
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
 #[cfg(feature = "cli")]
 use crate::common::utils::spinner;
 #[allow(unused_imports)]
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, GenerationOutput, Goal, Knowledge,
     OutputKind, Persona, Planner, Reflection, Route, Scope, Status, Task, TaskScheduler, Tool,
-    extract_array, strip_code_blocks,
+    strip_code_blocks,
 };
 use crate::prompts::frontend::{
     FIX_CODE_PROMPT, FRONTEND_CODE_PROMPT, IMPROVED_FRONTEND_CODE_PROMPT,
@@ -69,39 +71,14 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tracing::{debug, error, info, warn};
 
+use crate::traits::provider::{LlmProvider, TextStream};
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
-    crate::common::memory::save_long_term_memory,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
+    crate::common::memory::save_long_term_memory, crate::common::memory::with_recalled_memory,
 };
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
 
 use async_trait::async_trait;
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
 
 /// Struct representing a `FrontendGPT`, which manages frontend code generation and testing using Gemini API.
 #[derive(Debug, Clone, Default, Auto)]
@@ -253,17 +230,12 @@ impl FrontendGPT {
         tasks: &mut Task,
         output_type: OutputKind,
     ) -> Result<GenerationOutput> {
+        let request: String = format!("Task Description: {}", tasks.description);
         #[cfg(feature = "mem")]
-        {
-            self.agent.memory = self.get_ltm().await?;
-        }
-
-        let request: String = format!(
-            "{}\n\nTask Description: {}\nPrevious Conversation: {:?}",
-            prompt,
-            tasks.description,
-            self.agent.memory(),
-        );
+        let recalled = self.ltm_context(&request, self.agent.recall.k).await;
+        #[cfg(feature = "mem")]
+        let prompt = &with_recalled_memory(prompt, &recalled);
+        let messages = self.agent.conversation(prompt, &request);
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
@@ -280,13 +252,27 @@ impl FrontendGPT {
                 .await;
         }
 
-        #[allow(unused)]
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(&request).await?;
-        }
+        let (response_text, output) = match output_type {
+            OutputKind::Text => {
+                let text = self.generate_messages(&messages).await?;
+                let output = GenerationOutput::Text(strip_code_blocks(&text));
+                (text, output)
+            }
+            OutputKind::UrlList => {
+                let urls: Vec<Cow<'static, str>> = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&urls)?,
+                    GenerationOutput::UrlList(urls),
+                )
+            }
+            OutputKind::Scope => {
+                let scope: Scope = self.generate_structured(&messages).await?;
+                (
+                    serde_json::to_string(&scope)?,
+                    GenerationOutput::Scope(scope),
+                )
+            }
+        };
 
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
@@ -305,20 +291,11 @@ impl FrontendGPT {
 
         debug!("[*] {:?}: {:?}", self.agent.position(), self.agent);
 
-        match output_type {
-            OutputKind::Text => Ok(GenerationOutput::Text(strip_code_blocks(&response_text))),
-            OutputKind::UrlList => {
-                let urls: Vec<Cow<'static, str>> =
-                    serde_json::from_str(&extract_array(&response_text).unwrap_or_default())?;
-                tasks.urls = Some(urls.clone());
-                self.agent.update(Status::InUnitTesting);
-                Ok(GenerationOutput::UrlList(urls))
-            }
-            OutputKind::Scope => {
-                let scope: Scope = serde_json::from_str(&strip_code_blocks(&response_text))?;
-                Ok(GenerationOutput::Scope(scope))
-            }
+        if let GenerationOutput::UrlList(urls) = &output {
+            tasks.urls = Some(urls.clone());
+            self.agent.update(Status::InUnitTesting);
         }
+        Ok(output)
     }
 
     /// Asynchronously generates frontend code based on tasks.
@@ -669,21 +646,25 @@ impl FrontendGPT {
                 description: "Generate initial frontend code".into(),
                 priority: 1,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Improve code quality".into(),
                 priority: 2,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Run unit tests".into(),
                 priority: 3,
                 completed: false,
+                dependencies: Vec::new(),
             },
             Goal {
                 description: "Fix build/test bugs".into(),
                 priority: 4,
                 completed: false,
+                dependencies: Vec::new(),
             },
         ];
 
@@ -705,6 +686,7 @@ impl FrontendGPT {
             description: format!("Fallback task from context: {context}"),
             priority: 99,
             completed: false,
+            dependencies: Vec::new(),
         }
     }
     pub async fn act(
@@ -762,7 +744,7 @@ impl FrontendGPT {
             self.agent.objective()
         );
 
-        self.agent.memory_mut().push(Communication {
+        self.agent.add_communication(Communication {
             role: Cow::Borrowed("assistant"),
             content: summary.clone().into(),
         });
@@ -775,17 +757,20 @@ impl FrontendGPT {
                 content: summary.into(),
             });
 
-        if let Some(reflection) = self.agent.reflection() {
-            let feedback = (reflection.evaluation_fn)(&self.agent);
-            info!(
+        match self.agent.reflect(&mut self.client).await {
+            Ok(evaluation) => info!(
                 "{}",
                 format!(
                     "[*] {:?}: Self Reflection: {}",
                     self.agent.position(),
-                    feedback
+                    evaluation.summary
                 )
                 .blue()
-            );
+            ),
+            Err(err) => warn!(
+                "[*] {:?}: Self Reflection failed: {err}",
+                self.agent.position()
+            ),
         }
     }
     pub fn has_completed_objective(&self) -> bool {
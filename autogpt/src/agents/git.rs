@@ -1,4 +1,4 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use colored::*;
 use git2::{IndexAddOption, Repository, Signature};
 use std::borrow::Cow;
@@ -9,6 +9,8 @@ use tracing::{debug, error, info};
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, Knowledge, Persona, Planner, Reflection,
     Status, Task, TaskScheduler, Tool,
@@ -22,37 +24,11 @@ use tokio::sync::Mutex;
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
 };
 
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
+use crate::traits::provider::{LlmProvider, TextStream};
 
 /// Struct representing GitGPT, a thread-safe Git-aware task executor integrated with a GPT agent.
 #[allow(dead_code)]
@@ -5,6 +5,7 @@
 //! understands email contents and produces textual responses tailored to user requirements.
 
 use crate::agents::agent::AgentGPT;
+use crate::common::config::LlmConfig;
 use crate::common::utils::{ClientType, Communication, Status, Task};
 use crate::traits::agent::Agent;
 use crate::traits::functions::{AsyncFunctions, Functions};
@@ -18,33 +19,11 @@ use tracing::{debug, info};
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
 };
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
 
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    messages::{Content, Message as GemMessage},
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
+use crate::traits::provider::{LlmProvider, TextStream};
 
 use async_trait::async_trait;
 
@@ -210,218 +189,30 @@ impl MailerGPT {
                 .await;
         }
 
-        let gemini_response = match &mut self.client {
-            #[cfg(feature = "gem")]
-            ClientType::Gemini(gem_client) => {
-                let parameters = ChatBuilder::default()
-                    .messages(vec![GemMessage::User {
-                        content: Content::Text(format!(
-                            "User Request:{prompt}\n\nEmails:{emails:?}"
-                        )),
-                        name: None,
-                    }])
-                    .build()?;
-
-                let result = gem_client.chat().generate(parameters).await;
-
-                match result {
-                    Ok(response) => response,
-                    Err(err) => {
-                        let error_msg = format!("Failed to generate content from emails: {err}");
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("system"),
-                            content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-
-            #[cfg(feature = "oai")]
-            ClientType::OpenAI(oai_client) => {
-                let parameters = ChatCompletionParametersBuilder::default()
-                    .model(FlagshipModel::Gpt4O.to_string())
-                    .messages(vec![ChatMessage::User {
-                        content: ChatMessageContent::Text(format!(
-                            "User Request:{prompt}\n\nEmails:{emails:?}"
-                        )),
-                        name: None,
-                    }])
-                    .response_format(ChatCompletionResponseFormat::Text)
-                    .build()?;
-
-                let result = oai_client.chat().create(parameters).await;
-
-                match result {
-                    Ok(chat_response) => {
-                        let message = &chat_response.choices[0].message;
-
-                        match message {
-                            ChatMessage::Assistant {
-                                content: Some(chat_content),
-                                ..
-                            } => chat_content.to_string(),
-                            ChatMessage::User { content, .. } => content.to_string(),
-                            ChatMessage::System { content, .. } => content.to_string(),
-                            ChatMessage::Developer { content, .. } => content.to_string(),
-                            ChatMessage::Tool { content, .. } => content.clone(),
-                            _ => String::from(""),
-                        }
-                    }
-
-                    Err(err) => {
-                        let error_msg = format!("Failed to generate content from emails: {err}");
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("system"),
-                            content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-
-            #[cfg(feature = "cld")]
-            ClientType::Anthropic(client) => {
-                let body = CreateMessageParams::new(RequiredMessageParams {
-                    model: "claude-3-7-sonnet-latest".to_string(),
-                    messages: vec![AnthMessage::new_text(
-                        Role::User,
-                        format!("User Request:{prompt}\n\nEmails:{emails:?}"),
-                    )],
-                    max_tokens: 1024,
+        let gemini_response = match self
+            .client
+            .chat(&format!("User Request:{prompt}\n\nEmails:{emails:?}"))
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                let error_msg = format!("Failed to generate content from emails: {err}");
+                self.agent.add_communication(Communication {
+                    role: Cow::Borrowed("system"),
+                    content: Cow::Owned(error_msg.clone()),
                 });
 
-                match client.create_message(Some(&body)).await {
-                    Ok(chat_response) => chat_response
-                        .content
-                        .iter()
-                        .filter_map(|block| match block {
-                            ContentBlock::Text { text, .. } => Some(text),
-                            _ => None,
-                        })
-                        .cloned()
-                        .collect::<Vec<_>>()
-                        .join("\n"),
-
-                    Err(err) => {
-                        let error_msg =
-                            format!("Failed to generate content from Claude API: {err}");
-                        self.agent.add_communication(Communication {
+                #[cfg(feature = "mem")]
+                {
+                    let _ = self
+                        .save_ltm(Communication {
                             role: Cow::Borrowed("system"),
                             content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-            #[cfg(feature = "xai")]
-            ClientType::Xai(xai_client) => {
-                let messages = vec![XaiMessage {
-                    role: "user".into(),
-                    content: format!("User Request:{prompt}\n\nEmails:{emails:?}"),
-                }];
-
-                let rb = ChatCompletionsRequestBuilder::new(
-                    xai_client.clone(),
-                    "grok-beta".into(),
-                    messages,
-                )
-                .temperature(0.0)
-                .stream(false);
-
-                let req = rb.clone().build()?;
-                let resp = rb.create_chat_completion(req).await;
-
-                match resp {
-                    Ok(chat) => {
-                        let response_text = chat.choices[0].message.content.clone();
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(response_text.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(response_text.clone()),
-                                })
-                                .await;
-                        }
-
-                        #[cfg(debug_assertions)]
-                        debug!(
-                            "[*] {:?}: Got XAI Output: {:?}",
-                            self.agent.position(),
-                            response_text
-                        );
-
-                        response_text
-                    }
-
-                    Err(err) => {
-                        let err_msg = format!("Failed to generate content from emails: {err}");
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(err_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(err_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(err_msg));
-                    }
+                        })
+                        .await;
                 }
-            }
 
-            #[allow(unreachable_patterns)]
-            _ => {
-                return Err(anyhow!(
-                    "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                ));
+                return Err(anyhow!(error_msg));
             }
         };
 
@@ -452,6 +243,40 @@ impl MailerGPT {
 
         Ok(gemini_response)
     }
+
+    /// Generates text from the latest emails for the tasks, until the agent is completed.
+    async fn run_tasks(&mut self, tasks: &Task) -> Result<()> {
+        info!(
+            "{}",
+            format!("[*] {:?}: Executing task:", self.agent.position(),)
+                .bright_white()
+                .bold()
+        );
+        for task in tasks.clone().description.clone().split("- ") {
+            if !task.trim().is_empty() {
+                info!("{} {}", "•".bright_white().bold(), task.trim().cyan());
+            }
+        }
+        let mut _count = 0;
+        while self.agent.status() != &Status::Completed {
+            match self.agent.status() {
+                Status::Idle => {
+                    debug!("[*] {:?}: Idle", self.agent.position());
+
+                    let _generated_text =
+                        self.generate_text_from_emails(&tasks.description).await?;
+
+                    _count += 1;
+                    self.agent.update(Status::Completed);
+                }
+                _ => {
+                    self.agent.update(Status::Completed);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Functions for MailerGPT {
@@ -503,6 +328,8 @@ impl AsyncFunctions for MailerGPT {
     /// - Executes email processing and text generation tasks asynchronously based on the current status of the agent.
     /// - Handles task execution including email retrieval and text generation.
     /// - Manages retries and error handling during task execution.
+    /// - Refuses to start while the agent is blocked, paused or cancelled, and marks it as
+    ///   `Failed` when the run fails.
     ///
     async fn execute<'a>(
         &'a mut self,
@@ -511,41 +338,18 @@ impl AsyncFunctions for MailerGPT {
         _browse: bool,
         _max_tries: u64,
     ) -> Result<()> {
-        info!(
-            "{}",
-            format!("[*] {:?}: Executing task:", self.agent.position(),)
-                .bright_white()
-                .bold()
-        );
-        for task in tasks.clone().description.clone().split("- ") {
-            if !task.trim().is_empty() {
-                info!("{} {}", "•".bright_white().bold(), task.trim().cyan());
-            }
+        self.agent.start_run()?;
+        let result = self.run_tasks(tasks).await;
+        if let Err(err) = &result {
+            self.agent.fail(err);
         }
-        let mut _count = 0;
-        while self.agent.status() != &Status::Completed {
-            match self.agent.status() {
-                Status::Idle => {
-                    debug!("[*] {:?}: Idle", self.agent.position());
-
-                    let _generated_text =
-                        self.generate_text_from_emails(&tasks.description).await?;
-
-                    _count += 1;
-                    self.agent.update(Status::Completed);
-                }
-                _ => {
-                    self.agent.update(Status::Completed);
-                }
-            }
-        }
-
-        Ok(())
+        result
     }
 
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-    async fn generate(&mut self, _request: &str) -> Result<String> {
-        Ok("".to_string())
+    async fn generate(&mut self, request: &str) -> Result<String> {
+        let messages = [Communication::user(request.to_string())];
+        self.generate_messages_with(&messages, &LlmConfig::default())
+            .await
     }
 
     /// Saves a communication to long-term memory for the agent.
@@ -561,11 +365,12 @@ impl AsyncFunctions for MailerGPT {
     /// # Business Logic
     ///
     /// - This method uses the `save_long_term_memory` util function to save the communication into the agent's long-term memory.
-    /// - The communication is embedded and stored using the agent's unique ID as the namespace.
+    /// - The communication is embedded and stored in the agent's own namespace, `agent-<id>` as returned by `namespace`, and expires after the agent's `memory_ttl` if set. Memories shared with other agents are written with `save_shared_memory` instead.
     /// - It handles the embedding and metadata for the communication, ensuring it's stored correctly.
     #[cfg(feature = "mem")]
     async fn save_ltm(&mut self, communication: Communication) -> Result<()> {
-        save_long_term_memory(&mut self.client, self.agent.id.clone(), communication).await
+        save_long_term_memory(&mut self.agent, &mut self.client, communication).await?;
+        Ok(())
     }
 
     /// Retrieves all communications stored in the agent's long-term memory.
@@ -577,36 +382,76 @@ impl AsyncFunctions for MailerGPT {
     /// # Business Logic
     ///
     /// - This method fetches the stored communications for the agent by interacting with the `load_long_term_memory` function.
-    /// - The function will return a list of communications that are indexed by the agent's unique ID.
+    /// - The function will return the communications of the agent's own namespace, `agent-<id>`, without its shared memories.
     /// - It handles the retrieval of the stored metadata and content for each communication.
     #[cfg(feature = "mem")]
     async fn get_ltm(&self) -> Result<Vec<Communication>> {
-        load_long_term_memory(self.agent.id.clone()).await
+        load_long_term_memory(self.agent.ltm.as_ref(), self.agent.id.clone()).await
     }
 
-    /// Retrieves the concatenated context of all communications in the agent's long-term memory.
+    /// Retrieves the memories most relevant to a query from the agent's long-term memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to find memories about, such as the current request.
+    /// * `k` - The maximum number of memories to recall.
     ///
     /// # Returns
     ///
-    /// (`String`): A string containing the concatenated role and content of all communications stored in the agent's long-term memory.
+    /// (`String`): The recalled communications as `role: content` lines, the oldest first.
     ///
     /// # Business Logic
     ///
-    /// - This method calls the `long_term_memory_context` function to generate a string representation of the agent's entire long-term memory.
-    /// - The context string is composed of each communication's role and content, joined by new lines.
-    /// - It provides a quick overview of the agent's memory in a human-readable format.
+    /// - This method calls the `relevant_memory_context` util function, which embeds the query and searches the agent's namespace and its shared memories.
+    /// - The closest records are ranked by the agent's `recall` policy, which may favour recent memories and skip near duplicates.
+    /// - An empty string is returned if the memory cannot be read.
     #[cfg(feature = "mem")]
-    async fn ltm_context(&self) -> String {
-        long_term_memory_context(self.agent.id.clone()).await
+    async fn ltm_context(&mut self, query: &str, k: usize) -> String {
+        relevant_memory_context(&mut self.agent, &mut self.client, query, k).await
+    }
+
+    async fn generate_messages(&mut self, messages: &[Communication]) -> Result<String> {
+        self.generate_messages_with(messages, &LlmConfig::default())
+            .await
     }
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-    async fn imagen(&mut self, _request: &str) -> Result<Vec<u8>> {
-        // TODO: Impl
-        Ok(Default::default())
+
+    async fn generate_messages_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        self.agent
+            .generate_messages_with(&mut self.client, messages, config)
+            .await
+    }
+
+    async fn imagen(&mut self, request: &str) -> Result<Vec<u8>> {
+        let retry = self.agent.retry;
+        let mut attempt = 1;
+        loop {
+            match self.client.image(request).await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    attempt = retry.backoff("imagen", attempt, err).await?;
+                }
+            }
+        }
     }
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-    async fn stream(&mut self, _request: &str) -> Result<ReqResponse> {
-        // TODO: Impl
-        Ok(ReqResponse(None))
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        let config = self.agent.llm.clone();
+        let retry = self.agent.retry;
+        let mut attempt = 1;
+        loop {
+            match self.client.stream_with(request, &config).await {
+                Ok(deltas) => {
+                    let model = config.model_or(self.client.name());
+                    return Ok(self.agent.usage.meter(model, request, deltas));
+                }
+                Err(err) => {
+                    attempt = retry.backoff("stream", attempt, err).await?;
+                }
+            }
+        }
     }
 }
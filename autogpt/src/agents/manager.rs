@@ -12,51 +12,24 @@ use crate::agents::frontend::FrontendGPT;
 #[cfg(feature = "git")]
 use crate::agents::git::GitGPT;
 use crate::agents::types::AgentType;
+use crate::common::config::LlmConfig;
+use crate::common::usage::UsageReport;
 use crate::common::utils::strip_code_blocks;
 use crate::common::utils::{ClientType, Communication, Task};
 use crate::prompts::manager::{FRAMEWORK_MANAGER_PROMPT, LANGUAGE_MANAGER_PROMPT, MANAGER_PROMPT};
 use crate::traits::agent::Agent;
 use crate::traits::functions::{AsyncFunctions, Functions};
+use crate::traits::provider::LlmProvider;
 use anyhow::{Result, anyhow};
 use colored::*;
-#[cfg(feature = "gem")]
-use gems::Client;
 use std::borrow::Cow;
-use std::env::var;
 use tracing::{debug, info};
 
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
 };
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
 
 /// Struct representing a ManagerGPT, responsible for managing different types of GPT agents.
 #[derive(Debug)]
@@ -135,6 +108,51 @@ impl ManagerGPT {
         }
     }
 
+    /// Replaces the client used to reach the model provider.
+    ///
+    /// The client is also handed to the default agents spawned by the manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to use for every subsequent model call.
+    ///
+    /// # Returns
+    ///
+    /// (`ManagerGPT`): The manager using the given client.
+    pub fn with_client(mut self, client: ClientType) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Replaces the model and sampling settings of the manager's own requests.
+    ///
+    /// The agents spawned by the manager keep the settings configured for their position.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings to use for every subsequent model call.
+    ///
+    /// # Returns
+    ///
+    /// (`ManagerGPT`): The manager using the given settings.
+    pub fn with_config(mut self, config: LlmConfig) -> Self {
+        self.agent.llm = config;
+        self
+    }
+
+    /// Returns the tokens used by the manager and the agents it spawned.
+    ///
+    /// # Returns
+    ///
+    /// (`UsageReport`): The usage of every model call made so far, per model.
+    pub fn usage(&self) -> UsageReport {
+        self.agents
+            .iter()
+            .fold(self.agent.usage.report(), |report, agent| {
+                report.merge(&agent.get_agent().usage.report())
+            })
+    }
+
     /// Adds an agent to the manager.
     ///
     /// # Arguments
@@ -155,7 +173,8 @@ impl ManagerGPT {
                 "Creates innovative website designs and user experiences",
                 "ArchitectGPT",
             )
-            .await,
+            .await
+            .with_client(self.client.clone()),
         ));
         #[cfg(feature = "img")]
         self.add_agent(AgentType::Designer(
@@ -163,7 +182,8 @@ impl ManagerGPT {
                 "Creates innovative website designs and user experiences",
                 "DesignerGPT",
             )
-            .await,
+            .await
+            .with_client(self.client.clone()),
         ));
         self.add_agent(AgentType::Backend(
             BackendGPT::new(
@@ -171,7 +191,8 @@ impl ManagerGPT {
                 "BackendGPT",
                 self.language,
             )
-            .await,
+            .await
+            .with_client(self.client.clone()),
         ));
         self.add_agent(AgentType::Frontend(
             FrontendGPT::new(
@@ -179,7 +200,8 @@ impl ManagerGPT {
                 "FrontendGPT",
                 self.language,
             )
-            .await,
+            .await
+            .with_client(self.client.clone()),
         ));
         #[cfg(feature = "git")]
         self.add_agent(AgentType::Git(
@@ -187,234 +209,47 @@ impl ManagerGPT {
                 "Handles git operations like staging and committing code",
                 "GitGPT",
             )
-            .await,
+            .await
+            .with_client(self.client.clone()),
         ));
     }
 
-    /// Spawns default agents if the collection is empty.
+    /// Sends a prompt to the model and returns its reply without code fences.
     ///
     /// # Business Logic
     ///
-    /// - Adds default agents to the collection if it is empty.
+    /// - Sends the prompt through `AgentGPT::generate_messages_with`, like the `generate` of the other agents, so it is budgeted, cached, retried and metered the same way.
+    /// - Logs a failure to the manager's memory.
     ///
     pub async fn execute_prompt(&mut self, prompt: String) -> Result<String, anyhow::Error> {
-        let provider = var("AI_PROVIDER").unwrap_or_else(|_| "gemini".to_string());
-        let response = match &mut self.client {
-            #[cfg(feature = "gem")]
-            ClientType::Gemini(gem_client) if provider == "gemini" => {
-                let parameters = ChatBuilder::default()
-                    .messages(vec![Message::User {
-                        content: Content::Text(prompt),
-                        name: None,
-                    }])
-                    .build()?;
-                let result = gem_client.chat().generate(parameters).await;
-
-                match result {
-                    Ok(response) => strip_code_blocks(&response),
-                    Err(_err) => {
-                        let error_msg = "Failed to generate content via Gemini API.".to_string();
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("system"),
-                            content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-
-            #[cfg(feature = "oai")]
-            ClientType::OpenAI(oai_client) if provider == "openai" => {
-                use openai_dive::v1::resources::chat::*;
-                use openai_dive::v1::resources::model::*;
-
-                let parameters = ChatCompletionParametersBuilder::default()
-                    .model(FlagshipModel::Gpt4O.to_string())
-                    .messages(vec![ChatMessage::User {
-                        content: ChatMessageContent::Text(prompt.clone()),
-                        name: None,
-                    }])
-                    .response_format(ChatCompletionResponseFormat::Text)
-                    .build()?;
-
-                let result = oai_client.chat().create(parameters).await;
-
-                match result {
-                    Ok(chat_response) => {
-                        let message = &chat_response.choices[0].message;
-
-                        let response_text = match message {
-                            ChatMessage::Assistant {
-                                content: Some(chat_content),
-                                ..
-                            } => chat_content.to_string(),
-                            ChatMessage::User { content, .. } => content.to_string(),
-                            ChatMessage::System { content, .. } => content.to_string(),
-                            ChatMessage::Developer { content, .. } => content.to_string(),
-                            ChatMessage::Tool { content, .. } => content.clone(),
-                            _ => String::from(""),
-                        };
-
-                        strip_code_blocks(&response_text)
-                    }
-
-                    Err(_err) => {
-                        let error_msg = "Failed to generate content via OpenAI API.".to_string();
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("system"),
-                            content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-
-            #[cfg(feature = "cld")]
-            ClientType::Anthropic(client) if provider == "claude" => {
-                let body = CreateMessageParams::new(RequiredMessageParams {
-                    model: "claude-3-7-sonnet-latest".to_string(),
-                    messages: vec![AnthMessage::new_text(Role::User, prompt.clone())],
-                    max_tokens: 1024,
+        let messages = [Communication::user(prompt)];
+        let response = self
+            .agent
+            .generate_messages_with(&mut self.client, &messages, &LlmConfig::default())
+            .await;
+
+        match response {
+            Ok(response) => Ok(strip_code_blocks(&response)),
+            Err(err) => {
+                let error_msg = format!("Failed to generate content: {err}");
+                self.agent.add_communication(Communication {
+                    role: Cow::Borrowed("system"),
+                    content: Cow::Owned(error_msg.clone()),
                 });
 
-                match client.create_message(Some(&body)).await {
-                    Ok(chat_response) => {
-                        let response_text = chat_response
-                            .content
-                            .iter()
-                            .filter_map(|block| match block {
-                                ContentBlock::Text { text, .. } => Some(text),
-                                _ => None,
-                            })
-                            .cloned()
-                            .collect::<Vec<_>>()
-                            .join("\n");
-
-                        strip_code_blocks(&response_text)
-                    }
-
-                    Err(_) => {
-                        let error_msg = "Failed to generate content via Claude API.".to_string();
-                        self.agent.add_communication(Communication {
+                #[cfg(feature = "mem")]
+                {
+                    let _ = self
+                        .save_ltm(Communication {
                             role: Cow::Borrowed("system"),
                             content: Cow::Owned(error_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("system"),
-                                    content: Cow::Owned(error_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(error_msg));
-                    }
-                }
-            }
-            #[cfg(feature = "xai")]
-            ClientType::Xai(xai_client) => {
-                let messages = vec![XaiMessage {
-                    role: "user".into(),
-                    content: prompt.clone(),
-                }];
-
-                let rb = ChatCompletionsRequestBuilder::new(
-                    xai_client.clone(),
-                    "grok-beta".into(),
-                    messages,
-                )
-                .temperature(0.0)
-                .stream(false);
-
-                let req = rb.clone().build()?;
-                let resp = rb.create_chat_completion(req).await;
-
-                match resp {
-                    Ok(chat) => {
-                        let response_text = chat.choices[0].message.content.clone();
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(response_text.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(response_text.clone()),
-                                })
-                                .await;
-                        }
-
-                        #[cfg(debug_assertions)]
-                        debug!(
-                            "[*] {:?}: Got XAI Output: {:?}",
-                            self.agent.position(),
-                            response_text
-                        );
-
-                        strip_code_blocks(&response_text)
-                    }
-
-                    Err(err) => {
-                        let err_msg = format!("Failed to generate content via XAI API: {err}");
-
-                        self.agent.add_communication(Communication {
-                            role: Cow::Borrowed("assistant"),
-                            content: Cow::Owned(err_msg.clone()),
-                        });
-
-                        #[cfg(feature = "mem")]
-                        {
-                            let _ = self
-                                .save_ltm(Communication {
-                                    role: Cow::Borrowed("assistant"),
-                                    content: Cow::Owned(err_msg.clone()),
-                                })
-                                .await;
-                        }
-
-                        return Err(anyhow!(err_msg));
-                    }
+                        })
+                        .await;
                 }
-            }
 
-            #[allow(unreachable_patterns)]
-            _ => {
-                return Err(anyhow!(
-                    "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                ));
+                Err(anyhow!(error_msg))
             }
-        };
-
-        Ok(response)
+        }
     }
 
     /// Asynchronously executes the tasks described by the user request.
@@ -437,7 +272,9 @@ impl ManagerGPT {
     /// - Executes tasks described by the user request using the collection of agents managed by the manager.
     /// - Logs user request, system decisions, and assistant responses.
     /// - Manages retries and error handling during task execution.
+    /// - Refuses to start while the manager is blocked, paused or cancelled.
     pub async fn execute(&mut self, execute: bool, browse: bool, max_tries: u64) -> Result<()> {
+        self.agent.start_run()?;
         self.agent.add_communication(Communication {
             role: Cow::Borrowed("user"),
             content: Cow::Owned(format!(
@@ -625,11 +462,12 @@ impl ManagerGPT {
     /// # Business Logic
     ///
     /// - This method uses the `save_long_term_memory` util function to save the communication into the agent's long-term memory.
-    /// - The communication is embedded and stored using the agent's unique ID as the namespace.
+    /// - The communication is embedded and stored in the agent's own namespace, `agent-<id>` as returned by `namespace`, and expires after the agent's `memory_ttl` if set. Memories shared with other agents are written with `save_shared_memory` instead.
     /// - It handles the embedding and metadata for the communication, ensuring it's stored correctly.
     #[cfg(feature = "mem")]
     async fn save_ltm(&mut self, communication: Communication) -> Result<()> {
-        save_long_term_memory(&mut self.client, self.agent.id.clone(), communication).await
+        save_long_term_memory(&mut self.agent, &mut self.client, communication).await?;
+        Ok(())
     }
 
     /// Retrieves all communications stored in the agent's long-term memory.
@@ -641,26 +479,31 @@ impl ManagerGPT {
     /// # Business Logic
     ///
     /// - This method fetches the stored communications for the agent by interacting with the `load_long_term_memory` function.
-    /// - The function will return a list of communications that are indexed by the agent's unique ID.
+    /// - The function will return the communications of the agent's own namespace, `agent-<id>`, without its shared memories.
     /// - It handles the retrieval of the stored metadata and content for each communication.
     #[cfg(feature = "mem")]
     async fn get_ltm(&self) -> Result<Vec<Communication>> {
-        load_long_term_memory(self.agent.id.clone()).await
+        load_long_term_memory(self.agent.ltm.as_ref(), self.agent.id.clone()).await
     }
 
-    /// Retrieves the concatenated context of all communications in the agent's long-term memory.
+    /// Retrieves the memories most relevant to a query from the agent's long-term memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to find memories about, such as the current request.
+    /// * `k` - The maximum number of memories to recall.
     ///
     /// # Returns
     ///
-    /// (`String`): A string containing the concatenated role and content of all communications stored in the agent's long-term memory.
+    /// (`String`): The recalled communications as `role: content` lines, the oldest first.
     ///
     /// # Business Logic
     ///
-    /// - This method calls the `long_term_memory_context` function to generate a string representation of the agent's entire long-term memory.
-    /// - The context string is composed of each communication's role and content, joined by new lines.
-    /// - It provides a quick overview of the agent's memory in a human-readable format.
+    /// - This method calls the `relevant_memory_context` util function, which embeds the query and searches the agent's namespace and its shared memories.
+    /// - The closest records are ranked by the agent's `recall` policy, which may favour recent memories and skip near duplicates.
+    /// - An empty string is returned if the memory cannot be read.
     #[cfg(feature = "mem")]
-    async fn ltm_context(&self) -> String {
-        long_term_memory_context(self.agent.id.clone()).await
+    async fn ltm_context(&mut self, query: &str, k: usize) -> String {
+        relevant_memory_context(&mut self.agent, &mut self.client, query, k).await
     }
 }
@@ -66,6 +66,8 @@
 use crate::agents::agent::AgentGPT;
 #[cfg(feature = "net")]
 use crate::collaboration::Collaborator;
+use crate::common::cache::ResponseCache;
+use crate::common::config::LlmConfig;
 #[allow(unused_imports)]
 use crate::common::utils::{
     Capability, ClientType, Communication, ContextManager, Goal, Knowledge, Persona, Planner,
@@ -74,7 +76,7 @@ use crate::common::utils::{
 use crate::prompts::optimizer::{MODULARIZE_PROMPT, SPLIT_PROMPT};
 use crate::traits::agent::Agent;
 use crate::traits::functions::{AsyncFunctions, Executor, Functions};
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use auto_derive::Auto;
 use colored::*;
 use std::borrow::Cow;
@@ -85,38 +87,12 @@ use std::path::Path;
 use tokio::fs;
 use tracing::{debug, error, info};
 
+use crate::traits::provider::{LlmProvider, TextStream};
 #[cfg(feature = "mem")]
 use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory, crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
 };
-#[cfg(feature = "oai")]
-use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
-
-#[cfg(feature = "cld")]
-use anthropic_ai_sdk::types::message::{
-    ContentBlock, CreateMessageParams, Message as AnthMessage, MessageClient,
-    RequiredMessageParams, Role,
-};
-
-#[cfg(feature = "gem")]
-use gems::{
-    chat::ChatBuilder,
-    imagen::ImageGenBuilder,
-    messages::{Content, Message},
-    models::Model,
-    stream::StreamBuilder,
-    traits::CTrait,
-};
-
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-use crate::traits::functions::ReqResponse;
-
-#[cfg(feature = "xai")]
-use x_ai::{
-    chat_compl::{ChatCompletionsRequestBuilder, Message as XaiMessage},
-    traits::ChatCompletionsFetcher,
-};
 
 use async_trait::async_trait;
 
@@ -254,13 +230,7 @@ impl OptimizerGPT {
     /// - Prepares the returned content for further downstream processing (e.g., file writing or parsing).
     #[allow(unused)]
     pub async fn generate_and_track(&mut self, request: &str) -> Result<String> {
-        let mut response_text = String::new();
-
-        #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-        {
-            response_text = self.generate(request).await?;
-        }
-        Ok(response_text)
+        self.generate(request).await
     }
 }
 
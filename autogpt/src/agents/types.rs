@@ -1,5 +1,6 @@
 use crate::agents::agent::AgentGPT;
 use crate::agents::architect::ArchitectGPT;
+use crate::agents::autonomous::AutonomousGPT;
 use crate::agents::backend::BackendGPT;
 #[cfg(feature = "img")]
 use crate::agents::designer::DesignerGPT;
@@ -29,6 +30,8 @@ pub enum AgentType {
     Git(GitGPT),
     /// Optimizer GPT agent.
     Optimize(OptimizerGPT),
+    /// Autonomous GPT agent.
+    Autonomous(AutonomousGPT),
 }
 
 impl Default for AgentType {
@@ -74,6 +77,7 @@ impl AgentType {
             #[cfg(feature = "git")]
             AgentType::Git(agent) => agent.execute(tasks, execute, browse, max_tries).await,
             AgentType::Optimize(agent) => agent.execute(tasks, execute, browse, max_tries).await,
+            AgentType::Autonomous(agent) => agent.execute(tasks, execute, browse, max_tries).await,
         }
     }
 
@@ -92,6 +96,7 @@ impl AgentType {
             AgentType::Backend(agent) => agent.get_agent().position().to_string(),
             AgentType::Frontend(agent) => agent.get_agent().position().to_string(),
             AgentType::Optimize(agent) => agent.get_agent().position().to_string(),
+            AgentType::Autonomous(agent) => agent.get_agent().position().to_string(),
             #[cfg(feature = "img")]
             AgentType::Designer(agent) => agent.get_agent().position().to_string(),
             #[cfg(feature = "git")]
@@ -110,6 +115,7 @@ impl AgentType {
             AgentType::Backend(agent) => agent.get_agent(),
             AgentType::Frontend(agent) => agent.get_agent(),
             AgentType::Optimize(agent) => agent.get_agent(),
+            AgentType::Autonomous(agent) => agent.get_agent(),
             #[cfg(feature = "img")]
             AgentType::Designer(agent) => agent.get_agent(),
             #[cfg(feature = "git")]
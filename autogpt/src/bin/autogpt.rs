@@ -22,7 +22,6 @@ use anyhow::Result;
 async fn main() -> Result<()> {
     #[cfg(feature = "cli")]
     {
-        use anyhow::anyhow;
         use autogpt::agents::architect::ArchitectGPT;
         use autogpt::agents::backend::BackendGPT;
         use autogpt::agents::designer::DesignerGPT;
@@ -39,20 +38,16 @@ async fn main() -> Result<()> {
         use autogpt::common::utils::ask_to_run_command;
         use autogpt::common::utils::fetch_latest_version;
         use autogpt::common::utils::is_outdated;
+        use autogpt::common::utils::log_usage;
         use autogpt::common::utils::prompt_for_update;
         use autogpt::common::utils::setup_logging;
-        use autogpt::prelude::CTrait;
         use autogpt::prelude::ClientType;
+        use autogpt::prelude::LlmProvider;
         use autogpt::traits::functions::AsyncFunctions;
         use autogpt::traits::functions::Functions;
         use clap::Parser;
         use colored::*;
         use futures_util::StreamExt;
-        use gems::messages::Content;
-        use gems::messages::Message as GemMessage;
-        use gems::models::Model;
-        use gems::stream::StreamBuilder;
-        use gems::utils::extract_text_from_partial_json;
         use iac_rs::message::Message;
         use iac_rs::prelude::*;
         use std::env;
@@ -198,71 +193,17 @@ async fn main() -> Result<()> {
         if let Some(prompt) = args.prompt {
             let skin = MadSkin::default();
             let mut client = ClientType::from_env();
-            match &mut client {
-                #[cfg(feature = "gem")]
-                ClientType::Gemini(gem_client) => {
-                    let parameters = StreamBuilder::default()
-                        .model(Model::Flash20)
-                        .input(GemMessage::User {
-                            content: Content::Text(prompt),
-                            name: None,
-                        })
-                        .build()?;
-
-                    let response = gem_client.stream().generate(parameters).await?;
-                    let mut stream = response.bytes_stream();
-
-                    let delay = 1;
-                    let mut message: String = Default::default();
-                    while let Some(mut chunk) = stream.next().await {
-                        if let Ok(parsed_json) = std::str::from_utf8(chunk.as_mut().unwrap()) {
-                            if let Some(text_value) = extract_text_from_partial_json(parsed_json) {
-                                let lines: Vec<&str> = text_value
-                                    .split("\\n")
-                                    .flat_map(|s| s.split('\n'))
-                                    .collect();
-                                for line in lines {
-                                    message.push_str(&line.replace('\\', ""));
-                                    if !line.is_empty() {
-                                        type_with_cursor_effect(
-                                            &line.replace('\\', ""),
-                                            delay,
-                                            &skin,
-                                        );
-                                    } else {
-                                        println!("\n");
-                                    }
-                                }
-                            }
-                        } else {
-                            error!("Failed to parse chunk: {:?}", chunk.as_ref().unwrap());
-                        }
-                    }
-                }
-
-                #[cfg(feature = "oai")]
-                ClientType::OpenAI(_oai_client) => {
-                    // TODO: Implement streaming for OpenAI
-                    todo!("Implement me plz.");
-                }
+            let mut stream = client.stream(&prompt).await?;
 
-                #[cfg(feature = "cld")]
-                ClientType::Anthropic(_client) => {
-                    // TODO: Implement streaming for Anthropic
-                    todo!("Implement me plz.");
-                }
-
-                #[cfg(feature = "xai")]
-                ClientType::Xai(_xai_client) => {
-                    // TODO: Implement streaming for Xai
-                    todo!("Implement me plz.");
-                }
-
-                #[allow(unreachable_patterns)]
-                _ => {
-                    return Err(anyhow!(
-                        "No valid AI client configured. Enable `gem`, `oai`, `cld`, or `xai` feature."
-                    ));
+            let delay = 1;
+            while let Some(delta) = stream.next().await {
+                for (index, line) in delta?.split('\n').enumerate() {
+                    if index > 0 {
+                        println!();
+                    }
+                    if !line.is_empty() {
+                        type_with_cursor_effect(line, delay, &skin);
+                    }
                 }
             }
             println!();
@@ -334,6 +275,7 @@ async fn main() -> Result<()> {
 
                             let _ = manager.execute(true, true, 3).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(&manager.usage());
                         } else {
                             warn!("{}", "[*] \"AGI\": 🤔 You've entered an empty project description? What exactly does that entail?"
                                                 .bright_yellow()
@@ -390,6 +332,13 @@ async fn main() -> Result<()> {
                             );
                             let _ = git_agent.execute(&mut tasks, true, false, 1).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(
+                                &architect_agent
+                                    .get_agent()
+                                    .usage
+                                    .take()
+                                    .merge(&git_agent.get_agent().usage.take()),
+                            );
 
                             if let Err(e) = ask_to_run_command(
                                 architect_agent.get_agent().clone(),
@@ -475,6 +424,13 @@ async fn main() -> Result<()> {
                             );
                             let _ = git_agent.execute(&mut tasks, true, false, 1).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(
+                                &frontend_agent
+                                    .get_agent()
+                                    .usage
+                                    .take()
+                                    .merge(&git_agent.get_agent().usage.take()),
+                            );
 
                             if let Err(e) = ask_to_run_command(
                                 frontend_agent.get_agent().clone(),
@@ -560,6 +516,13 @@ async fn main() -> Result<()> {
                             );
                             let _ = git_agent.execute(&mut tasks, true, false, 1).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(
+                                &backend_gpt
+                                    .get_agent()
+                                    .usage
+                                    .take()
+                                    .merge(&git_agent.get_agent().usage.take()),
+                            );
 
                             if let Err(e) = ask_to_run_command(
                                 backend_gpt.get_agent().clone(),
@@ -638,6 +601,13 @@ async fn main() -> Result<()> {
 
                             git_agent.execute(&mut tasks, true, false, 1).await?;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(
+                                &designer_agent
+                                    .get_agent()
+                                    .usage
+                                    .take()
+                                    .merge(&git_agent.get_agent().usage.take()),
+                            );
                         } else {
                             warn!("{}", "[*] \"AGI\": 🤔 You've entered an empty project description? What exactly does that entail?"
                                                     .bright_yellow()
@@ -684,6 +654,7 @@ async fn main() -> Result<()> {
 
                             let _ = mailer_agent.execute(&mut tasks, true, false, 3).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(&mailer_agent.get_agent().usage.take());
                         } else {
                             warn!("{}", "[*] \"AGI\": 🤔 You've entered an empty project description? What exactly does that entail?"
                                                     .bright_yellow()
@@ -730,6 +701,7 @@ async fn main() -> Result<()> {
 
                             let _result = git_agent.execute(&mut tasks, true, false, 1).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(&git_agent.get_agent().usage.take());
                         } else {
                             warn!("{}", "[*] \"AGI\": 🤔 You've entered an empty project description? What exactly does that entail?"
                                                     .bright_yellow()
@@ -774,6 +746,7 @@ async fn main() -> Result<()> {
 
                             let _result = optimizer_agent.execute(&mut tasks, true, false, 1).await;
                             info!("{}", "[*] \"AGI\": ✅ Done!".green().bold());
+                            log_usage(&optimizer_agent.get_agent().usage.take());
                         } else {
                             warn!("{}", "[*] \"AGI\": 🤔 You've entered an empty project description? What exactly does that entail?"
                                                     .bright_yellow()
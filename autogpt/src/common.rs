@@ -5,8 +5,33 @@
 //! ## Sub-modules
 //!
 //! - `utils`: Contains definitions and implementations of various utility functions and helpers that can be used throughout the project.
+//! - `tokens`: Contains the token estimator and the budget that keeps prompts inside a model's context window.
+//! - `compaction`: Contains the policy summarizing the older part of an agent's memory once it grows too long.
+//! - `cache`: Contains the opt-in on-disk cache of model replies and embeddings.
+//! - `config`: Contains the model and sampling configuration sent with every request.
+//! - `lifecycle`: Contains the status transitions of agents and the events they publish to their subscribers.
+//! - `memory`: Contains the records of long-term memory, the stores keeping them and the helpers saving and loading an agent's memory.
+//! - `planning`: Contains the goal plans proposed by the model and the dependency graph they form.
+//! - `scheduler`: Contains the runtime firing scheduled tasks on their agent, once or on a recurrence.
+//! - `reflection`: Contains the evaluations of reflections and the critic asking the model to review an agent's work.
+//! - `retry`: Contains the retry policy and error classification applied to every model call.
+//! - `structured`: Contains the schema, parsing and repair helpers behind structured output.
+//! - `tools`: Contains the types used to offer an agent's tools to the model through native function calling.
+//! - `usage`: Contains the token usage records and the price table used to compute their cost.
 //!
 
+pub mod cache;
+pub mod compaction;
+pub mod config;
+pub mod lifecycle;
+pub mod planning;
+pub mod reflection;
+pub mod retry;
+pub mod scheduler;
+pub mod structured;
+pub mod tokens;
+pub mod tools;
+pub mod usage;
 pub mod utils;
 
 #[cfg(feature = "mem")]
@@ -0,0 +1,287 @@
+//! # Response cache.
+//!
+//! An opt-in, on-disk cache of model replies and embeddings, so that re-running the same task
+//! does not pay for identical requests again. Entries are keyed by the kind of request, the
+//! provider, the `LlmConfig` and the messages sent, and each one is stored as a JSON file
+//! named after the hash of its key. The full key is kept in the file and checked on every
+//! read, so a hash collision is a miss rather than a wrong reply.
+//!
+//! When the cache is enabled, agents check it before each `generate` and `generate_messages`
+//! call, and long-term memory checks it before embedding content. Replies served from the
+//! cache are not counted in the agent's token usage. Streams are never cached.
+//!
+//! The cache is configured through the following environment variables:
+//!
+//! - `AUTOGPT_CACHE`: Set to `1` or `true` to enable the cache.
+//! - `AUTOGPT_CACHE_DIR`: Directory of the cache, defaults to `.cache/llm` under
+//!   `AUTOGPT_WORKSPACE`. Setting it also enables the cache.
+//! - `AUTOGPT_CACHE_TTL`: Seconds after which an entry expires, unset by default.
+//! - `AUTOGPT_CACHE_MAX_BYTES`: Upper bound on the total size of the entries. The oldest
+//!   entries are evicted first, unset by default.
+//! - `AUTOGPT_CACHE_BYPASS`: Set to `1` or `true` to ignore cached entries while still
+//!   storing fresh replies, to refresh the cache.
+//!
+//! The key does not include the default model a provider reads from its own environment
+//! variables, such as `GEMINI_MODEL`, so clear the cache or bypass it after changing one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::cache::ResponseCache;
+//! use autogpt::common::config::LlmConfig;
+//! use autogpt::common::utils::Communication;
+//!
+//! let dir = std::env::temp_dir().join("autogpt-cache-example");
+//! let cache = ResponseCache::new(&dir);
+//! let entry = cache.entry(
+//!     "generate",
+//!     "gemini",
+//!     &LlmConfig::new(),
+//!     &[Communication::user("Say hello")],
+//! );
+//!
+//! entry.store(&"Hello!");
+//! assert_eq!(entry.load::<String>().as_deref(), Some("Hello!"));
+//! # cache.clear().unwrap();
+//! ```
+
+use crate::common::config::LlmConfig;
+use crate::common::utils::Communication;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::env::var;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// A cached value along with the key it was stored under.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    key: String,
+    value: Value,
+}
+
+/// On-disk cache of model replies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseCache {
+    /// Directory holding the entries.
+    pub dir: PathBuf,
+    /// How long an entry stays valid, forever if unset.
+    pub ttl: Option<Duration>,
+    /// Upper bound on the total size of the entries, unbounded if unset.
+    pub max_bytes: Option<u64>,
+    /// Whether to ignore cached entries, while still storing fresh ones.
+    pub bypass: bool,
+}
+
+impl ResponseCache {
+    /// Creates a cache in `dir` whose entries never expire.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: None,
+            max_bytes: None,
+            bypass: false,
+        }
+    }
+
+    /// Builds a cache from the `AUTOGPT_CACHE_*` environment variables, or returns `None` if
+    /// the cache is not enabled.
+    pub fn from_env() -> Option<Self> {
+        let flag = |name: &str| {
+            var(name).is_ok_and(|value| matches!(value.trim(), "1" | "true" | "yes" | "on"))
+        };
+        let number = |name: &str| var(name).ok().and_then(|value| value.trim().parse().ok());
+
+        let dir = match var("AUTOGPT_CACHE_DIR") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ if flag("AUTOGPT_CACHE") => {
+                let workspace =
+                    var("AUTOGPT_WORKSPACE").unwrap_or_else(|_| "workspace/".to_string());
+                Path::new(&workspace).join(".cache").join("llm")
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            dir,
+            ttl: number("AUTOGPT_CACHE_TTL").map(Duration::from_secs),
+            max_bytes: number("AUTOGPT_CACHE_MAX_BYTES"),
+            bypass: flag("AUTOGPT_CACHE_BYPASS"),
+        })
+    }
+
+    /// Sets how long an entry stays valid.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the upper bound on the total size of the entries.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets whether to ignore cached entries.
+    pub fn bypass(mut self, bypass: bool) -> Self {
+        self.bypass = bypass;
+        self
+    }
+
+    /// Returns the entry of a request.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of request, such as `"generate"` or `"embed"`.
+    /// * `provider` - Name of the provider, as returned by `ClientType::name`.
+    /// * `config` - The model and sampling settings sent with the request.
+    /// * `messages` - The messages sent with the request.
+    pub fn entry(
+        &self,
+        kind: &str,
+        provider: &str,
+        config: &LlmConfig,
+        messages: &[Communication],
+    ) -> CacheEntry {
+        let key = json!({
+            "kind": kind,
+            "provider": provider,
+            "config": config,
+            "messages": messages,
+        })
+        .to_string();
+
+        CacheEntry {
+            cache: self.clone(),
+            key,
+        }
+    }
+
+    /// Returns the value stored under `key`, unless it is missing, expired or bypassed.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if self.bypass {
+            return None;
+        }
+
+        let path = self.path(key);
+        let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+        if let Some(ttl) = self.ttl
+            && modified.elapsed().unwrap_or_default() > ttl
+        {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+
+        let file: CacheFile = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+        if file.key != key {
+            return None;
+        }
+        serde_json::from_value(file.value).ok()
+    }
+
+    /// Stores `value` under `key`, then evicts the oldest entries beyond the size limit.
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        if !self.dir.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            // Keep the cache out of the repositories agents commit to.
+            std::fs::write(self.dir.join(".gitignore"), "*\n")?;
+        }
+
+        let file = CacheFile {
+            key: key.to_string(),
+            value: serde_json::to_value(value)?,
+        };
+        std::fs::write(self.path(key), serde_json::to_string(&file)?)?;
+
+        self.evict()
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> Result<()> {
+        for (path, _, _) in self.entries()? {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", fnv1a(key.as_bytes())))
+    }
+
+    /// Lists the entry files with their size and modification time.
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let metadata = std::fs::metadata(&path)?;
+                entries.push((path, metadata.len(), metadata.modified()?));
+            }
+        }
+        Ok(entries)
+    }
+
+    fn evict(&self) -> Result<()> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            std::fs::remove_file(&path)?;
+            total -= size;
+            debug!("Evicted cache entry {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// A request's slot in a `ResponseCache`.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    cache: ResponseCache,
+    key: String,
+}
+
+impl CacheEntry {
+    /// Returns the cached value, unless it is missing, expired or bypassed.
+    pub fn load<T: DeserializeOwned>(&self) -> Option<T> {
+        let value = self.cache.get(&self.key);
+        if value.is_some() {
+            debug!("Serving the reply from {}", self.cache.dir.display());
+        }
+        value
+    }
+
+    /// Stores `value`. A failure to write is logged rather than returned, since the reply is
+    /// still valid.
+    pub fn store<T: Serialize>(&self, value: &T) {
+        if let Err(err) = self.cache.put(&self.key, value) {
+            warn!("Failed to cache the reply: {err}");
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, stable across builds and platforms.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
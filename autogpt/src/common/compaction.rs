@@ -0,0 +1,193 @@
+//! # Memory compaction.
+//!
+//! Every request and reply of an agent is appended to its `memory`, which would otherwise grow
+//! without bound. A `CompactionPolicy` keeps it in check: once the memory takes up more than
+//! `max_tokens`, the communications older than the last `keep_recent` are summarized by the
+//! model into a single system message at the start of the memory. That summary is rolling:
+//! the next compaction folds it together with the newly compacted communications.
+//!
+//! With the `mem` feature, the compacted communications can also be archived to the agent's
+//! long-term memory, so that they can still be recalled by relevance later on.
+//!
+//! Agents deriving `Auto` compact their memory before each model call made through
+//! `generate`, with `AgentGPT::compact_memory`, which retries the summary according to the
+//! agent's `retry` policy and records it in its `usage`. Compaction is off unless a policy is set on the agent, or read from the
+//! environment:
+//!
+//! - `AUTOGPT_COMPACT_TOKENS`: Size of the memory, in estimated tokens, past which it is
+//!   compacted. Compaction is enabled when it is set.
+//! - `AUTOGPT_COMPACT_KEEP`: Number of recent communications kept verbatim, defaults to 8.
+//! - `AUTOGPT_COMPACT_ARCHIVE`: Whether to archive the compacted communications, defaults to
+//!   false.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::compaction::{CompactionPolicy, is_summary};
+//! use autogpt::common::utils::{ClientType, Communication};
+//! use autogpt::providers::mock::MockClient;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut client = ClientType::Mock(MockClient::new().then("The user wants a parser."));
+//! let policy = CompactionPolicy::default().max_tokens(50).keep_recent(1);
+//!
+//! let mut memory = vec![
+//!     Communication::user("Write a parser for ".to_string() + &"JSON ".repeat(40)),
+//!     Communication::assistant("fn parse() {}"),
+//!     Communication::user("Add tests."),
+//! ];
+//! let compacted = policy.compact(&mut client, &mut memory).await?;
+//!
+//! assert_eq!(compacted.len(), 2);
+//! assert_eq!(memory.len(), 2);
+//! assert!(is_summary(&memory[0]));
+//! assert_eq!(memory[1].content, "Add tests.");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::tokens::{CHARS_PER_TOKEN, estimate_message_tokens};
+use crate::common::utils::Communication;
+use crate::traits::provider::{LlmProvider, transcript};
+use anyhow::Result;
+use std::env::var;
+use tracing::warn;
+
+/// Start of the system message holding the summary of the earlier conversation.
+pub const SUMMARY_HEADER: &str = "Summary of the earlier conversation:\n";
+
+const COMPACTION_PROMPT: &str = "Summarize the conversation below between a user and an \
+assistant, starting from the summary of its earlier part if one is given. Keep every decision, \
+requirement, file name and piece of code that later messages may rely on, and drop the rest. \
+Reply with the summary only.";
+
+/// Returns whether a communication is the rolling summary written by a compaction.
+pub fn is_summary(communication: &Communication) -> bool {
+    communication.role == "system" && communication.content.starts_with(SUMMARY_HEADER)
+}
+
+/// When and how the memory of an agent is compacted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionPolicy {
+    /// Size of the memory, in estimated tokens, past which it is compacted.
+    pub max_tokens: usize,
+    /// Number of recent communications kept verbatim.
+    pub keep_recent: usize,
+    /// Whether to archive the compacted communications to long-term memory. Agents that
+    /// already save each communication with `save_ltm` do not need it.
+    pub archive: bool,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            max_tokens: 8_000,
+            keep_recent: 8,
+            archive: false,
+        }
+    }
+}
+
+impl CompactionPolicy {
+    /// Builds a policy from the `AUTOGPT_COMPACT_*` environment variables, or returns `None`
+    /// if `AUTOGPT_COMPACT_TOKENS` is unset or invalid.
+    pub fn from_env() -> Option<Self> {
+        let number = |name: &str| var(name).ok().and_then(|value| value.trim().parse().ok());
+        let default = Self::default();
+
+        Some(Self {
+            max_tokens: number("AUTOGPT_COMPACT_TOKENS")?,
+            keep_recent: number("AUTOGPT_COMPACT_KEEP").unwrap_or(default.keep_recent),
+            archive: var("AUTOGPT_COMPACT_ARCHIVE")
+                .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+                .unwrap_or(default.archive),
+        })
+    }
+
+    /// Sets the size of the memory, in estimated tokens, past which it is compacted.
+    pub fn max_tokens(mut self, tokens: usize) -> Self {
+        self.max_tokens = tokens;
+        self
+    }
+
+    /// Sets the number of recent communications kept verbatim.
+    pub fn keep_recent(mut self, count: usize) -> Self {
+        self.keep_recent = count;
+        self
+    }
+
+    /// Sets whether to archive the compacted communications to long-term memory.
+    pub fn archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Returns whether a memory is past the threshold and has communications to compact.
+    pub fn needs_compaction(&self, memory: &[Communication]) -> bool {
+        let summary = memory.first().is_some_and(is_summary) as usize;
+        memory.len() > self.keep_recent + summary
+            && estimate_message_tokens(memory) > self.max_tokens
+    }
+
+    /// Compacts a memory past the threshold, replacing its older communications, along with
+    /// the previous summary, by a summary written by `client`.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client writing the summary.
+    /// * `memory` - The memory to compact, oldest communication first.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Vec<Communication>>`): The communications replaced by the summary, without the
+    /// previous summary, or an empty list if the memory did not need compacting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the memory untouched, if the summary cannot be generated.
+    pub async fn compact<P>(
+        &self,
+        client: &mut P,
+        memory: &mut Vec<Communication>,
+    ) -> Result<Vec<Communication>>
+    where
+        P: LlmProvider + ?Sized,
+    {
+        if !self.needs_compaction(memory) {
+            return Ok(Vec::new());
+        }
+        let start = memory.first().is_some_and(is_summary) as usize;
+        let end = memory.len() - self.keep_recent;
+
+        let mut prompt = String::from(COMPACTION_PROMPT);
+        if start == 1 {
+            prompt.push_str("\n\nSummary of the earlier part:\n");
+            prompt.push_str(&memory[0].content[SUMMARY_HEADER.len()..]);
+        }
+        // Keep the end of long conversations, so that the prompt fits the threshold.
+        let conversation = transcript(&memory[start..end]);
+        let skip = conversation
+            .chars()
+            .count()
+            .saturating_sub(self.max_tokens * CHARS_PER_TOKEN);
+        if skip > 0 {
+            warn!("Leaving the oldest {skip} characters of the conversation out of its summary.");
+        }
+        prompt.push_str("\n\nConversation:\n");
+        prompt.extend(conversation.chars().skip(skip));
+
+        let summary = client.chat(&prompt).await?;
+        let compacted: Vec<Communication> = memory
+            .splice(
+                ..end,
+                [Communication::system(format!(
+                    "{SUMMARY_HEADER}{}",
+                    summary.trim()
+                ))],
+            )
+            .skip(start)
+            .collect();
+        Ok(compacted)
+    }
+}
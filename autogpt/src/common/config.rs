@@ -0,0 +1,245 @@
+//! # Model configuration.
+//!
+//! `LlmConfig` holds the model and sampling settings sent with each request. Every field is
+//! optional: unset fields fall back to the provider's defaults, such as `GEMINI_MODEL` or
+//! `LOCAL_MODEL` for the model.
+//!
+//! Each agent starts from the configuration returned by `LlmConfig::for_agent`, which merges,
+//! from lowest to highest precedence:
+//!
+//! 1. The top-level settings of the JSON file named by `AUTOGPT_LLM_CONFIG`.
+//! 2. The `AUTOGPT_MODEL`, `AUTOGPT_TEMPERATURE`, `AUTOGPT_TOP_P`, `AUTOGPT_MAX_TOKENS`,
+//!    `AUTOGPT_STOP` (comma-separated) and `AUTOGPT_SEED` environment variables.
+//! 3. The file's entry for the agent's position under `agents`.
+//!
+//! The file is read again only when it changes. A file that cannot be read or parsed is logged
+//! and ignored, so agents still start with the environment and provider defaults.
+//!
+//! ```json
+//! {
+//!   "temperature": 0.2,
+//!   "agents": {
+//!     "GitGPT": { "model": "gemini-2.0-flash-lite", "max_tokens": 256 },
+//!     "BackendGPT": { "model": "gemini-2.5-pro" }
+//!   }
+//! }
+//! ```
+//!
+//! Providers ignore the settings their API does not support, such as `seed` for Anthropic. The
+//! `schema` setting is set per request by `generate_structured` rather than in the file.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::config::LlmConfig;
+//!
+//! let defaults = LlmConfig::new().model("llama3.2").temperature(0.7);
+//! let config = defaults.merge(&LlmConfig::new().temperature(0.0).stop(["```"]));
+//!
+//! assert_eq!(config.model.as_deref(), Some("llama3.2"));
+//! assert_eq!(config.temperature, Some(0.0));
+//! assert_eq!(config.stop, vec!["```"]);
+//! ```
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env::var;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+use tracing::warn;
+
+/// Model and sampling settings for model requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmConfig {
+    /// Model name, overriding the provider's default model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Sampling temperature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Maximum number of tokens in the reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end the reply when generated.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Seed for deterministic sampling, where supported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// JSON schema the reply must follow, sent through the provider's native JSON mode where
+    /// supported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
+/// Layout of the `AUTOGPT_LLM_CONFIG` file.
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(flatten)]
+    defaults: LlmConfig,
+    #[serde(default)]
+    agents: HashMap<String, LlmConfig>,
+}
+
+/// The last file loaded, with the modification time and length it was parsed at.
+type CachedFile = (PathBuf, Option<(SystemTime, u64)>, Arc<ConfigFile>);
+
+impl ConfigFile {
+    /// Returns the file named by `AUTOGPT_LLM_CONFIG`, parsed again only when it changed, or an
+    /// empty file if the variable is unset or the file is invalid.
+    fn load() -> Arc<Self> {
+        static CACHE: Mutex<Option<CachedFile>> = Mutex::new(None);
+
+        let Ok(path) = var("AUTOGPT_LLM_CONFIG") else {
+            return Arc::default();
+        };
+        let path = PathBuf::from(path);
+        let version = std::fs::metadata(&path)
+            .ok()
+            .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+
+        let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+        let unchanged = cache.as_ref().filter(|(cached, cached_version, _)| {
+            version.is_some() && *cached == path && *cached_version == version
+        });
+        if let Some((_, _, file)) = unchanged {
+            return file.clone();
+        }
+
+        let file = Arc::new(Self::read(&path).unwrap_or_else(|err| {
+            warn!(
+                "Ignoring the model configuration `{}`: {err}",
+                path.display()
+            );
+            Self::default()
+        }));
+        *cache = Some((path, version, file.clone()));
+        file
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+impl LlmConfig {
+    /// Creates an empty configuration, deferring every setting to the provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the configuration shared by every agent from the configuration file and the
+    /// `AUTOGPT_*` environment variables.
+    pub fn from_env() -> Self {
+        ConfigFile::load().defaults.clone().merge(&Self::env())
+    }
+
+    /// Builds the configuration of the agent at `position`, applying its entry in the
+    /// configuration file on top of `from_env`.
+    pub fn for_agent(position: &str) -> Self {
+        let file = ConfigFile::load();
+        let agent = file.agents.get(position).cloned().unwrap_or_default();
+        file.defaults.clone().merge(&Self::env()).merge(&agent)
+    }
+
+    fn env() -> Self {
+        fn parse<T: FromStr>(name: &str) -> Option<T> {
+            var(name).ok().and_then(|value| value.trim().parse().ok())
+        }
+
+        Self {
+            model: var("AUTOGPT_MODEL").ok().filter(|model| !model.is_empty()),
+            temperature: parse("AUTOGPT_TEMPERATURE"),
+            top_p: parse("AUTOGPT_TOP_P"),
+            max_tokens: parse("AUTOGPT_MAX_TOKENS"),
+            stop: var("AUTOGPT_STOP")
+                .map(|stop| {
+                    stop.split(',')
+                        .filter(|sequence| !sequence.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            seed: parse("AUTOGPT_SEED"),
+            schema: None,
+        }
+    }
+
+    /// Returns this configuration with every setting of `other` applied on top of it.
+    pub fn merge(mut self, other: &LlmConfig) -> Self {
+        if other.model.is_some() {
+            self.model = other.model.clone();
+        }
+        self.temperature = other.temperature.or(self.temperature);
+        self.top_p = other.top_p.or(self.top_p);
+        self.max_tokens = other.max_tokens.or(self.max_tokens);
+        if !other.stop.is_empty() {
+            self.stop = other.stop.clone();
+        }
+        self.seed = other.seed.or(self.seed);
+        if other.schema.is_some() {
+            self.schema = other.schema.clone();
+        }
+        self
+    }
+
+    /// Returns the configured model, or `default` if none is set.
+    pub fn model_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.model.as_deref().unwrap_or(default)
+    }
+
+    /// Sets the model name.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Sets the sampling temperature.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the nucleus sampling probability mass.
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Sets the maximum number of tokens in the reply.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Sets the stop sequences.
+    pub fn stop<I, S>(mut self, stop: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the sampling seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the JSON schema the reply must follow.
+    pub fn schema(mut self, schema: Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+}
@@ -0,0 +1,150 @@
+//! # Agent lifecycle.
+//!
+//! The status of an agent moves through a small state machine, checked by
+//! `Agent::try_update`. `AgentGPT` also checks it on `Agent::update`, where transitions that
+//! are not allowed are logged and keep the current status:
+//!
+//! - `Idle`, `Active` and `InUnitTesting` are working states, and may move to any status.
+//! - `Completed` and `Failed` end a run, and only move back to a working state for the next
+//!   one.
+//! - `Blocked` waits on something outside the agent, and `Paused` waits to be resumed. Both
+//!   move back to a working state, or end as `Failed` or `Cancelled`.
+//! - `Cancelled` is only left by resetting the agent to `Idle`.
+//!
+//! Setting the status an agent already has is always allowed, and changes nothing.
+//!
+//! Agents refuse to start a run while blocked, paused or cancelled, as checked by
+//! `Status::ensure_runnable`, and start over from `Idle` after a failed one. This is checked
+//! when `AsyncFunctions::execute` is called, and before every attempt of `AutonomousGPT::run`.
+//! Agents run this way also mark themselves as `Failed` when a run fails.
+//!
+//! Every `AgentGPT` also publishes what happens to it as `AgentEvent`s on a broadcast channel,
+//! so that UIs and loggers can follow agents live: status changes, messages added to its
+//! memory and tools it invokes. Subscribers that fall more than `EVENT_CAPACITY` events behind
+//! miss the oldest ones, and are told so by the channel.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::agents::agent::AgentGPT;
+//! use autogpt::common::lifecycle::AgentEvent;
+//! use autogpt::common::utils::Status;
+//! use autogpt::traits::agent::Agent;
+//!
+//! let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+//! let mut events = agent.subscribe();
+//!
+//! agent.update(Status::Active);
+//! agent.update(Status::Cancelled);
+//! assert!(agent.try_update(Status::Active).is_err());
+//! assert!(agent.status().ensure_runnable().is_err());
+//!
+//! assert!(matches!(
+//!     events.try_recv(),
+//!     Ok(AgentEvent::StatusChanged { to: Status::Active, .. })
+//! ));
+//! ```
+
+use crate::common::utils::{Communication, Status};
+use anyhow::{Result, bail};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Number of events kept for the slowest subscriber of an agent.
+pub const EVENT_CAPACITY: usize = 256;
+
+impl Status {
+    /// Returns whether an agent with this status may move to `next`.
+    pub fn can_become(&self, next: &Status) -> bool {
+        use Status::*;
+
+        if self == next {
+            return true;
+        }
+        match self {
+            Idle | Active | InUnitTesting => true,
+            Completed | Failed(_) => matches!(next, Idle | Active | InUnitTesting),
+            Blocked | Paused => {
+                matches!(next, Idle | Active | InUnitTesting | Failed(_) | Cancelled)
+            }
+            Cancelled => matches!(next, Idle),
+        }
+    }
+
+    /// Moves to `next` and returns the previous status.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, and keeps the current status, if the transition is not allowed.
+    pub fn transition(&mut self, next: Status) -> Result<Status> {
+        if !self.can_become(&next) {
+            bail!("An agent cannot move from {self:?} to {next:?}.");
+        }
+        Ok(std::mem::replace(self, next))
+    }
+
+    /// Checks that an agent with this status may start or go on with a run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the agent is blocked, paused or cancelled.
+    pub fn ensure_runnable(&self) -> Result<()> {
+        match self {
+            Status::Blocked => bail!("The agent is blocked."),
+            Status::Paused => bail!("The agent is paused, resume it to run it."),
+            Status::Cancelled => bail!("The agent was cancelled."),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether the status ends a run, successfully or not.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Status::Completed | Status::Failed(_) | Status::Cancelled
+        )
+    }
+}
+
+/// Something that happened to an agent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentEvent {
+    /// The status of the agent changed.
+    StatusChanged {
+        /// Identifier of the agent.
+        agent: Cow<'static, str>,
+        /// The previous status.
+        from: Status,
+        /// The new status.
+        to: Status,
+    },
+    /// A message was added to the memory of the agent.
+    MessageAdded {
+        /// Identifier of the agent.
+        agent: Cow<'static, str>,
+        /// The added message.
+        message: Communication,
+    },
+    /// The agent ran one of its tools.
+    ToolInvoked {
+        /// Identifier of the agent.
+        agent: Cow<'static, str>,
+        /// Name of the tool.
+        tool: String,
+        /// Arguments the tool was called with.
+        arguments: Value,
+        /// Result of the tool, or its error.
+        result: String,
+    },
+}
+
+impl AgentEvent {
+    /// Returns the identifier of the agent the event happened to.
+    pub fn agent(&self) -> &str {
+        match self {
+            AgentEvent::StatusChanged { agent, .. }
+            | AgentEvent::MessageAdded { agent, .. }
+            | AgentEvent::ToolInvoked { agent, .. } => agent,
+        }
+    }
+}
@@ -1,242 +1,543 @@
+//! # Long-term memory.
+//!
+//! The long-term memory of an agent is a namespace of a `MemoryStore`, named `agent-<id>`,
+//! holding the embedding of every saved communication along with the communication itself.
+//!
+//! Agents share the store returned by `default_store`, built from the environment on first
+//! use:
+//!
+//! - With the `pinecone` feature, and `PINECONE_API_KEY` and `PINECONE_INDEX_URL` set, it is
+//!   a `PineconeStore` on that index.
+//! - Otherwise it is a `FileStore` in `AUTOGPT_MEMORY_DIR`, which defaults to `.memory` under
+//!   `AUTOGPT_WORKSPACE`. It searches each namespace through an `HnswIndex`, an approximate
+//!   nearest neighbour graph saved next to the records and updated on every save.
+//!
+//! The `mem` feature no longer pulls in Pinecone on its own: builds that used a Pinecone index
+//! through it need the `pinecone` feature, and a warning is logged when `PINECONE_API_KEY` is
+//! set without it.
+//!
+//! Another store can be given to an agent through its `ltm` field.
+//!
+//! Memories can be saved with a time to live, after which stores stop returning them and
+//! delete them. They can also be forgotten one by one with `AgentGPT::forget`, or all at once
+//! with `AgentGPT::wipe_memory`. Besides its own namespace, an agent recalls memories from the
+//! shared namespaces named in its `shared_memory`, such as one per project, which any agent
+//! can write to with `save_shared_memory`.
+//!
+//! Embeddings go through the agent's `cache` and `retry` policy, and retries are logged into
+//! its memory like the ones of any other model call.
+//!
+//! When building a prompt, agents recall only the memories relevant to the request: the
+//! request is embedded, the closest records are fetched from the store, and they are ranked
+//! by the agent's `RecallPolicy`, which can favour recent memories and skip near duplicates.
+//! The policy is read from the environment:
+//!
+//! - `AUTOGPT_RECALL_K`: Number of memories recalled per request, defaults to 5.
+//! - `AUTOGPT_RECALL_RECENCY`: Weight of recency in the ranking, defaults to 0.
+//! - `AUTOGPT_RECALL_HALF_LIFE_HOURS`: Age at which the recency of a memory is halved,
+//!   defaults to a week.
+//! - `AUTOGPT_RECALL_DIVERSITY`: Weight of diversity in the ranking, between 0 and 1, defaults
+//!   to 0.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::agents::agent::AgentGPT;
+//! use autogpt::common::memory::FileStore;
+//! use std::sync::Arc;
+//!
+//! let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+//! agent.ltm = Arc::new(FileStore::new("memory/"));
+//! ```
+
+mod file;
+mod hnsw;
+#[cfg(feature = "pinecone")]
+mod pinecone;
+
+pub use file::FileStore;
+pub use hnsw::{HnswConfig, HnswIndex, Metric};
+#[cfg(feature = "pinecone")]
+pub use pinecone::PineconeStore;
+
+use crate::agents::agent::AgentGPT;
+use crate::common::config::LlmConfig;
 use crate::common::utils::ClientType;
 use crate::common::utils::Communication;
-use anyhow::Result;
-use pinecone_sdk::models::{Kind, Value, Vector};
-use pinecone_sdk::pinecone::PineconeClientConfig;
+use crate::traits::memory::MemoryStore;
+use crate::traits::provider::LlmProvider;
+use anyhow::{Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::env::var;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use tracing::{error, warn};
+use uuid::Uuid;
 
-async fn embed_text(client: &mut ClientType, content: Cow<'static, str>) -> Vec<f64> {
-    match client {
-        #[cfg(feature = "gem")]
-        ClientType::Gemini(gem_client) => {
-            use gems::embed::EmbeddingBuilder;
-            use gems::messages::Content;
-            use gems::messages::Message;
-            use gems::models::Model;
-            use gems::traits::CTrait;
-
-            let params = EmbeddingBuilder::default()
-                .model(Model::Embedding)
-                .input(Message::User {
-                    content: Content::Text(content.into()),
-                    name: None,
-                })
-                .build()
-                .unwrap_or_default();
-            gem_client.set_model(Model::Embedding);
-            let response = gem_client.embeddings().create(params).await;
-            gem_client.set_model(Model::Flash20);
-            match response {
-                Ok(embed_response) => {
-                    if let Some(embedding) = embed_response.embedding {
-                        embedding.values
-                    } else {
-                        error!("Gemini: No embedding returned.");
-                        vec![]
-                    }
-                }
-                Err(err) => {
-                    error!("Gemini: Failed to embed content: {}", err);
-                    vec![]
-                }
-            }
-        }
-        #[cfg(feature = "oai")]
-        ClientType::OpenAI(oai_client) => {
-            use openai_dive::v1::models::EmbeddingModel;
-            use openai_dive::v1::resources::embedding::{
-                EmbeddingEncodingFormat, EmbeddingInput, EmbeddingOutput,
-                EmbeddingParametersBuilder,
-            };
+/// A communication remembered by an agent, with its embedding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryRecord {
+    /// Unique identifier of the record in its namespace.
+    pub id: String,
+    /// Embedding of the content of the communication.
+    pub vector: Vec<f32>,
+    /// The remembered communication.
+    pub communication: Communication,
+    /// When the record was created.
+    pub created_at: DateTime<Utc>,
+    /// When the record expires, if ever. Stores never return expired records.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
 
-            let parameters = EmbeddingParametersBuilder::default()
-                .model(EmbeddingModel::TextEmbedding3Small.to_string())
-                .input(EmbeddingInput::String(content.to_string()))
-                .encoding_format(EmbeddingEncodingFormat::Float)
-                .build()
-                .unwrap();
-
-            match oai_client.embeddings().create(parameters).await {
-                Ok(response) => {
-                    if let Some(embedding) = response.data.first() {
-                        match &embedding.embedding {
-                            EmbeddingOutput::Float(vec) => vec.clone(),
-                            EmbeddingOutput::Base64(_) => {
-                                error!("OpenAI: Expected embedding as Float, found Base64.");
-                                vec![]
-                            }
-                        }
-                    } else {
-                        error!("OpenAI: No embedding returned.");
-                        vec![]
-                    }
-                }
-                Err(err) => {
-                    error!("OpenAI: Failed to embed content: {}", err);
-                    vec![]
-                }
-            }
+impl MemoryRecord {
+    /// Creates a record with a new identifier, created now, that never expires.
+    pub fn new(vector: Vec<f32>, communication: Communication) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            vector,
+            communication,
+            created_at: Utc::now(),
+            expires_at: None,
         }
+    }
 
-        // TODO: Add embeddings for claude and xai
-        #[allow(unreachable_patterns)]
-        _ => {
-            error!("Unsupported AI client for embedding.");
-            vec![]
-        }
+    /// Makes the record expire `ttl` after its creation.
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.expires_at = TimeDelta::from_std(ttl)
+            .ok()
+            .and_then(|ttl| self.created_at.checked_add_signed(ttl));
+        self
+    }
+
+    /// Returns whether the record has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
     }
 }
 
-pub async fn save_long_term_memory(
+/// A record found by `MemoryStore::query`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMatch {
+    /// The matching record.
+    pub record: MemoryRecord,
+    /// Similarity of the record to the query, higher is closer.
+    pub score: f32,
+}
+
+/// Returns the cosine similarity of two vectors, or `0.0` if either of them is zero.
+///
+/// Vectors of different lengths are compared over their common prefix.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    Metric::Cosine.score(a, b)
+}
+
+/// Returns the namespace holding the long-term memory of an agent.
+pub fn namespace(agent_id: &str) -> String {
+    format!("agent-{agent_id}")
+}
+
+/// Returns the namespace of a memory shared by several agents, such as one per project.
+pub fn shared_namespace(name: &str) -> String {
+    format!("shared-{name}")
+}
+
+/// Builds a store from the environment, as described in the module documentation.
+///
+/// A warning is logged when `PINECONE_API_KEY` is set in a build without the `pinecone`
+/// feature, since the memories of the index are not read from then on.
+pub fn store_from_env() -> Arc<dyn MemoryStore> {
+    #[cfg(feature = "pinecone")]
+    if let Some(store) = PineconeStore::from_env() {
+        return Arc::new(store);
+    }
+    #[cfg(not(feature = "pinecone"))]
+    if var("PINECONE_API_KEY").is_ok_and(|key| !key.is_empty()) {
+        warn!(
+            "PINECONE_API_KEY is set, but autogpt was built without the `pinecone` feature: \
+             the long-term memory is kept on disk instead of in the Pinecone index."
+        );
+    }
+    Arc::new(FileStore::from_env())
+}
+
+/// Returns the store shared by agents, built with `store_from_env` on first use.
+pub fn default_store() -> Arc<dyn MemoryStore> {
+    static STORE: OnceLock<Arc<dyn MemoryStore>> = OnceLock::new();
+    Arc::clone(STORE.get_or_init(store_from_env))
+}
+
+/// Embeds a text with the agent's cache and retry policy.
+async fn embed_text(
+    agent: &mut AgentGPT,
     client: &mut ClientType,
-    agent_id: Cow<'static, str>,
-    communication: Communication,
-) -> Result<()> {
-    let config = PineconeClientConfig {
-        api_key: Some(std::env::var("PINECONE_API_KEY").unwrap_or_default()),
-        ..Default::default()
-    };
+    content: &str,
+) -> Result<Vec<f64>> {
+    let cached = agent.cache.as_ref().map(|cache| {
+        cache.entry(
+            "embed",
+            client.name(),
+            &LlmConfig::default(),
+            &[Communication::user(content.to_string())],
+        )
+    });
+    if let Some(embedding) = cached.as_ref().and_then(|entry| entry.load()) {
+        return Ok(embedding);
+    }
 
-    let pinecone_result = config.client();
-    let pinecone = match pinecone_result {
-        Ok(client) => client,
-        Err(e) => {
-            error!("Error creating Pinecone client: {:?}", e);
-            return Err(std::io::Error::other("Failed to create Pinecone client").into());
+    let retry = agent.retry;
+    let mut attempt = 1;
+    let embedding = loop {
+        match client.embed(content).await {
+            Ok(embedding) => break embedding,
+            Err(err) => attempt = retry.backoff("embed", attempt, err).await?,
         }
     };
+    if embedding.is_empty() {
+        bail!("The provider returned an empty embedding.");
+    }
 
-    let index_result = pinecone
-        .index(&std::env::var("PINECONE_INDEX_URL").unwrap_or_default())
-        .await;
-    let mut index = match index_result {
-        Ok(index) => index,
-        Err(e) => {
-            error!("Error connecting to Pinecone index: {:?}", e);
-            return Err(std::io::Error::other("Failed to connect to Pinecone index").into());
-        }
-    };
+    if let Some(entry) = &cached {
+        entry.store(&embedding);
+    }
+    Ok(embedding)
+}
 
-    let namespace = format!("agent-{agent_id}");
-    let values_f32: Vec<f32> = embed_text(client, communication.content.clone())
-        .await
+/// Embeds a communication and saves it in a namespace of the agent's store, returning the
+/// identifier of its record. Nothing is saved if the communication cannot be embedded.
+async fn remember(
+    agent: &mut AgentGPT,
+    client: &mut ClientType,
+    namespace: &str,
+    communication: Communication,
+) -> Result<String> {
+    let vector = embed_text(agent, client, &communication.content)
+        .await?
         .into_iter()
-        .map(|v| v as f32)
+        .map(|value| value as f32)
         .collect();
+    let mut record = MemoryRecord::new(vector, communication);
+    if let Some(ttl) = agent.memory_ttl {
+        record = record.expires_in(ttl);
+    }
+    let id = record.id.clone();
+    agent.ltm.upsert(namespace, vec![record]).await?;
+    Ok(id)
+}
 
-    let padding: Vec<f32> = vec![0.0; 1024 - values_f32.len()];
-    let padded_values: Vec<f32> = values_f32.into_iter().chain(padding).collect();
-
-    let content = communication.content.clone();
-    let role = communication.role.clone();
-
-    let vector = Vector {
-        id: uuid::Uuid::new_v4().to_string(),
-        values: padded_values,
-        sparse_values: None,
-        metadata: Some(pinecone_sdk::models::Metadata {
-            fields: BTreeMap::from([
-                (
-                    "role".to_string(),
-                    Value {
-                        kind: Some(Kind::StringValue(role.to_string())),
-                    },
-                ),
-                (
-                    "content".to_string(),
-                    Value {
-                        kind: Some(Kind::StringValue(content.to_string())),
-                    },
-                ),
-            ]),
-        }),
-    };
-    if let Err(_e) = index.upsert(&[vector], &namespace.into()).await {
-        warn!("Upsert failed -> check `PINECONE_INDEX_URL` and trial limits.");
+/// Embeds a communication and saves it in the long-term memory of an agent, to expire after
+/// its `memory_ttl` if set.
+///
+/// # Arguments
+///
+/// * `agent` - The agent remembering the communication, whose cache and retry policy are used
+///   for the embedding.
+/// * `client` - The client embedding the content.
+/// * `communication` - The communication to remember.
+///
+/// # Returns
+///
+/// (`Result<String>`): The identifier of the saved record, with which it can be forgotten.
+///
+/// # Errors
+///
+/// Returns an error if the communication cannot be embedded, or if the store rejects it, for
+/// instance because its embedding has another dimension than the ones already saved.
+pub async fn save_long_term_memory(
+    agent: &mut AgentGPT,
+    client: &mut ClientType,
+    communication: Communication,
+) -> Result<String> {
+    let namespace = namespace(&agent.id);
+    remember(agent, client, &namespace, communication).await
+}
+
+/// Embeds a communication and saves it in a memory shared by several agents, which recall it
+/// when its name is in their `shared_memory`.
+///
+/// # Arguments
+///
+/// * `agent` - The agent writing the communication, whose store and `memory_ttl` are used.
+/// * `client` - The client embedding the content.
+/// * `name` - The name of the shared memory, such as the name of a project.
+/// * `communication` - The communication to remember.
+///
+/// # Returns
+///
+/// (`Result<String>`): The identifier of the saved record, with which it can be forgotten.
+///
+/// # Errors
+///
+/// Returns an error if the communication cannot be embedded, or if the store rejects it.
+pub async fn save_shared_memory(
+    agent: &mut AgentGPT,
+    client: &mut ClientType,
+    name: &str,
+    communication: Communication,
+) -> Result<String> {
+    remember(agent, client, &shared_namespace(name), communication).await
+}
+
+/// Returns every communication in the long-term memory of an agent, the oldest first.
+///
+/// # Arguments
+///
+/// * `store` - The store holding the memory.
+/// * `agent_id` - The identifier of the agent.
+pub async fn load_long_term_memory(
+    store: &dyn MemoryStore,
+    agent_id: Cow<'static, str>,
+) -> Result<Vec<Communication>> {
+    Ok(store
+        .list(&namespace(&agent_id))
+        .await?
+        .into_iter()
+        .map(|record| record.communication)
+        .collect())
+}
+
+/// Returns the long-term memory of an agent as `role: content` lines, or an empty string if
+/// it cannot be read.
+///
+/// # Arguments
+///
+/// * `store` - The store holding the memory.
+/// * `agent_id` - The identifier of the agent.
+pub async fn long_term_memory_context(
+    store: &dyn MemoryStore,
+    agent_id: Cow<'static, str>,
+) -> String {
+    match load_long_term_memory(store, agent_id).await {
+        Ok(comms) => comms
+            .iter()
+            .map(|c| format!("{}: {}", c.role, c.content))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(err) => {
+            error!("Failed to load the long-term memory: {err}");
+            String::new()
+        }
     }
-    Ok(())
 }
 
-pub async fn load_long_term_memory(agent_id: Cow<'static, str>) -> Result<Vec<Communication>> {
-    let config = PineconeClientConfig {
-        api_key: Some(std::env::var("PINECONE_API_KEY").unwrap()),
-        ..Default::default()
-    };
+/// How the memories recalled for a request are chosen among the closest records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecallPolicy {
+    /// Number of memories recalled when building a prompt.
+    pub k: usize,
+    /// Weight of recency, added to the similarity of a memory as `recency * 0.5^(age /
+    /// half_life)`. Zero ranks memories by similarity alone.
+    pub recency: f32,
+    /// Age at which the recency bonus of a memory is halved.
+    pub half_life: Duration,
+    /// Weight of diversity, between 0 and 1. Above zero, memories are picked by maximal
+    /// marginal relevance, so that a memory too similar to one already picked is passed over.
+    pub diversity: f32,
+    /// Number of records fetched from the store for each recalled memory, to rerank.
+    pub candidates: usize,
+}
 
-    let pinecone_result = config.client();
-    let pinecone = match pinecone_result {
-        Ok(client) => client,
-        Err(e) => {
-            error!("Error creating Pinecone client: {:?}", e);
-            return Err(std::io::Error::other("Failed to create Pinecone client").into());
+impl Default for RecallPolicy {
+    fn default() -> Self {
+        Self {
+            k: 5,
+            recency: 0.0,
+            half_life: Duration::from_secs(7 * 24 * 3600),
+            diversity: 0.0,
+            candidates: 4,
         }
-    };
+    }
+}
+
+impl RecallPolicy {
+    /// Builds a policy from the `AUTOGPT_RECALL_*` environment variables, falling back to the
+    /// defaults for unset or invalid values.
+    pub fn from_env() -> Self {
+        let number = |name: &str| {
+            var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite() && *value >= 0.0)
+        };
+        let default = Self::default();
 
-    let index_result = pinecone
-        .index(&std::env::var("PINECONE_INDEX_URL").unwrap_or_default())
-        .await;
-    let mut index = match index_result {
-        Ok(index) => index,
-        Err(e) => {
-            error!("Error connecting to Pinecone index: {:?}", e);
-            return Err(std::io::Error::other("Failed to connect to Pinecone index").into());
+        Self {
+            k: number("AUTOGPT_RECALL_K").map_or(default.k, |k| k as usize),
+            recency: number("AUTOGPT_RECALL_RECENCY").map_or(default.recency, |w| w as f32),
+            half_life: number("AUTOGPT_RECALL_HALF_LIFE_HOURS")
+                .filter(|hours| *hours > 0.0)
+                .map_or(default.half_life, |hours| {
+                    Duration::from_secs_f64(hours * 3600.0)
+                }),
+            diversity: number("AUTOGPT_RECALL_DIVERSITY")
+                .map_or(default.diversity, |w| (w as f32).min(1.0)),
+            ..default
         }
-    };
+    }
 
-    let namespace = format!("agent-{agent_id}");
-    let list = index
-        .list(&namespace.clone().into(), None, None, None)
-        .await
-        .unwrap();
-
-    let ids: Vec<&str> = list.vectors.iter().map(|v| v.id.as_str()).collect();
-
-    let fetched_result = index.fetch(&ids, &namespace.into()).await;
-
-    let communications = if let Ok(fetched) = fetched_result {
-        fetched
-            .vectors
-            .values()
-            .map(|v| {
-                let metadata_opt = v.metadata.as_ref();
-
-                let role = metadata_opt
-                    .and_then(|meta| meta.fields.get("role"))
-                    .and_then(|v| v.kind.as_ref())
-                    .and_then(|kind| match kind {
-                        Kind::StringValue(val) => Some(Cow::Owned(val.clone())),
-                        _ => None,
-                    })
-                    .unwrap_or(Cow::Borrowed("unknown"));
-
-                let content = metadata_opt
-                    .and_then(|meta| meta.fields.get("content"))
-                    .and_then(|v| v.kind.as_ref())
-                    .and_then(|kind| match kind {
-                        Kind::StringValue(val) => Some(Cow::Owned(val.clone())),
-                        _ => None,
-                    })
-                    .unwrap_or(Cow::Borrowed(""));
-
-                Communication { role, content }
+    /// Sets the number of memories recalled when building a prompt.
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    /// Sets the weight of recency and the age at which it is halved.
+    pub fn recency(mut self, weight: f32, half_life: Duration) -> Self {
+        self.recency = weight.max(0.0);
+        self.half_life = half_life;
+        self
+    }
+
+    /// Sets the weight of diversity, clamped between 0 and 1.
+    pub fn diversity(mut self, weight: f32) -> Self {
+        self.diversity = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Picks the `k` memories to recall among the records found by a query.
+    ///
+    /// # Returns
+    ///
+    /// The picked memories, the most relevant first, each scored by its similarity plus its
+    /// recency bonus.
+    pub fn rank(&self, matches: Vec<MemoryMatch>, k: usize) -> Vec<MemoryMatch> {
+        let now = Utc::now();
+        let half_life = self.half_life.as_secs_f64().max(1.0);
+        let mut pool: Vec<MemoryMatch> = matches
+            .into_iter()
+            .map(|mut found| {
+                if self.recency > 0.0 {
+                    let age = (now - found.record.created_at).num_seconds().max(0) as f64;
+                    found.score += self.recency * 0.5f64.powf(age / half_life) as f32;
+                }
+                found
             })
-            .collect::<Vec<_>>()
-    } else {
-        Vec::new()
+            .collect();
+        pool.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if self.diversity <= 0.0 {
+            pool.truncate(k);
+            return pool;
+        }
+
+        let mut picked: Vec<MemoryMatch> = Vec::with_capacity(k.min(pool.len()));
+        while picked.len() < k && !pool.is_empty() {
+            let marginal = |found: &MemoryMatch| {
+                let redundancy = picked
+                    .iter()
+                    .map(|other| cosine_similarity(&found.record.vector, &other.record.vector))
+                    .fold(0.0, f32::max);
+                (1.0 - self.diversity) * found.score - self.diversity * redundancy
+            };
+            let best = (0..pool.len())
+                .max_by(|&a, &b| {
+                    marginal(&pool[a])
+                        .total_cmp(&marginal(&pool[b]))
+                        .then(b.cmp(&a))
+                })
+                .expect("the pool is not empty");
+            picked.push(pool.remove(best));
+        }
+        picked
+    }
+}
+
+/// Returns the memories most relevant to a query among the namespaces of an agent, as given
+/// by `AgentGPT::memory_namespaces`, ranked by its `recall` policy.
+///
+/// If the query cannot be embedded, the `k` most recent memories are returned instead.
+///
+/// # Arguments
+///
+/// * `agent` - The agent recalling the memories.
+/// * `client` - The client embedding the query.
+/// * `query` - The text to find memories about, such as the current request.
+/// * `k` - The maximum number of memories to return.
+///
+/// # Returns
+///
+/// (`Result<Vec<MemoryMatch>>`): The recalled memories, the most relevant first.
+pub async fn recall_long_term_memory(
+    agent: &mut AgentGPT,
+    client: &mut ClientType,
+    query: &str,
+    k: usize,
+) -> Result<Vec<MemoryMatch>> {
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+    let store = Arc::clone(&agent.ltm);
+    let namespaces = agent.memory_namespaces();
+    let vector: Vec<f32> = match embed_text(agent, client, query).await {
+        Ok(embedding) => embedding.into_iter().map(|value| value as f32).collect(),
+        Err(err) => {
+            warn!("Failed to embed the query, recalling the latest memories instead: {err:#}");
+            Vec::new()
+        }
     };
+    if vector.is_empty() {
+        let mut records = Vec::new();
+        for namespace in &namespaces {
+            records.extend(store.list(namespace).await?);
+        }
+        records.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+        return Ok(records
+            .into_iter()
+            .take(k)
+            .map(|record| MemoryMatch { record, score: 0.0 })
+            .collect());
+    }
 
-    Ok(communications)
+    let candidates = k.saturating_mul(agent.recall.candidates.max(1));
+    let mut matches = Vec::new();
+    for namespace in &namespaces {
+        matches.extend(store.query(namespace, &vector, candidates).await?);
+    }
+    Ok(agent.recall.rank(matches, k))
 }
 
-pub async fn long_term_memory_context(agent_id: Cow<'static, str>) -> String {
-    match load_long_term_memory(agent_id).await {
-        Ok(comms) => comms
-            .iter()
-            .map(|c| format!("{}: {}", c.role, c.content))
-            .collect::<Vec<_>>()
-            .join("\n"),
-        Err(_) => String::from(""),
+/// Returns the memories of an agent most relevant to a query as `role: content` lines, the
+/// oldest first, or an empty string if they cannot be read.
+///
+/// # Arguments
+///
+/// * `agent` - The agent recalling the memories.
+/// * `client` - The client embedding the query.
+/// * `query` - The text to find memories about, such as the current request.
+/// * `k` - The maximum number of memories to return.
+pub async fn relevant_memory_context(
+    agent: &mut AgentGPT,
+    client: &mut ClientType,
+    query: &str,
+    k: usize,
+) -> String {
+    match recall_long_term_memory(agent, client, query, k).await {
+        Ok(mut matches) => {
+            matches.sort_by_key(|found| found.record.created_at);
+            matches
+                .iter()
+                .map(|found| {
+                    let c = &found.record.communication;
+                    format!("{}: {}", c.role, c.content)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Err(err) => {
+            error!("Failed to recall the long-term memory: {err}");
+            String::new()
+        }
+    }
+}
+
+/// Appends recalled memories to a system prompt, under a heading telling the model what they
+/// are. The prompt is returned unchanged when there is nothing to recall.
+pub fn with_recalled_memory(prompt: &str, context: &str) -> String {
+    if context.is_empty() {
+        prompt.to_string()
+    } else {
+        format!("{prompt}\n\nRelevant memories from previous requests:\n{context}")
     }
 }
@@ -0,0 +1,291 @@
+//! Embedded store keeping each namespace in a JSON file, searched through an HNSW index.
+
+use crate::common::memory::{HnswConfig, HnswIndex, MemoryMatch, MemoryRecord};
+use crate::traits::memory::MemoryStore;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::env::var;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// The records of a namespace loaded in memory, with their index.
+#[derive(Debug)]
+struct Namespace {
+    records: Vec<MemoryRecord>,
+    /// Position of each record, by identifier.
+    positions: HashMap<String, usize>,
+    index: HnswIndex,
+}
+
+impl Namespace {
+    /// Removes the records for which `remove` is true, returning whether there were any.
+    fn remove_where(&mut self, remove: impl Fn(&MemoryRecord) -> bool) -> bool {
+        let count = self.records.len();
+        let index = &mut self.index;
+        self.records.retain(|record| {
+            let removed = remove(record);
+            if removed {
+                index.remove(&record.id);
+            }
+            !removed
+        });
+        if self.records.len() == count {
+            return false;
+        }
+        self.positions = positions(&self.records);
+        true
+    }
+}
+
+/// Returns the position of each record, by identifier.
+fn positions(records: &[MemoryRecord]) -> HashMap<String, usize> {
+    records
+        .iter()
+        .enumerate()
+        .map(|(position, record)| (record.id.clone(), position))
+        .collect()
+}
+
+/// A `MemoryStore` keeping each namespace in a directory: its records in a JSON file, and the
+/// graph of its `HnswIndex` next to them, updated on every upsert.
+///
+/// Namespaces are loaded on first use and kept in memory, so a directory should only be used
+/// by one process at a time. An index that is missing, out of date or built with other
+/// settings is rebuilt from the records. Expired records are deleted whenever their namespace
+/// is read or written. Clones of a store share its namespaces.
+///
+/// Upserts and queries with a vector whose dimension differs from the one of the other
+/// records of the namespace fail, as the vectors of an `HnswIndex` all share a dimension.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    /// Directory of the namespace files.
+    pub dir: PathBuf,
+    /// Settings of the indexes of the namespaces.
+    pub index: HnswConfig,
+    namespaces: Arc<Mutex<HashMap<String, Namespace>>>,
+}
+
+impl FileStore {
+    /// Creates a store in `dir`, created on the first write, with the default index
+    /// settings.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            index: HnswConfig::default(),
+            namespaces: Arc::default(),
+        }
+    }
+
+    /// Creates a store in `AUTOGPT_MEMORY_DIR`, or in `.memory` under `AUTOGPT_WORKSPACE`.
+    pub fn from_env() -> Self {
+        let dir = match var("AUTOGPT_MEMORY_DIR") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => {
+                let workspace =
+                    var("AUTOGPT_WORKSPACE").unwrap_or_else(|_| "workspace/".to_string());
+                Path::new(&workspace).join(".memory")
+            }
+        };
+        Self::new(dir)
+    }
+
+    /// Sets the settings of the indexes, such as their metric.
+    pub fn with_index(mut self, index: HnswConfig) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Returns the file of a namespace with the given extension. Characters other than ASCII
+    /// letters, digits, `-` and `_` are escaped, so that distinct namespaces never share a
+    /// file.
+    fn path(&self, namespace: &str, extension: &str) -> PathBuf {
+        let mut name = String::with_capacity(namespace.len() + extension.len() + 1);
+        for byte in namespace.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("%{byte:02x}"));
+            }
+        }
+        name.push('.');
+        name.push_str(extension);
+        self.dir.join(name)
+    }
+
+    /// Returns a namespace, loading it and its index on first use.
+    async fn load<'a>(
+        &self,
+        namespaces: &'a mut HashMap<String, Namespace>,
+        namespace: &str,
+    ) -> Result<&'a mut Namespace> {
+        if !namespaces.contains_key(namespace) {
+            let path = self.path(namespace, "json");
+            let records: Vec<MemoryRecord> = match fs::read(&path).await {
+                Ok(bytes) => serde_json::from_slice(&bytes)
+                    .with_context(|| format!("Invalid memory in {}", path.display()))?,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+                }
+            };
+            let index = self.load_index(namespace, &records).await;
+            let positions = positions(&records);
+            namespaces.insert(
+                namespace.to_string(),
+                Namespace {
+                    records,
+                    positions,
+                    index,
+                },
+            );
+        }
+        Ok(namespaces
+            .get_mut(namespace)
+            .expect("the namespace is loaded"))
+    }
+
+    /// Reads the index of a namespace, or builds it from the records.
+    async fn load_index(&self, namespace: &str, records: &[MemoryRecord]) -> HnswIndex {
+        let path = self.path(namespace, "hnsw.json");
+        let saved = fs::read(&path)
+            .await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HnswIndex>(&bytes).ok())
+            .filter(|index| *index.config() == self.index);
+        let vectors = records
+            .iter()
+            .map(|record| (record.id.as_str(), record.vector.as_slice()));
+        if let Some(index) = saved.and_then(|index| index.restore(vectors)) {
+            return index;
+        }
+
+        debug!("Indexing {} records of {namespace}", records.len());
+        let mut index = HnswIndex::new(self.index);
+        for record in records {
+            // Such records are still listed, but cannot be found by a query.
+            if let Err(err) = index.insert(record.id.clone(), &record.vector) {
+                warn!("Not indexing a record of {namespace}: {err}");
+            }
+        }
+        index
+    }
+
+    /// Writes the records and the index of a namespace.
+    async fn save(&self, name: &str, namespace: &Namespace) -> Result<()> {
+        if !fs::try_exists(&self.dir).await? {
+            fs::create_dir_all(&self.dir).await?;
+            // Keep the memory out of the repositories agents commit to.
+            fs::write(self.dir.join(".gitignore"), "*\n").await?;
+        }
+        write(
+            &self.path(name, "json"),
+            &serde_json::to_vec(&namespace.records)?,
+        )
+        .await?;
+        // The index can always be rebuilt from the records.
+        if let Err(err) = write(
+            &self.path(name, "hnsw.json"),
+            &serde_json::to_vec(&namespace.index)?,
+        )
+        .await
+        {
+            warn!("Failed to save the index of {name}: {err:#}");
+        }
+        Ok(())
+    }
+}
+
+/// Replaces a file atomically.
+async fn write(path: &Path, contents: &[u8]) -> Result<()> {
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, contents)
+        .await
+        .with_context(|| format!("Failed to write {}", temporary.display()))?;
+    fs::rename(&temporary, path)
+        .await
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+#[async_trait]
+impl MemoryStore for FileStore {
+    async fn upsert(&self, namespace: &str, records: Vec<MemoryRecord>) -> Result<()> {
+        let mut namespaces = self.namespaces.lock().await;
+        let loaded = self.load(&mut namespaces, namespace).await?;
+        for record in records {
+            loaded.index.insert(record.id.clone(), &record.vector)?;
+            match loaded.positions.get(&record.id) {
+                Some(&position) => loaded.records[position] = record,
+                None => {
+                    loaded
+                        .positions
+                        .insert(record.id.clone(), loaded.records.len());
+                    loaded.records.push(record);
+                }
+            }
+        }
+        loaded.remove_where(MemoryRecord::is_expired);
+        self.save(namespace, loaded).await
+    }
+
+    async fn query(&self, namespace: &str, vector: &[f32], k: usize) -> Result<Vec<MemoryMatch>> {
+        let mut namespaces = self.namespaces.lock().await;
+        let loaded = self.load(&mut namespaces, namespace).await?;
+        if loaded.remove_where(MemoryRecord::is_expired) {
+            self.save(namespace, loaded).await?;
+        }
+        Ok(loaded
+            .index
+            .search(vector, k)?
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let record = loaded.records.get(*loaded.positions.get(id)?)?;
+                Some(MemoryMatch {
+                    record: record.clone(),
+                    score,
+                })
+            })
+            .collect())
+    }
+
+    async fn list(&self, namespace: &str) -> Result<Vec<MemoryRecord>> {
+        let mut namespaces = self.namespaces.lock().await;
+        let loaded = self.load(&mut namespaces, namespace).await?;
+        if loaded.remove_where(MemoryRecord::is_expired) {
+            self.save(namespace, loaded).await?;
+        }
+        let mut records = loaded.records.clone();
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn delete(&self, namespace: &str, ids: &[String]) -> Result<()> {
+        let mut namespaces = self.namespaces.lock().await;
+        let loaded = self.load(&mut namespaces, namespace).await?;
+        if loaded.remove_where(|record| record.is_expired() || ids.contains(&record.id)) {
+            self.save(namespace, loaded).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_namespace(&self, namespace: &str) -> Result<()> {
+        let mut namespaces = self.namespaces.lock().await;
+        namespaces.remove(namespace);
+        for path in [
+            self.path(namespace, "json"),
+            self.path(namespace, "hnsw.json"),
+        ] {
+            match fs::remove_file(&path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to delete {}", path.display()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
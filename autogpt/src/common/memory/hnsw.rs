@@ -0,0 +1,548 @@
+//! Hierarchical navigable small world graph, the approximate nearest neighbour index of the
+//! `FileStore`.
+//!
+//! Every vector is a node of a stack of graphs: all of them are in the bottom layer, and each
+//! layer up holds exponentially fewer of them. A search walks greedily from the entry point
+//! through the sparse upper layers, then explores the bottom layer around the closest node
+//! found, keeping the `ef_search` best candidates. Inserting runs the same search to link the
+//! new node to its closest neighbours, so the index is updated incrementally.
+//!
+//! Removed vectors are only marked as such, since other nodes still route through them, and
+//! the graph is rebuilt once they outnumber the live ones.
+//!
+//! All the vectors of an index have the dimension of the first one inserted. Vectors of
+//! another dimension, such as the embeddings of another model, are rejected rather than
+//! compared.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Highest layer a node can be placed on.
+const MAX_LEVEL: usize = 16;
+
+/// Similarity measure between vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Cosine of the angle between the vectors, from `-1.0` to `1.0`.
+    #[default]
+    Cosine,
+    /// Dot product of the vectors.
+    Dot,
+    /// Euclidean distance between the vectors, scored as its opposite so that higher is
+    /// closer.
+    Euclidean,
+}
+
+impl Metric {
+    /// Returns the similarity of two vectors, higher is closer. Vectors of different lengths
+    /// are compared over their common prefix.
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => {
+                let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+                for (x, y) in a.iter().zip(b) {
+                    dot += x * y;
+                    norm_a += x * x;
+                    norm_b += y * y;
+                }
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    return 0.0;
+                }
+                dot / (norm_a.sqrt() * norm_b.sqrt())
+            }
+            Metric::Dot => dot(a, b),
+            Metric::Euclidean => {
+                let squared: f32 = a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum();
+                -squared.sqrt()
+            }
+        }
+    }
+
+    /// Returns the vector stored in the index for `vector`: cosine indexes store unit vectors,
+    /// so that their similarity is a dot product.
+    fn prepare(self, vector: &[f32]) -> Vec<f32> {
+        let mut vector = vector.to_vec();
+        if self == Metric::Cosine {
+            let norm = dot(&vector, &vector).sqrt();
+            if norm > 0.0 {
+                vector.iter_mut().for_each(|x| *x /= norm);
+            }
+        }
+        vector
+    }
+
+    /// Returns the similarity of two vectors returned by `prepare`.
+    fn prepared_score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => dot(a, b),
+            _ => self.score(a, b),
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Settings of an `HnswIndex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Similarity measure of the index.
+    pub metric: Metric,
+    /// Number of neighbours of a node on the upper layers, twice as many on the bottom one.
+    pub m: usize,
+    /// Number of candidates kept while linking a new node. Higher builds a better graph,
+    /// more slowly.
+    pub ef_construction: usize,
+    /// Number of candidates kept while searching, at least the number of results. Higher
+    /// finds more of the true nearest neighbours, more slowly.
+    pub ef_search: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            metric: Metric::Cosine,
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+        }
+    }
+}
+
+impl HnswConfig {
+    /// Creates the default settings: cosine similarity, `m` of 16, `ef_construction` of 200
+    /// and `ef_search` of 64.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the similarity measure.
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Sets the number of neighbours of a node, at least 2.
+    pub fn m(mut self, m: usize) -> Self {
+        self.m = m.max(2);
+        self
+    }
+
+    /// Sets the number of candidates kept while linking a new node.
+    pub fn ef_construction(mut self, ef_construction: usize) -> Self {
+        self.ef_construction = ef_construction.max(1);
+        self
+    }
+
+    /// Sets the number of candidates kept while searching.
+    pub fn ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = ef_search.max(1);
+        self
+    }
+}
+
+/// A node of the graph, with its neighbours on each of its layers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    id: String,
+    /// Not persisted, the vectors are restored from the records of the namespace.
+    #[serde(skip)]
+    vector: Vec<f32>,
+    links: Vec<Vec<usize>>,
+    #[serde(default)]
+    removed: bool,
+}
+
+/// Sorts the neighbours of the node at `origin`, the most similar first.
+///
+/// Ties are broken by a hash of both positions rather than by position, so that the nodes of
+/// a group of identical vectors each keep their own neighbours among the group and stay
+/// reachable, instead of all of them linking to the same few.
+fn by_similarity(candidates: &mut [Candidate], origin: usize) {
+    let spread = |position: usize| {
+        let mut hash = (origin as u64) << 32 ^ position as u64;
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^ (hash >> 31)
+    };
+    candidates.sort_unstable_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| spread(a.1).cmp(&spread(b.1)))
+    });
+}
+
+/// A node index along with its similarity to the query, ordered by similarity.
+#[derive(Debug, Clone, Copy)]
+struct Candidate(f32, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(other.1.cmp(&self.1))
+    }
+}
+
+/// Approximate nearest neighbour index over identified vectors.
+///
+/// # Examples
+///
+/// ```rust
+/// use autogpt::common::memory::{HnswConfig, HnswIndex, Metric};
+///
+/// let mut index = HnswIndex::new(HnswConfig::new().metric(Metric::Euclidean));
+/// index.insert("origin", &[0.0, 0.0]).unwrap();
+/// index.insert("far", &[10.0, 10.0]).unwrap();
+/// index.insert("near", &[1.0, 0.0]).unwrap();
+/// assert!(index.insert("flat", &[1.0]).is_err());
+///
+/// let closest = index.search(&[0.9, 0.1], 2).unwrap();
+/// assert_eq!(closest[0].0, "near");
+/// assert_eq!(closest[1].0, "origin");
+///
+/// index.remove("near");
+/// assert_eq!(index.search(&[0.9, 0.1], 1).unwrap()[0].0, "origin");
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    nodes: Vec<Node>,
+    entry: Option<usize>,
+    /// Position of the live node of each identifier.
+    #[serde(skip)]
+    positions: HashMap<String, usize>,
+}
+
+impl HnswIndex {
+    /// Creates an empty index.
+    pub fn new(config: HnswConfig) -> Self {
+        Self {
+            config,
+            nodes: Vec::new(),
+            entry: None,
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Returns the settings of the index.
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Returns the number of vectors in the index.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns whether the index holds no vector.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns whether the index holds a vector with this identifier.
+    pub fn contains(&self, id: &str) -> bool {
+        self.positions.contains_key(id)
+    }
+
+    /// Returns the dimension of the vectors of the index, or `None` if it is empty.
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension_besides("")
+    }
+
+    /// Returns the dimension of the live vectors other than the one with identifier `id`.
+    fn dimension_besides(&self, id: &str) -> Option<usize> {
+        self.positions
+            .iter()
+            .find(|(other, _)| *other != id)
+            .map(|(_, &position)| self.nodes[position].vector.len())
+    }
+
+    /// Inserts a vector, replacing the one with the same identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, and leaves the index unchanged, if the vector is empty or if its
+    /// dimension differs from the one of the other vectors of the index.
+    pub fn insert(&mut self, id: impl Into<String>, vector: &[f32]) -> Result<()> {
+        let id = id.into();
+        if vector.is_empty() {
+            bail!("The vector of {id} is empty.");
+        }
+        if let Some(dimension) = self
+            .dimension_besides(&id)
+            .filter(|&dimension| dimension != vector.len())
+        {
+            bail!(
+                "The vector of {id} has {} dimensions, but the index holds vectors of {dimension}.",
+                vector.len()
+            );
+        }
+        self.link_node(id, vector);
+        Ok(())
+    }
+
+    /// Inserts a vector whose dimension was checked, replacing the one with the same
+    /// identifier.
+    fn link_node(&mut self, id: String, vector: &[f32]) {
+        self.remove(&id);
+
+        let vector = self.config.metric.prepare(vector);
+        let level = self.level(&id);
+        let position = self.nodes.len();
+        self.nodes.push(Node {
+            id: id.clone(),
+            vector,
+            links: vec![Vec::new(); level + 1],
+            removed: false,
+        });
+        self.positions.insert(id, position);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(position);
+            return;
+        };
+        let top = self.nodes[entry].links.len() - 1;
+        let query = self.nodes[position].vector.clone();
+
+        let mut closest = entry;
+        for layer in (level + 1..=top).rev() {
+            closest = self.greedy(&query, closest, layer);
+        }
+        let mut entries = vec![closest];
+        for layer in (0..=level.min(top)).rev() {
+            let mut found = self.search_layer(&query, &entries, self.config.ef_construction, layer);
+            by_similarity(&mut found, position);
+            let neighbours: Vec<usize> = found
+                .iter()
+                .take(self.config.m)
+                .map(|candidate| candidate.1)
+                .collect();
+            for &neighbour in &neighbours {
+                self.link(neighbour, position, layer);
+            }
+            self.nodes[position].links[layer] = neighbours;
+            entries = found.into_iter().map(|candidate| candidate.1).collect();
+        }
+        if level > top {
+            self.entry = Some(position);
+        }
+    }
+
+    /// Removes the vector with this identifier, if any.
+    pub fn remove(&mut self, id: &str) {
+        let Some(position) = self.positions.remove(id) else {
+            return;
+        };
+        self.nodes[position].removed = true;
+        if self.nodes.len() - self.positions.len() > self.positions.len() {
+            self.rebuild();
+        }
+    }
+
+    /// Returns the identifiers of the vectors most similar to `query` with their similarity,
+    /// the most similar first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dimension of `query` differs from the one of the vectors of
+    /// the index.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(&str, f32)>> {
+        if let Some(dimension) = self
+            .dimension()
+            .filter(|&dimension| dimension != query.len())
+        {
+            bail!(
+                "The query has {} dimensions, but the index holds vectors of {dimension}.",
+                query.len()
+            );
+        }
+        let Some(entry) = self.entry else {
+            return Ok(Vec::new());
+        };
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let query = self.config.metric.prepare(query);
+        let mut closest = entry;
+        for layer in (1..self.nodes[entry].links.len()).rev() {
+            closest = self.greedy(&query, closest, layer);
+        }
+        // Removed nodes are visited but not returned, so look a little further for each.
+        let removed = self.nodes.len() - self.positions.len();
+        let ef = self.config.ef_search.max(k) + removed.min(k);
+        Ok(self
+            .search_layer(&query, &[closest], ef, 0)
+            .into_iter()
+            .filter(|candidate| !self.nodes[candidate.1].removed)
+            .take(k)
+            .map(|Candidate(score, position)| (self.nodes[position].id.as_str(), score))
+            .collect())
+    }
+
+    /// Restores an index saved without its vectors, from the vectors of its identifiers.
+    ///
+    /// # Returns
+    ///
+    /// The index, or `None` if it does not hold exactly the given identifiers, if one of its
+    /// removed vectors is missing, or if the vectors differ in dimension, in which case it
+    /// should be rebuilt.
+    pub fn restore<'a>(
+        mut self,
+        vectors: impl IntoIterator<Item = (&'a str, &'a [f32])>,
+    ) -> Option<Self> {
+        let vectors: HashMap<&str, &[f32]> = vectors.into_iter().collect();
+        let dimension = vectors.values().next().map(|vector| vector.len());
+        if vectors
+            .values()
+            .any(|vector| vector.is_empty() || Some(vector.len()) != dimension)
+        {
+            return None;
+        }
+        self.positions.clear();
+        for (position, node) in self.nodes.iter_mut().enumerate() {
+            if node.removed {
+                return None;
+            }
+            node.vector = self.config.metric.prepare(vectors.get(node.id.as_str())?);
+            if self.positions.insert(node.id.clone(), position).is_some() {
+                return None;
+            }
+        }
+        (self.positions.len() == vectors.len()).then_some(self)
+    }
+
+    /// Rebuilds the graph from its live vectors, dropping the removed ones.
+    pub fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.entry = None;
+        self.positions.clear();
+        for node in nodes.into_iter().filter(|node| !node.removed) {
+            self.link_node(node.id, &node.vector);
+        }
+    }
+
+    /// Draws the top layer of a node from its identifier, so that rebuilding an index gives
+    /// the same graph.
+    fn level(&self, id: &str) -> usize {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in id.bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        // SplitMix64 finalizer, to spread the bits of the hash.
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^= hash >> 31;
+
+        let uniform = ((hash >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let scale = 1.0 / (self.config.m as f64).ln();
+        ((-uniform.ln() * scale) as usize).min(MAX_LEVEL)
+    }
+
+    /// Adds `to` to the neighbours of `from`, keeping only the closest ones.
+    fn link(&mut self, from: usize, to: usize, layer: usize) {
+        let limit = if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        };
+        self.nodes[from].links[layer].push(to);
+        if self.nodes[from].links[layer].len() > limit {
+            let metric = self.config.metric;
+            let origin = &self.nodes[from].vector;
+            let mut scored: Vec<Candidate> = self.nodes[from].links[layer]
+                .iter()
+                .map(|&n| Candidate(metric.prepared_score(origin, &self.nodes[n].vector), n))
+                .collect();
+            by_similarity(&mut scored, from);
+            self.nodes[from].links[layer] = scored.into_iter().take(limit).map(|c| c.1).collect();
+        }
+    }
+
+    /// Moves from `start` to closer neighbours on a layer, until none is closer.
+    fn greedy(&self, query: &[f32], start: usize, layer: usize) -> usize {
+        let metric = self.config.metric;
+        let mut current = start;
+        let mut best = metric.prepared_score(query, &self.nodes[current].vector);
+        loop {
+            let mut moved = false;
+            for &neighbour in &self.nodes[current].links[layer] {
+                let score = metric.prepared_score(query, &self.nodes[neighbour].vector);
+                if score > best {
+                    best = score;
+                    current = neighbour;
+                    moved = true;
+                }
+            }
+            if !moved {
+                return current;
+            }
+        }
+    }
+
+    /// Returns the `ef` closest nodes to `query` reachable on a layer from `entries`, the
+    /// closest first.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[usize],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let metric = self.config.metric;
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &entry in entries {
+            let candidate = Candidate(
+                metric.prepared_score(query, &self.nodes[entry].vector),
+                entry,
+            );
+            candidates.push(candidate);
+            found.push(Reverse(candidate));
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = found.peek().map_or(f32::NEG_INFINITY, |Reverse(c)| c.0);
+            if candidate.0 < worst && found.len() >= ef {
+                break;
+            }
+            for &neighbour in &self.nodes[candidate.1].links[layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let score = metric.prepared_score(query, &self.nodes[neighbour].vector);
+                let worst = found.peek().map_or(f32::NEG_INFINITY, |Reverse(c)| c.0);
+                if found.len() < ef || score > worst {
+                    candidates.push(Candidate(score, neighbour));
+                    found.push(Reverse(Candidate(score, neighbour)));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut found: Vec<Candidate> = found.into_iter().map(|Reverse(c)| c).collect();
+        found.sort_unstable_by(|a, b| b.cmp(a));
+        found
+    }
+}
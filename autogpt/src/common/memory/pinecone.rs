@@ -0,0 +1,258 @@
+//! Store on a Pinecone index.
+
+use crate::common::memory::{MemoryMatch, MemoryRecord};
+use crate::common::utils::Communication;
+use crate::traits::memory::MemoryStore;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derivative::Derivative;
+use pinecone_sdk::models::{Kind, Metadata, Namespace, Value, Vector};
+use pinecone_sdk::pinecone::PineconeClientConfig;
+use pinecone_sdk::pinecone::data::Index;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::env::var;
+use tokio::sync::{Mutex, MutexGuard, OnceCell};
+
+/// Dimension of the index, to which embeddings are padded or truncated.
+pub const DEFAULT_DIMENSION: usize = 1024;
+
+/// A `MemoryStore` on a Pinecone index, whose namespaces are Pinecone namespaces. The
+/// connection to the index is made on first use, and kept.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct PineconeStore {
+    /// URL of the index.
+    pub index_url: String,
+    /// Dimension of the index.
+    pub dimension: usize,
+    #[derivative(Debug = "ignore")]
+    api_key: String,
+    #[derivative(Debug = "ignore")]
+    index: OnceCell<Mutex<Index>>,
+}
+
+impl PineconeStore {
+    /// Creates a store on the index at `index_url`, of `DEFAULT_DIMENSION` dimensions.
+    pub fn new(api_key: impl Into<String>, index_url: impl Into<String>) -> Self {
+        Self {
+            index_url: index_url.into(),
+            dimension: DEFAULT_DIMENSION,
+            api_key: api_key.into(),
+            index: OnceCell::new(),
+        }
+    }
+
+    /// Creates a store from `PINECONE_API_KEY` and `PINECONE_INDEX_URL`, or returns `None`
+    /// if either is unset.
+    pub fn from_env() -> Option<Self> {
+        let api_key = var("PINECONE_API_KEY").ok().filter(|key| !key.is_empty())?;
+        let index_url = var("PINECONE_INDEX_URL")
+            .ok()
+            .filter(|url| !url.is_empty())?;
+        Some(Self::new(api_key, index_url))
+    }
+
+    /// Sets the dimension of the index.
+    pub fn dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// Returns the index, connecting to it first if needed.
+    async fn index(&self) -> Result<MutexGuard<'_, Index>> {
+        let index = self
+            .index
+            .get_or_try_init(|| async {
+                let config = PineconeClientConfig {
+                    api_key: Some(self.api_key.clone()),
+                    ..Default::default()
+                };
+                let pinecone = config
+                    .client()
+                    .map_err(|err| anyhow!("Failed to create the Pinecone client: {err:?}"))?;
+                let index = pinecone.index(&self.index_url).await.map_err(|err| {
+                    anyhow!(
+                        "Failed to connect to the Pinecone index {}: {err:?}",
+                        self.index_url
+                    )
+                })?;
+                Ok::<_, anyhow::Error>(Mutex::new(index))
+            })
+            .await?;
+        Ok(index.lock().await)
+    }
+
+    /// Pads or truncates an embedding to the dimension of the index.
+    fn values(&self, vector: &[f32]) -> Vec<f32> {
+        let mut values = vector.to_vec();
+        values.resize(self.dimension, 0.0);
+        values
+    }
+}
+
+/// Builds a string field of the metadata of a vector.
+fn field(value: String) -> Value {
+    Value {
+        kind: Some(Kind::StringValue(value)),
+    }
+}
+
+/// Reads a string field of the metadata of a vector.
+fn text(metadata: Option<&Metadata>, name: &str) -> Option<String> {
+    match metadata?.fields.get(name)?.kind.as_ref()? {
+        Kind::StringValue(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Rebuilds a record from a vector and its metadata. Vectors saved before records had a
+/// creation time are dated from the epoch.
+fn record(id: String, vector: Vec<f32>, metadata: Option<&Metadata>) -> MemoryRecord {
+    MemoryRecord {
+        id,
+        vector,
+        communication: Communication {
+            role: Cow::Owned(text(metadata, "role").unwrap_or_else(|| "unknown".to_string())),
+            content: Cow::Owned(text(metadata, "content").unwrap_or_default()),
+        },
+        created_at: date(metadata, "created_at").unwrap_or_default(),
+        expires_at: date(metadata, "expires_at"),
+    }
+}
+
+/// Reads a date field of the metadata of a vector.
+fn date(metadata: Option<&Metadata>, name: &str) -> Option<DateTime<Utc>> {
+    let date = DateTime::parse_from_rfc3339(&text(metadata, name)?).ok()?;
+    Some(date.with_timezone(&Utc))
+}
+
+#[async_trait]
+impl MemoryStore for PineconeStore {
+    async fn upsert(&self, namespace: &str, records: Vec<MemoryRecord>) -> Result<()> {
+        let vectors: Vec<Vector> = records
+            .into_iter()
+            .map(|record| {
+                let mut fields = BTreeMap::from([
+                    ("role".to_string(), field(record.communication.role.into())),
+                    (
+                        "content".to_string(),
+                        field(record.communication.content.into()),
+                    ),
+                    (
+                        "created_at".to_string(),
+                        field(record.created_at.to_rfc3339()),
+                    ),
+                ]);
+                if let Some(expires_at) = record.expires_at {
+                    fields.insert("expires_at".to_string(), field(expires_at.to_rfc3339()));
+                }
+                Vector {
+                    values: self.values(&record.vector),
+                    id: record.id,
+                    sparse_values: None,
+                    metadata: Some(Metadata { fields }),
+                }
+            })
+            .collect();
+        self.index()
+            .await?
+            .upsert(&vectors, &namespace.to_string().into())
+            .await
+            .map_err(|err| {
+                anyhow!("Upsert failed, check `PINECONE_INDEX_URL` and trial limits: {err:?}")
+            })?;
+        Ok(())
+    }
+
+    async fn query(&self, namespace: &str, vector: &[f32], k: usize) -> Result<Vec<MemoryMatch>> {
+        let response = self
+            .index()
+            .await?
+            .query_by_value(
+                self.values(vector),
+                None,
+                k as u32,
+                &namespace.to_string().into(),
+                None,
+                Some(true),
+                Some(true),
+            )
+            .await
+            .map_err(|err| anyhow!("Query failed: {err:?}"))?;
+        let (expired, matches): (Vec<MemoryMatch>, Vec<MemoryMatch>) = response
+            .matches
+            .into_iter()
+            .map(|scored| MemoryMatch {
+                score: scored.score,
+                record: record(scored.id, scored.values, scored.metadata.as_ref()),
+            })
+            .partition(|found| found.record.is_expired());
+        if !expired.is_empty() {
+            let ids: Vec<String> = expired.into_iter().map(|found| found.record.id).collect();
+            self.delete(namespace, &ids).await?;
+        }
+        Ok(matches)
+    }
+
+    async fn list(&self, namespace: &str) -> Result<Vec<MemoryRecord>> {
+        let name: Namespace = namespace.to_string().into();
+        let mut index = self.index().await?;
+        let list = index
+            .list(&name, None, None, None)
+            .await
+            .map_err(|err| anyhow!("Listing failed: {err:?}"))?;
+        let ids: Vec<&str> = list.vectors.iter().map(|v| v.id.as_str()).collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let fetched = index
+            .fetch(&ids, &name)
+            .await
+            .map_err(|err| anyhow!("Fetch failed: {err:?}"))?;
+
+        let (expired, mut records): (Vec<MemoryRecord>, Vec<MemoryRecord>) = fetched
+            .vectors
+            .into_values()
+            .map(|vector| record(vector.id, vector.values, vector.metadata.as_ref()))
+            .partition(MemoryRecord::is_expired);
+        drop(index);
+        if !expired.is_empty() {
+            let ids: Vec<String> = expired.into_iter().map(|record| record.id).collect();
+            self.delete(namespace, &ids).await?;
+        }
+        records.sort_by_key(|record| record.created_at);
+        Ok(records)
+    }
+
+    async fn delete(&self, namespace: &str, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        self.index()
+            .await?
+            .delete_by_id(&ids, &namespace.to_string().into())
+            .await
+            .map_err(|err| anyhow!("Delete failed: {err:?}"))?;
+        Ok(())
+    }
+
+    async fn delete_namespace(&self, namespace: &str) -> Result<()> {
+        let namespace: Namespace = namespace.to_string().into();
+        let mut index = self.index().await?;
+        // Pinecone reports missing namespaces as errors.
+        let list = index
+            .list(&namespace, None, Some(1), None)
+            .await
+            .map_err(|err| anyhow!("Listing failed: {err:?}"))?;
+        if !list.vectors.is_empty() {
+            index
+                .delete_all(&namespace)
+                .await
+                .map_err(|err| anyhow!("Delete failed: {err:?}"))?;
+        }
+        Ok(())
+    }
+}
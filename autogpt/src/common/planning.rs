@@ -0,0 +1,258 @@
+//! # Goal planning.
+//!
+//! Helpers turning the decomposition of an objective proposed by the model into a `Planner`
+//! whose goals form a directed acyclic graph: each `Goal` lists the positions of the goals
+//! that must be completed before it. Goals are worked on in topological order, and among the
+//! goals ready at the same time, by ascending `priority`.
+//!
+//! The model proposes goals as a `GoalPlan`, numbering them from 1 and referring to their
+//! dependencies by number. Plans referring to missing goals or containing a cycle are
+//! rejected. When a goal fails, `Planner::replan` keeps the completed goals and replaces all
+//! the others with a new proposal.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::planning::{GoalPlan, PlannedGoal};
+//! use autogpt::common::utils::Planner;
+//!
+//! let plan = GoalPlan {
+//!     goals: vec![
+//!         PlannedGoal::new("Write the tests", 2, vec![2]),
+//!         PlannedGoal::new("Design the API", 1, vec![]),
+//!         PlannedGoal::new("Write the docs", 1, vec![2]),
+//!     ],
+//! };
+//! let mut planner = Planner::from_plan(plan).unwrap();
+//! assert_eq!(planner.topological_order().unwrap(), vec![1, 2, 0]);
+//!
+//! assert_eq!(planner.next_goal(), Some(1));
+//! planner.complete(1);
+//! assert_eq!(planner.next_goal(), Some(2));
+//!
+//! let cycle = GoalPlan {
+//!     goals: vec![PlannedGoal::new("A", 1, vec![2]), PlannedGoal::new("B", 1, vec![1])],
+//! };
+//! assert!(Planner::from_plan(cycle).is_err());
+//! ```
+
+use crate::common::utils::{Goal, Planner};
+use anyhow::{Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A goal proposed by the model.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PlannedGoal {
+    /// What the goal achieves, as a task that can be carried out on its own.
+    pub description: String,
+    /// Goals with lower numbers are worked on first among the goals that are ready.
+    #[serde(default)]
+    pub priority: u8,
+    /// Numbers of the goals of the plan, counted from 1, that must be completed first.
+    #[serde(default)]
+    pub depends_on: Vec<usize>,
+}
+
+/// A decomposition of an objective proposed by the model.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GoalPlan {
+    /// The goals, in any order.
+    pub goals: Vec<PlannedGoal>,
+}
+
+impl PlannedGoal {
+    /// Creates a goal depending on the goals numbered `depends_on`, counted from 1.
+    pub fn new(description: impl Into<String>, priority: u8, depends_on: Vec<usize>) -> Self {
+        Self {
+            description: description.into(),
+            priority,
+            depends_on,
+        }
+    }
+}
+
+impl Planner {
+    /// Builds a planner from a plan proposed by the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plan is empty, if a goal depends on itself or on a goal that
+    /// does not exist, or if the dependencies contain a cycle.
+    pub fn from_plan(plan: GoalPlan) -> Result<Self> {
+        if plan.goals.is_empty() {
+            bail!("The plan has no goals.");
+        }
+        let planner = Self {
+            current_plan: goals(plan, 0)?,
+        };
+        planner.topological_order()?;
+        Ok(planner)
+    }
+
+    /// Keeps the completed goals and replaces the others with a new plan, whose dependencies
+    /// refer to its own goals. Completed goals are always satisfied, so the new goals need not
+    /// refer to them.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `from_plan`, leaving the planner unchanged.
+    pub fn replan(&mut self, plan: GoalPlan) -> Result<()> {
+        if plan.goals.is_empty() {
+            bail!("The plan has no goals.");
+        }
+        let done: Vec<Goal> = self
+            .current_plan
+            .iter()
+            .filter(|goal| goal.completed)
+            .map(|goal| Goal {
+                dependencies: Vec::new(),
+                ..goal.clone()
+            })
+            .collect();
+        let offset = done.len();
+
+        let mut replanned = Self { current_plan: done };
+        replanned.current_plan.extend(goals(plan, offset)?);
+        replanned.topological_order()?;
+        *self = replanned;
+        Ok(())
+    }
+
+    /// Returns the positions of all the goals in the order they are worked on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the goals left out if the dependencies contain a cycle or
+    /// refer to goals that do not exist.
+    pub fn topological_order(&self) -> Result<Vec<usize>> {
+        let goals = &self.current_plan;
+        let mut missing = vec![0; goals.len()];
+        let mut dependents = vec![Vec::new(); goals.len()];
+        for (i, goal) in goals.iter().enumerate() {
+            for &dependency in &goal.dependencies {
+                if dependency >= goals.len() || dependency == i {
+                    bail!(
+                        "Goal {} depends on goal {}, which is not another goal of the plan.",
+                        i + 1,
+                        dependency + 1
+                    );
+                }
+                missing[i] += 1;
+                dependents[dependency].push(i);
+            }
+        }
+
+        let mut ready: BinaryHeap<_> = (0..goals.len())
+            .filter(|&i| missing[i] == 0)
+            .map(|i| Reverse((goals[i].priority, i)))
+            .collect();
+        let mut order = Vec::with_capacity(goals.len());
+        while let Some(Reverse((_, i))) = ready.pop() {
+            order.push(i);
+            for &dependent in &dependents[i] {
+                missing[dependent] -= 1;
+                if missing[dependent] == 0 {
+                    ready.push(Reverse((goals[dependent].priority, dependent)));
+                }
+            }
+        }
+
+        if order.len() < goals.len() {
+            let cycle: Vec<String> = (0..goals.len())
+                .filter(|&i| missing[i] > 0)
+                .map(|i| (i + 1).to_string())
+                .collect();
+            bail!(
+                "The dependencies of goals {} form a cycle.",
+                cycle.join(", ")
+            );
+        }
+        Ok(order)
+    }
+
+    /// Returns the position of the next goal to work on: the uncompleted goal whose
+    /// dependencies are all completed with the lowest priority, if any.
+    pub fn next_goal(&self) -> Option<usize> {
+        self.current_plan
+            .iter()
+            .enumerate()
+            .filter(|(_, goal)| {
+                !goal.completed
+                    && goal.dependencies.iter().all(|&dependency| {
+                        self.current_plan
+                            .get(dependency)
+                            .is_some_and(|dependency| dependency.completed)
+                    })
+            })
+            .min_by_key(|(i, goal)| (goal.priority, *i))
+            .map(|(i, _)| i)
+    }
+
+    /// Marks the goal at `position` as completed.
+    pub fn complete(&mut self, position: usize) {
+        if let Some(goal) = self.current_plan.get_mut(position) {
+            goal.completed = true;
+        }
+    }
+
+    /// Returns whether every goal is completed.
+    pub fn is_complete(&self) -> bool {
+        self.current_plan.iter().all(|goal| goal.completed)
+    }
+
+    /// Renders the plan for a prompt, one numbered goal per line, with its state and
+    /// dependencies.
+    pub fn describe(&self) -> String {
+        self.current_plan
+            .iter()
+            .enumerate()
+            .map(|(i, goal)| {
+                let mark = if goal.completed { "x" } else { " " };
+                let mut line = format!("{}. [{mark}] {}", i + 1, goal.description);
+                if !goal.dependencies.is_empty() {
+                    let dependencies: Vec<String> = goal
+                        .dependencies
+                        .iter()
+                        .map(|dependency| (dependency + 1).to_string())
+                        .collect();
+                    line.push_str(&format!(" (after {})", dependencies.join(", ")));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Converts proposed goals to goals placed after `offset` others, with dependencies counted
+/// from 0.
+fn goals(plan: GoalPlan, offset: usize) -> Result<Vec<Goal>> {
+    let count = plan.goals.len();
+    plan.goals
+        .into_iter()
+        .enumerate()
+        .map(|(i, goal)| {
+            let mut dependencies = Vec::with_capacity(goal.depends_on.len());
+            for number in goal.depends_on {
+                if number == 0 || number > count || number == i + 1 {
+                    bail!(
+                        "Goal {} depends on goal {number}, which is not another goal of the plan.",
+                        i + 1
+                    );
+                }
+                if !dependencies.contains(&(offset + number - 1)) {
+                    dependencies.push(offset + number - 1);
+                }
+            }
+            Ok(Goal {
+                description: goal.description,
+                priority: goal.priority,
+                completed: false,
+                dependencies,
+            })
+        })
+        .collect()
+}
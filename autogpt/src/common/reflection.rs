@@ -0,0 +1,223 @@
+//! # Reflection.
+//!
+//! Helpers behind `AgentGPT::reflect`, which runs the evaluator of an agent's `Reflection`
+//! and writes the lessons it returns into the reflection logs and the memory of the agent, so
+//! that they are part of the conversation of its next attempt.
+//!
+//! - `Evaluation` is the result of an evaluator: a summary and actionable lessons.
+//! - `Reflection::new`, `Reflection::from_fn` and `Reflection::from_async` build a
+//!   reflection from an evaluator, a plain function or an async closure.
+//! - `Critic` is the built-in model-backed evaluator: it shows the model the objective, the
+//!   plan, the recent logs and the last outputs of the agent, and asks for a structured
+//!   critique, repaired like any other structured output.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::agents::agent::AgentGPT;
+//! use autogpt::common::reflection::Critic;
+//! use autogpt::common::utils::Reflection;
+//!
+//! let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+//! agent.reflection = Some(Reflection::new(Critic::new().with_outputs(2)));
+//! ```
+
+use crate::agents::agent::{AgentGPT, Metered};
+use crate::common::config::LlmConfig;
+use crate::common::structured;
+use crate::common::utils::{ClientType, Communication, Reflection};
+use crate::traits::agent::Agent;
+use crate::traits::evaluator::{AsyncEvaluator, Evaluator};
+use crate::traits::provider::LlmProvider;
+use anyhow::Result;
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+const CRITIC_PROMPT: &str = r#"
+You review the work of an agent before its next attempt at its objective.
+
+You are given the objective of the agent, its plan, its recent activity and its last outputs. Judge the outputs strictly against the objective:
+- In `summary`, state in one or two sentences how far the outputs meet the objective and what is wrong or missing.
+- In `lessons`, give at most three concrete, actionable instructions that would make the next attempt better, such as a bug to fix, a requirement that was ignored or a step to take first.
+- Each lesson is one imperative sentence the agent can follow without seeing this review.
+- If the outputs fully meet the objective, leave `lessons` empty.
+"#;
+
+/// Number of outputs reviewed by a `Critic` when none is configured.
+pub const DEFAULT_CRITIC_OUTPUTS: usize = 3;
+
+/// Number of reflection logs shown to a `Critic`.
+const CRITIC_LOGS: usize = 10;
+
+/// The result of an evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Evaluation {
+    /// How far the recent work meets the objective.
+    pub summary: String,
+    /// Concrete instructions that would make the next attempt better.
+    #[serde(default)]
+    pub lessons: Vec<String>,
+}
+
+impl Evaluation {
+    /// Creates an evaluation without lessons.
+    pub fn new(summary: impl Into<String>) -> Self {
+        Self {
+            summary: summary.into(),
+            lessons: Vec::new(),
+        }
+    }
+}
+
+impl Reflection {
+    /// Creates a reflection evaluated by `evaluator`.
+    pub fn new(evaluator: impl Evaluator + 'static) -> Self {
+        Self {
+            recent_logs: Vec::new(),
+            evaluator: Arc::new(evaluator),
+        }
+    }
+
+    /// Creates a reflection evaluated by a function returning a summary.
+    pub fn from_fn(evaluate: fn(&dyn Agent) -> Cow<'static, str>) -> Self {
+        Self::new(evaluate)
+    }
+
+    /// Creates a reflection evaluated by an async closure, which receives a snapshot of the
+    /// agent.
+    pub fn from_async<F, Fut>(evaluate: F) -> Self
+    where
+        F: Fn(AgentGPT) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Evaluation>> + Send + 'static,
+    {
+        Self::new(AsyncEvaluator(evaluate))
+    }
+}
+
+/// Evaluator asking the model to critique the last outputs of the agent against its
+/// objective.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Critic {
+    /// Number of the last outputs of the agent shown to the model.
+    pub outputs: usize,
+    /// Settings of the critique requests, over those of the agent.
+    pub config: LlmConfig,
+}
+
+impl Default for Critic {
+    fn default() -> Self {
+        Self {
+            outputs: DEFAULT_CRITIC_OUTPUTS,
+            config: LlmConfig::default(),
+        }
+    }
+}
+
+impl Critic {
+    /// Creates a critic reviewing the last `DEFAULT_CRITIC_OUTPUTS` outputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of the last outputs of the agent shown to the model.
+    pub fn with_outputs(mut self, outputs: usize) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    /// Sets the settings of the critique requests, such as a cheaper model.
+    pub fn with_config(mut self, config: LlmConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Builds the request describing the agent to the model.
+    fn request(&self, agent: &AgentGPT) -> String {
+        let mut request = format!("Objective: {}\n", agent.objective);
+        if let Some(planner) = &agent.planner
+            && !planner.current_plan.is_empty()
+        {
+            request.push_str(&format!("\nPlan:\n{}\n", planner.describe()));
+        }
+        if let Some(reflection) = &agent.reflection
+            && !reflection.recent_logs.is_empty()
+        {
+            let start = reflection.recent_logs.len().saturating_sub(CRITIC_LOGS);
+            request.push_str("\nRecent activity:\n");
+            for log in &reflection.recent_logs[start..] {
+                request.push_str(&format!("- {log}\n"));
+            }
+        }
+
+        let outputs: Vec<&Communication> = agent
+            .memory
+            .iter()
+            .rev()
+            .filter(|message| message.role == "assistant")
+            .take(self.outputs)
+            .collect();
+        request.push_str("\nLast outputs, most recent last:\n");
+        if outputs.is_empty() {
+            request.push_str("None yet.\n");
+        }
+        for (i, output) in outputs.iter().rev().enumerate() {
+            request.push_str(&format!("--- Output {} ---\n{}\n", i + 1, output.content));
+        }
+        request
+    }
+}
+
+#[async_trait]
+impl Evaluator for Critic {
+    async fn evaluate(&self, agent: &AgentGPT, client: &mut ClientType) -> Result<Evaluation> {
+        let schema = structured::schema::<Evaluation>();
+        let config = agent.llm.clone().merge(&self.config).schema(schema.clone());
+        let mut messages = vec![
+            Communication::system(CRITIC_PROMPT.trim().to_string()),
+            Communication::user(self.request(agent)),
+            Communication::user(structured::instructions(&schema)),
+        ];
+        let repairs = structured::repairs();
+        let mut repair = 0;
+        loop {
+            let mut metered = Metered {
+                client: &mut *client,
+                retry: agent.retry,
+                usage: &agent.usage,
+            };
+            let fitted = agent.budget.fit(&mut metered, &messages, &config).await?;
+            let mut attempt = 1;
+            let completion = loop {
+                match client.completion(&fitted, &config).await {
+                    Ok(completion) => break completion,
+                    Err(err) => {
+                        attempt = agent.retry.backoff("reflect", attempt, err).await?;
+                    }
+                }
+            };
+            agent
+                .usage
+                .record_completion(client.name(), &fitted, &completion);
+
+            match structured::parse::<Evaluation>(&completion.text) {
+                Ok(evaluation) => return Ok(evaluation),
+                Err(err) if repair < repairs => {
+                    repair += 1;
+                    warn!("Critique does not parse ({repair}/{repairs} repairs): {err}");
+                    messages.push(Communication::assistant(completion.text));
+                    messages.push(Communication::user(structured::repair(&err)));
+                }
+                Err(err) => {
+                    return Err(err.context(format!(
+                        "Critique still does not parse after {repairs} repairs"
+                    )));
+                }
+            }
+        }
+    }
+}
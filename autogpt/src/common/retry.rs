@@ -0,0 +1,340 @@
+//! # Retries.
+//!
+//! Rate limits and transient server errors are common with hosted models, so every model call
+//! made by an agent goes through a `RetryPolicy`. A failed call is retried when the error is
+//! transient:
+//!
+//! - A `ProviderError` with status 408, 425, 429 or 5xx.
+//! - A `reqwest` timeout or connection error.
+//! - An SDK error whose message mentions a rate limit, an overloaded model or a timeout.
+//!
+//! The delay before each retry doubles from `initial_delay` up to `max_delay`, with random
+//! jitter so that concurrent agents do not retry in lockstep. A `Retry-After` header sent by the
+//! provider takes precedence over the computed delay, but is also capped at `max_delay`. Retries
+//! are logged with `tracing` rather than added to the conversation.
+//!
+//! The policy of an agent is read from the environment:
+//!
+//! - `AUTOGPT_RETRY_ATTEMPTS`: Total number of attempts per call, defaults to 3.
+//! - `AUTOGPT_RETRY_INITIAL_DELAY_MS`: Delay before the first retry, defaults to 1000.
+//! - `AUTOGPT_RETRY_MAX_DELAY_MS`: Upper bound on every delay, defaults to 60000.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::retry::{ProviderError, RetryPolicy};
+//! use std::time::Duration;
+//!
+//! let policy = RetryPolicy::default()
+//!     .initial_delay(Duration::from_millis(100))
+//!     .jitter(false);
+//!
+//! let limited = ProviderError::new(429, "Too many requests").retry_after(Duration::from_secs(2));
+//! let invalid = ProviderError::new(400, "Invalid request");
+//!
+//! assert_eq!(policy.next_delay(1, &limited.into()), Some(Duration::from_secs(2)));
+//! assert_eq!(policy.next_delay(1, &invalid.into()), None);
+//! ```
+
+use anyhow::{Error, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Response;
+use std::collections::hash_map::RandomState;
+use std::env::var;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// Error messages of SDK clients that indicate a transient failure.
+const TRANSIENT_MARKERS: &[&str] = &[
+    "429",
+    "too many requests",
+    "rate limit",
+    "resource_exhausted",
+    "overloaded",
+    "internal server error",
+    "bad gateway",
+    "service unavailable",
+    "gateway timeout",
+    "timed out",
+    "connection reset",
+];
+
+/// Error returned by a provider API with an unsuccessful HTTP status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderError {
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// The delay requested by the `Retry-After` header, if any.
+    pub retry_after: Option<Duration>,
+    /// Description of the failed request, including the response body.
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Creates an error for the given status code.
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            retry_after: None,
+            message: message.into(),
+        }
+    }
+
+    /// Sets the delay requested by the provider before the next attempt.
+    pub fn retry_after(mut self, delay: Duration) -> Self {
+        self.retry_after = Some(delay);
+        self
+    }
+
+    /// Builds an error from an unsuccessful response, reading its `Retry-After` header and body.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Prefix of the error message, such as `"Local: Failed to generate content"`.
+    /// * `response` - The response to describe.
+    pub async fn from_response(context: &str, response: Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = response.text().await.unwrap_or_default();
+
+        Self {
+            status: status.as_u16(),
+            retry_after,
+            message: format!("{context}: {status}: {}", body.trim()),
+        }
+    }
+
+    /// Returns whether the request may succeed if it is sent again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self.status, 408 | 425 | 429 | 500..=599)
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Returns the response if its status is successful, or a `ProviderError` describing it.
+///
+/// # Arguments
+///
+/// * `context` - Prefix of the error message.
+/// * `response` - The response to check.
+pub async fn check_status(context: &str, response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(ProviderError::from_response(context, response).await.into())
+    }
+}
+
+/// Parses a `Retry-After` header value, given either in seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds >= 0.0 && seconds.is_finite()).then(|| Duration::from_secs_f64(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns whether a failed model call is worth retrying.
+pub fn is_transient(err: &Error) -> bool {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<ProviderError>() {
+            return err.is_transient();
+        }
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            if err.is_timeout() || err.is_connect() {
+                return true;
+            }
+            if let Some(status) = err.status() {
+                return ProviderError::new(status.as_u16(), "").is_transient();
+            }
+        }
+    }
+
+    let message = format!("{err:#}").to_lowercase();
+    TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Returns the delay requested by the provider for a failed call, if any.
+pub fn retry_after(err: &Error) -> Option<Duration> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ProviderError>())
+        .and_then(|err| err.retry_after)
+}
+
+/// How often and how fast failed model calls are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts per call, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Upper bound on every delay, including the one requested by a `Retry-After` header.
+    pub max_delay: Duration,
+    /// Whether to randomize each delay between half and all of its value.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+
+    /// Builds a policy from the `AUTOGPT_RETRY_*` environment variables, falling back to the
+    /// defaults for unset or invalid values.
+    pub fn from_env() -> Self {
+        let number = |name: &str| var(name).ok().and_then(|value| value.trim().parse().ok());
+        let default = Self::default();
+
+        Self {
+            max_attempts: number("AUTOGPT_RETRY_ATTEMPTS")
+                .map(|attempts: u64| attempts.clamp(1, u32::MAX as u64) as u32)
+                .unwrap_or(default.max_attempts),
+            initial_delay: number("AUTOGPT_RETRY_INITIAL_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.initial_delay),
+            max_delay: number("AUTOGPT_RETRY_MAX_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+            jitter: default.jitter,
+        }
+    }
+
+    /// Sets the total number of attempts per call. Values below 1 are treated as 1.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Sets the upper bound on every delay.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Enables or disables random jitter.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the backoff delay after the given failed attempt, starting at 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay);
+
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            delay.mul_f64(0.5 + (random as f64 / u64::MAX as f64) / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /// Returns how long to wait before retrying a call whose given attempt failed with `err`,
+    /// or `None` if the call should not be retried. A `Retry-After` longer than `max_delay` is
+    /// cut to it.
+    pub fn next_delay(&self, attempt: u32, err: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_transient(err) {
+            return None;
+        }
+        Some(match retry_after(err) {
+            Some(delay) => delay.min(self.max_delay),
+            None => self.delay(attempt),
+        })
+    }
+
+    /// Handles a failed attempt of a model call: either waits out the backoff and returns the
+    /// number of the next attempt, or gives up and returns the error.
+    ///
+    /// Each retry, and the final failure of a call that was retried, is logged as a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - Name of the call used in the log, such as `"generate"`.
+    /// * `attempt` - The number of the failed attempt, starting at 1.
+    /// * `err` - The error the attempt failed with.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use autogpt::common::retry::RetryPolicy;
+    /// use autogpt::common::utils::ClientType;
+    /// use autogpt::providers::mock::MockClient;
+    /// use autogpt::traits::provider::LlmProvider;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ClientType::Mock(MockClient::new().then("Hello!"));
+    /// let retry = RetryPolicy::from_env();
+    ///
+    /// let mut attempt = 1;
+    /// let reply = loop {
+    ///     match client.chat("Say hello").await {
+    ///         Ok(reply) => break reply,
+    ///         Err(err) => attempt = retry.backoff("generate", attempt, err).await?,
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(reply, "Hello!");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn backoff(&self, action: &str, attempt: u32, err: Error) -> Result<u32> {
+        let Some(delay) = self.next_delay(attempt, &err) else {
+            if attempt > 1 {
+                warn!("{action} failed after {attempt} attempts: {err}");
+            }
+            return Err(err);
+        };
+
+        warn!(
+            "{action} attempt {attempt}/{} failed, retrying in {delay:?}: {err}",
+            self.max_attempts
+        );
+
+        tokio::time::sleep(delay).await;
+        Ok(attempt + 1)
+    }
+}
@@ -0,0 +1,473 @@
+//! # Task scheduling.
+//!
+//! This module fires the tasks of a `TaskScheduler` on their agent when they are due:
+//!
+//! - `TaskScheduler` keeps its tasks sorted by time and hands out the due ones with
+//!   `take_due`, putting recurring tasks back at their next time.
+//! - `Recurrence` computes the next time of a task repeating on a cron expression or at a
+//!   fixed interval. Runs missed while nothing was running are skipped, not caught up on.
+//! - `Scheduler` is the runtime: it owns a schedule and the agents it runs tasks on, exposes
+//!   `add`, `add_after`, `add_recurring`, `cancel` and `list`, and with `run` executes every
+//!   task once it is due. With `with_store`, the schedule is saved to a JSON file after every
+//!   change and loaded back on start, so it survives restarts.
+//!
+//! Cron expressions have five fields, from minutes to days of the week, or six or seven with
+//! leading seconds and trailing years. Days of the week are best given by name, such as
+//! `Mon-Fri`, since they are numbered from 1 for Sunday.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::utils::{Recurrence, Task, TaskScheduler};
+//! use chrono::{Duration, TimeZone, Utc};
+//!
+//! let now = Utc.with_ymd_and_hms(2025, 1, 6, 8, 0, 0).unwrap();
+//! let weekdays = Recurrence::cron("0 9 * * Mon-Fri").unwrap();
+//! assert_eq!(
+//!     weekdays.next_after(now).unwrap(),
+//!     Utc.with_ymd_and_hms(2025, 1, 6, 9, 0, 0).unwrap()
+//! );
+//!
+//! let mut scheduler = TaskScheduler::default();
+//! let report = scheduler.add("ReporterGPT", now, Task::from_payload("Report"));
+//! let backup = scheduler.add_recurring(
+//!     "BackupGPT",
+//!     Recurrence::every(std::time::Duration::from_secs(3600)),
+//!     Task::from_payload("Back up"),
+//!     now,
+//! ).unwrap();
+//!
+//! let due = scheduler.take_due(now + Duration::hours(1));
+//! assert_eq!(due.iter().map(|task| task.id).collect::<Vec<_>>(), [report, backup]);
+//! assert_eq!(scheduler.list().len(), 1);
+//! assert_eq!(scheduler.next_time(), Some(now + Duration::hours(2)));
+//! ```
+
+use crate::common::utils::{Recurrence, ScheduledTask, Task, TaskScheduler};
+use crate::traits::agent::Agent;
+use crate::traits::composite::AgentFunctions;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use cron::Schedule;
+use futures::future::join_all;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, sleep};
+use tracing::{debug, error, info};
+
+/// An agent shared between the runtime and the rest of the program.
+pub type SharedAgent = Arc<Mutex<Box<dyn AgentFunctions>>>;
+
+/// Longest time the runtime sleeps before checking the schedule again, so that changes of the
+/// system clock are noticed.
+pub const MAX_IDLE: Duration = Duration::from_secs(60);
+
+impl Recurrence {
+    /// Creates a recurrence following a cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression does not parse.
+    pub fn cron(expression: impl Into<String>) -> Result<Self> {
+        let recurrence = Self::Cron(expression.into());
+        recurrence.next_after(Utc::now())?;
+        Ok(recurrence)
+    }
+
+    /// Creates a recurrence running every `interval`, rounded up to the second.
+    pub fn every(interval: std::time::Duration) -> Self {
+        let seconds = interval.as_secs() + u64::from(interval.subsec_nanos() > 0);
+        Self::Every(seconds.max(1))
+    }
+
+    /// Returns the first time strictly after `time` at which the task runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cron expression does not parse or never matches again, or if
+    /// the interval overflows.
+    pub fn next_after(&self, time: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        match self {
+            Self::Cron(expression) => {
+                let fields = expression.split_whitespace().count();
+                let expression = if fields == 5 {
+                    format!("0 {expression}")
+                } else {
+                    expression.clone()
+                };
+                let schedule = Schedule::from_str(&expression)
+                    .map_err(|err| anyhow!("Invalid cron expression `{expression}`: {err}"))?;
+                schedule.after(&time).next().ok_or_else(|| {
+                    anyhow!("The cron expression `{expression}` never matches again.")
+                })
+            }
+            Self::Every(seconds) => i64::try_from(*seconds)
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .and_then(TimeDelta::try_seconds)
+                .and_then(|interval| time.checked_add_signed(interval))
+                .ok_or_else(|| anyhow!("Invalid interval of {seconds} seconds.")),
+        }
+    }
+}
+
+impl TaskScheduler {
+    /// Schedules a task on the agent at `agent` position, once at `time`, and returns its id.
+    pub fn add(
+        &mut self,
+        agent: impl Into<Cow<'static, str>>,
+        time: DateTime<Utc>,
+        task: Task,
+    ) -> u64 {
+        self.insert(ScheduledTask {
+            id: 0,
+            agent: agent.into(),
+            time,
+            task,
+            recurrence: None,
+        })
+    }
+
+    /// Schedules a task repeating after `now` on the agent at `agent` position, and returns its
+    /// id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recurrence never runs after `now`.
+    pub fn add_recurring(
+        &mut self,
+        agent: impl Into<Cow<'static, str>>,
+        recurrence: Recurrence,
+        task: Task,
+        now: DateTime<Utc>,
+    ) -> Result<u64> {
+        Ok(self.insert(ScheduledTask {
+            id: 0,
+            agent: agent.into(),
+            time: recurrence.next_after(now)?,
+            task,
+            recurrence: Some(recurrence),
+        }))
+    }
+
+    /// Removes a task and returns it, if it is scheduled.
+    pub fn cancel(&mut self, id: u64) -> Option<ScheduledTask> {
+        let position = self.scheduled_tasks.iter().position(|task| task.id == id)?;
+        Some(self.scheduled_tasks.remove(position))
+    }
+
+    /// Returns the scheduled tasks, the earliest first.
+    pub fn list(&self) -> &[ScheduledTask] {
+        &self.scheduled_tasks
+    }
+
+    /// Returns the time of the earliest task.
+    pub fn next_time(&self) -> Option<DateTime<Utc>> {
+        self.scheduled_tasks.iter().map(|task| task.time).min()
+    }
+
+    /// Removes and returns the tasks due at `now`, the earliest first. Recurring tasks are
+    /// scheduled again at their first time after `now`, keeping their id.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<ScheduledTask> {
+        let (due, pending) = std::mem::take(&mut self.scheduled_tasks)
+            .into_iter()
+            .partition(|task| task.time <= now);
+        self.scheduled_tasks = pending;
+
+        for task in &due {
+            let Some(recurrence) = &task.recurrence else {
+                continue;
+            };
+            match recurrence.next_after(now) {
+                Ok(time) => {
+                    self.insert(ScheduledTask {
+                        time,
+                        ..task.clone()
+                    });
+                }
+                Err(err) => error!("Task {} will not run again: {err}", task.id),
+            }
+        }
+        due
+    }
+
+    /// Saves the schedule to a JSON file, replacing it atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", temporary.display()))?;
+        std::fs::rename(&temporary, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Loads a schedule saved with `save`, or returns an empty one if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(bytes) => {
+                let mut scheduler: Self = serde_json::from_slice(&bytes)
+                    .with_context(|| format!("Invalid schedule in {}", path.display()))?;
+                scheduler.scheduled_tasks.sort_by_key(|task| task.time);
+                let last = scheduler.scheduled_tasks.iter().map(|task| task.id).max();
+                scheduler.last_id = scheduler.last_id.max(last.unwrap_or(0));
+                Ok(scheduler)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Inserts a task in time order, giving it a new id unless it has one, and returns its id.
+    fn insert(&mut self, mut task: ScheduledTask) -> u64 {
+        if task.id == 0 {
+            self.last_id += 1;
+            task.id = self.last_id;
+        }
+        let id = task.id;
+        let position = self
+            .scheduled_tasks
+            .partition_point(|scheduled| scheduled.time <= task.time);
+        self.scheduled_tasks.insert(position, task);
+        id
+    }
+}
+
+/// Runtime executing scheduled tasks on their agent once they are due.
+///
+/// Clones share the same schedule and agents, so tasks can be added to a scheduler that is
+/// running in another task.
+#[derive(Debug, Clone, Default)]
+pub struct Scheduler {
+    /// The scheduled tasks.
+    schedule: Arc<Mutex<TaskScheduler>>,
+    /// The agents running the tasks, with their position.
+    agents: Vec<(String, SharedAgent)>,
+    /// File the schedule is saved to, if any.
+    store: Option<PathBuf>,
+    /// Wakes the runtime up when the schedule changes.
+    changed: Arc<Notify>,
+    /// Whether agents execute the code they write.
+    pub execute: bool,
+    /// Whether agents may browse external resources.
+    pub browse: bool,
+    /// Number of attempts of each run of a task.
+    pub max_tries: u64,
+}
+
+impl Scheduler {
+    /// Creates a runtime running tasks on `agents`, with an empty schedule kept in memory.
+    pub async fn new(agents: Vec<SharedAgent>) -> Self {
+        let mut positioned = Vec::with_capacity(agents.len());
+        for agent in agents {
+            let position = agent.lock().await.get_agent().position().to_string();
+            positioned.push((position, agent));
+        }
+        Self {
+            agents: positioned,
+            execute: true,
+            max_tries: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Saves the schedule to `path` after every change, starting from the schedule already
+    /// saved there, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn with_store(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        self.schedule = Arc::new(Mutex::new(TaskScheduler::load(&path)?));
+        self.store = Some(path);
+        Ok(self)
+    }
+
+    /// Sets whether agents execute the code they write.
+    pub fn execute(mut self, execute: bool) -> Self {
+        self.execute = execute;
+        self
+    }
+
+    /// Sets whether agents may browse external resources.
+    pub fn browse(mut self, browse: bool) -> Self {
+        self.browse = browse;
+        self
+    }
+
+    /// Sets the number of attempts of each run of a task.
+    pub fn max_tries(mut self, max_tries: u64) -> Self {
+        self.max_tries = max_tries;
+        self
+    }
+
+    /// Schedules a task on the agent at `agent` position, once at `time`, and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no agent has this position, or if the schedule cannot be saved.
+    pub async fn add(&self, agent: &str, time: DateTime<Utc>, task: Task) -> Result<u64> {
+        let agent = self.position(agent)?;
+        let id = self
+            .change(|schedule| Ok(schedule.add(agent, time, task)))
+            .await?;
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Schedules a task on the agent at `agent` position, once after `delay`, and returns its
+    /// id.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `add`, or an error if the delay overflows.
+    pub async fn add_after(&self, agent: &str, delay: Duration, task: Task) -> Result<u64> {
+        let time = TimeDelta::from_std(delay)
+            .ok()
+            .and_then(|delay| Utc::now().checked_add_signed(delay))
+            .ok_or_else(|| anyhow!("Invalid delay of {delay:?}."))?;
+        self.add(agent, time, task).await
+    }
+
+    /// Schedules a task repeating on the agent at `agent` position, and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `add`, or an error if the recurrence never runs.
+    pub async fn add_recurring(
+        &self,
+        agent: &str,
+        recurrence: Recurrence,
+        task: Task,
+    ) -> Result<u64> {
+        let agent = self.position(agent)?;
+        let id = self
+            .change(|schedule| schedule.add_recurring(agent, recurrence, task, Utc::now()))
+            .await?;
+        self.changed.notify_one();
+        Ok(id)
+    }
+
+    /// Cancels a task and returns whether it was scheduled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schedule cannot be saved.
+    pub async fn cancel(&self, id: u64) -> Result<bool> {
+        let cancelled = self
+            .change(|schedule| Ok(schedule.cancel(id).is_some()))
+            .await?;
+        self.changed.notify_one();
+        Ok(cancelled)
+    }
+
+    /// Returns the scheduled tasks, the earliest first.
+    pub async fn list(&self) -> Vec<ScheduledTask> {
+        self.schedule.lock().await.list().to_vec()
+    }
+
+    /// Runs the tasks that are due, concurrently across agents, and returns how many ran.
+    /// Failed runs are logged, and do not prevent recurring tasks from running again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schedule cannot be saved.
+    pub async fn run_pending(&self) -> Result<usize> {
+        let due = self
+            .change(|schedule| Ok(schedule.take_due(Utc::now())))
+            .await?;
+
+        let runs = due.iter().map(|scheduled| async move {
+            let Some(agent) = self.find(&scheduled.agent) else {
+                error!(
+                    "Task {} is scheduled on the unknown agent {}",
+                    scheduled.id, scheduled.agent
+                );
+                return;
+            };
+            let mut task = scheduled.task.clone();
+            let mut agent = agent.lock().await;
+            info!(
+                "[*] {:?}: Running scheduled task {}: {}",
+                scheduled.agent, scheduled.id, task.description
+            );
+            match agent
+                .execute(&mut task, self.execute, self.browse, self.max_tries)
+                .await
+            {
+                Ok(()) => debug!("Scheduled task {} succeeded", scheduled.id),
+                Err(err) => error!("Scheduled task {} failed: {err}", scheduled.id),
+            }
+        });
+        join_all(runs).await;
+        Ok(due.len())
+    }
+
+    /// Runs due tasks until the process ends, sleeping until the next one is due or the
+    /// schedule changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schedule cannot be saved.
+    pub async fn run(&self) -> Result<()> {
+        loop {
+            let changed = self.changed.notified();
+            self.run_pending().await?;
+
+            let idle = self
+                .schedule
+                .lock()
+                .await
+                .next_time()
+                .map_or(MAX_IDLE, |time| {
+                    (time - Utc::now())
+                        .to_std()
+                        .unwrap_or_default()
+                        .min(MAX_IDLE)
+                });
+            tokio::select! {
+                _ = sleep(idle) => {}
+                _ = changed => {}
+            }
+        }
+    }
+
+    /// Starts `run` in the background.
+    pub fn spawn(&self) -> JoinHandle<Result<()>> {
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.run().await })
+    }
+
+    /// Applies a change to the schedule and saves it.
+    async fn change<T>(&self, change: impl FnOnce(&mut TaskScheduler) -> Result<T>) -> Result<T> {
+        let mut schedule = self.schedule.lock().await;
+        let result = change(&mut schedule)?;
+        if let Some(store) = &self.store {
+            schedule.save(store)?;
+        }
+        Ok(result)
+    }
+
+    /// Returns the position of the agent at `position`.
+    fn position(&self, position: &str) -> Result<Cow<'static, str>> {
+        if self.find(position).is_none() {
+            bail!("No agent has the position `{position}`.");
+        }
+        Ok(position.to_string().into())
+    }
+
+    /// Finds the agent at `position`.
+    fn find(&self, position: &str) -> Option<&SharedAgent> {
+        self.agents
+            .iter()
+            .find(|(agent, _)| agent == position)
+            .map(|(_, agent)| agent)
+    }
+}
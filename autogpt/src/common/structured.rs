@@ -0,0 +1,107 @@
+//! # Structured output.
+//!
+//! Helpers behind `AsyncFunctions::generate_structured`, which asks the model for a reply
+//! matching the JSON schema of a Rust type and parses it into that type. The schema is derived
+//! with `schemars`, sent through the provider's native JSON mode where it has one, and spelled
+//! out in the prompt for the others.
+//!
+//! Models still get the format wrong from time to time, so a reply that does not parse is not
+//! fatal: the model is shown its reply along with the parse error and asked to correct it. The
+//! number of such repairs is read from `AUTOGPT_STRUCTURED_REPAIRS`, and defaults to 2.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::structured::{parse, schema};
+//! use autogpt::common::utils::Scope;
+//!
+//! let schema = schema::<Scope>();
+//! assert_eq!(schema["type"], "object");
+//!
+//! let scope: Scope =
+//!     parse("Sure!\n```json\n{\"crud\": true, \"auth\": false, \"external\": false}\n```").unwrap();
+//! assert!(scope.crud);
+//!
+//! assert!(parse::<Scope>("{\"crud\": true}").is_err());
+//! ```
+
+use crate::common::utils::{extract_balanced, strip_code_blocks};
+use anyhow::{Result, anyhow};
+use schemars::{JsonSchema, schema_for};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::env::var;
+
+/// Number of repairs used when `AUTOGPT_STRUCTURED_REPAIRS` is not set.
+pub const DEFAULT_REPAIRS: u32 = 2;
+
+/// Returns the JSON schema of `T`.
+pub fn schema<T: JsonSchema>() -> Value {
+    schema_for!(T).to_value()
+}
+
+/// Returns how many times a malformed reply is sent back to the model for correction.
+pub fn repairs() -> u32 {
+    var("AUTOGPT_STRUCTURED_REPAIRS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_REPAIRS)
+}
+
+/// Builds the instruction asking the model to reply with JSON following `schema`.
+pub fn instructions(schema: &Value) -> String {
+    format!(
+        "Reply with a single JSON value that follows this JSON schema, without any other text:\n{schema}"
+    )
+}
+
+/// Builds the follow-up asking the model to correct a reply that failed to parse.
+pub fn repair(error: &anyhow::Error) -> String {
+    format!(
+        "Your reply does not follow the JSON schema: {error}. Reply again with only the corrected JSON value."
+    )
+}
+
+/// Parses a model reply into `T`.
+///
+/// The reply is tried as-is, then without its code fences, then as the first JSON object or
+/// array found in it, so that prose around the JSON is tolerated.
+///
+/// # Errors
+///
+/// Returns why the JSON found in the reply does not fit `T`, or why the reply is not JSON if
+/// none is found.
+pub fn parse<T: DeserializeOwned>(reply: &str) -> Result<T> {
+    let trimmed = reply.trim();
+    let error = match serde_json::from_str(trimmed) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+
+    let stripped = strip_code_blocks(trimmed);
+    let candidates = [
+        Some(stripped.clone()),
+        extract_balanced(&stripped, '{', '}'),
+        extract_balanced(&stripped, '[', ']'),
+    ];
+    for candidate in candidates.into_iter().flatten() {
+        if let Ok(value) = serde_json::from_str(candidate.trim()) {
+            return Ok(value);
+        }
+    }
+
+    let error = candidates_error::<T>(&stripped).unwrap_or(error);
+    Err(anyhow!(error))
+}
+
+/// Returns the deserialization error of the first JSON value found in `text`, if any.
+fn candidates_error<T: DeserializeOwned>(text: &str) -> Option<serde_json::Error> {
+    [
+        extract_balanced(text, '{', '}'),
+        extract_balanced(text, '[', ']'),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|candidate| serde_json::from_str::<Value>(candidate).is_ok())
+    .find_map(|candidate| serde_json::from_str::<T>(&candidate).err())
+}
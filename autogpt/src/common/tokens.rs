@@ -0,0 +1,304 @@
+//! # Token budgeting.
+//!
+//! Keeps the conversation sent with each request inside the model's context window.
+//!
+//! Token counts are estimated from the character count, which is close enough for English text
+//! and code with the tokenizers used by the supported providers, and errs on the side of
+//! overestimating. A `TokenBudget` reserves room for the reply and, when the conversation is too
+//! long, applies its `TruncationPolicy`:
+//!
+//! - `DropOldest`: drops the oldest messages until the rest fits.
+//! - `KeepLast(n)`: keeps the system messages, the first user message (the task) and the last `n`
+//!   messages, then drops the oldest of those if it still does not fit.
+//! - `Summarize`: asks the model to summarize the messages that would be dropped and sends the
+//!   summary as a system message in their place. Agents fit their requests with
+//!   `AgentGPT::fit_to_budget`, which retries the summary according to their `retry` policy
+//!   and records it in their `usage`.
+//!
+//! System messages and the latest message are never dropped.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::tokens::{TokenBudget, TruncationPolicy};
+//! use autogpt::common::utils::Communication;
+//!
+//! let budget = TokenBudget::new(TruncationPolicy::DropOldest)
+//!     .max_context(40)
+//!     .reserved(0);
+//!
+//! let messages = vec![
+//!     Communication::system("Answer briefly."),
+//!     Communication::user("a".repeat(200)),
+//!     Communication::assistant("b".repeat(200)),
+//!     Communication::user("What now?"),
+//! ];
+//!
+//! let kept = budget.truncate(&messages, 128_000);
+//! assert_eq!(kept.len(), 2);
+//! assert_eq!(kept[1].content, "What now?");
+//! ```
+
+use crate::common::compaction::SUMMARY_HEADER;
+use crate::common::config::LlmConfig;
+use crate::common::utils::Communication;
+use crate::traits::provider::{LlmProvider, transcript};
+use anyhow::Result;
+use tracing::{debug, warn};
+
+/// Context window assumed for models that are not listed in `model_context_window`.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8_192;
+
+/// Average number of characters per token used by `estimate_tokens`.
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens added per message for the role and separators.
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Known context windows, matched in order against the lowercased model name.
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gemini-1.5", 1_048_576),
+    ("gemini-2", 1_048_576),
+    ("gemini", 32_768),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("claude", 200_000),
+    ("grok", 131_072),
+    ("llama3.1", 131_072),
+    ("llama3.2", 131_072),
+    ("llama3.3", 131_072),
+    ("llama3", 8_192),
+    ("mixtral", 32_768),
+    ("mistral", 32_768),
+    ("qwen", 32_768),
+];
+
+const SUMMARY_PROMPT: &str = "Summarize the following conversation between a user and an \
+assistant. Keep every decision, requirement, file name and piece of code that later messages \
+may rely on. Reply with the summary only.";
+
+/// Estimates the number of tokens in `text`.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Estimates the number of tokens a conversation takes up, including per-message overhead.
+pub fn estimate_message_tokens(messages: &[Communication]) -> usize {
+    messages.iter().map(message_tokens).sum()
+}
+
+fn message_tokens(message: &Communication) -> usize {
+    estimate_tokens(&message.content) + MESSAGE_OVERHEAD
+}
+
+/// Returns the context window of a model, or `DEFAULT_CONTEXT_WINDOW` if it is unknown.
+pub fn model_context_window(model: &str) -> usize {
+    let model = model.to_lowercase();
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(name, _)| model.contains(name))
+        .map(|(_, window)| *window)
+        .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+}
+
+/// How a conversation is shortened when it does not fit the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationPolicy {
+    /// Drop the oldest messages first.
+    #[default]
+    DropOldest,
+    /// Keep the system messages, the task and the last `n` messages.
+    KeepLast(usize),
+    /// Replace the dropped messages with a model-written summary.
+    Summarize,
+}
+
+/// Limits how much of a conversation is sent with each request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBudget {
+    /// Upper bound on the context, used when it is smaller than the model's window.
+    pub max_context: Option<usize>,
+    /// Tokens kept free for the model's reply.
+    pub reserved: usize,
+    /// How to shorten conversations that do not fit.
+    pub policy: TruncationPolicy,
+}
+
+impl Default for TokenBudget {
+    fn default() -> Self {
+        Self::new(TruncationPolicy::default())
+    }
+}
+
+impl TokenBudget {
+    /// Creates a budget bounded by the model's window that reserves 1024 tokens for the reply.
+    pub fn new(policy: TruncationPolicy) -> Self {
+        Self {
+            max_context: None,
+            reserved: 1_024,
+            policy,
+        }
+    }
+
+    /// Caps the context at `tokens`, even if the model accepts more.
+    pub fn max_context(mut self, tokens: usize) -> Self {
+        self.max_context = Some(tokens);
+        self
+    }
+
+    /// Sets the number of tokens kept free for the reply.
+    pub fn reserved(mut self, tokens: usize) -> Self {
+        self.reserved = tokens;
+        self
+    }
+
+    /// Returns the number of tokens the conversation may use with a model of the given window.
+    pub fn limit(&self, context_window: usize) -> usize {
+        self.max_context
+            .map_or(context_window, |max| max.min(context_window))
+            .saturating_sub(self.reserved)
+    }
+
+    /// Shortens `messages` to fit a model with the given window, without calling the model.
+    ///
+    /// The `Summarize` policy behaves like `DropOldest` here.
+    pub fn truncate(
+        &self,
+        messages: &[Communication],
+        context_window: usize,
+    ) -> Vec<Communication> {
+        self.split(messages, self.limit(context_window)).0
+    }
+
+    /// Returns the budget of requests sent with `config`, which keeps `config.max_tokens` free
+    /// for the reply instead of `reserved` when it is set.
+    pub fn for_config(&self, config: &LlmConfig) -> Self {
+        Self {
+            reserved: config
+                .max_tokens
+                .map_or(self.reserved, |tokens| tokens as usize),
+            ..self.clone()
+        }
+    }
+
+    /// Shortens `messages` to fit the context window of the model `client` sends requests
+    /// made with `config` to, summarizing the overflow with it if the policy asks for it.
+    ///
+    /// The reply is given `config.max_tokens` when it is set, see `for_config`. If the summary
+    /// cannot be generated, the overflow is dropped instead.
+    pub async fn fit<P>(
+        &self,
+        client: &mut P,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Vec<Communication>>
+    where
+        P: LlmProvider + ?Sized,
+    {
+        let limit = self
+            .for_config(config)
+            .limit(client.context_window(config).await);
+        let (mut kept, dropped) = self.split(messages, limit);
+
+        if dropped.is_empty() {
+            return Ok(kept);
+        }
+        debug!(
+            "Dropping {} of {} messages to fit {limit} tokens.",
+            dropped.len(),
+            messages.len()
+        );
+
+        if self.policy != TruncationPolicy::Summarize {
+            return Ok(kept);
+        }
+
+        let overflow = transcript(&dropped);
+        let skip = overflow
+            .chars()
+            .count()
+            .saturating_sub(limit / 2 * CHARS_PER_TOKEN);
+        let overflow: String = overflow.chars().skip(skip).collect();
+
+        match client
+            .chat(&format!("{SUMMARY_PROMPT}\n\n{overflow}"))
+            .await
+        {
+            Ok(summary) => {
+                let at = kept
+                    .iter()
+                    .position(|message| message.role != "system")
+                    .unwrap_or(kept.len());
+                kept.insert(
+                    at,
+                    Communication::system(format!("{SUMMARY_HEADER}{summary}")),
+                );
+                Ok(TokenBudget::new(TruncationPolicy::DropOldest)
+                    .split(&kept, limit)
+                    .0)
+            }
+            Err(err) => {
+                warn!("Failed to summarize the dropped messages: {err}");
+                Ok(kept)
+            }
+        }
+    }
+
+    /// Splits `messages` into the messages to send and the ones to drop, both in order.
+    fn split(
+        &self,
+        messages: &[Communication],
+        limit: usize,
+    ) -> (Vec<Communication>, Vec<Communication>) {
+        let last = messages.len().saturating_sub(1);
+        let task = match self.policy {
+            TruncationPolicy::KeepLast(_) => messages.iter().position(|m| m.role == "user"),
+            _ => None,
+        };
+
+        let candidates: Vec<usize> = (0..messages.len())
+            .filter(|&i| i != last && Some(i) != task && messages[i].role != "system")
+            .collect();
+
+        let mut keep = vec![true; messages.len()];
+        if let TruncationPolicy::KeepLast(n) = self.policy {
+            for &i in &candidates[..candidates.len().saturating_sub(n)] {
+                keep[i] = false;
+            }
+        }
+
+        let mut total: usize = messages
+            .iter()
+            .zip(&keep)
+            .filter(|(_, kept)| **kept)
+            .map(|(message, _)| message_tokens(message))
+            .sum();
+
+        for &i in &candidates {
+            if total <= limit {
+                break;
+            }
+            if keep[i] {
+                keep[i] = false;
+                total -= message_tokens(&messages[i]);
+            }
+        }
+
+        let (kept, dropped): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .cloned()
+            .zip(keep)
+            .partition(|(_, kept)| *kept);
+
+        (
+            kept.into_iter().map(|(message, _)| message).collect(),
+            dropped.into_iter().map(|(message, _)| message).collect(),
+        )
+    }
+}
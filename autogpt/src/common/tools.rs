@@ -0,0 +1,272 @@
+//! # Tool calling.
+//!
+//! Types shared by agents and providers to offer an agent's `Tool`s to the model through the
+//! provider's native function calling: OpenAI `tools`, Anthropic `tool_use` blocks and Gemini
+//! function declarations.
+//!
+//! Every tool is offered as a function named after its `ToolName`, such as `calc` or `search`,
+//! taking the arguments described by its `ToolHandler`, a single string called `input` unless
+//! the handler says otherwise. When the model calls one, the agent runs the matching tool,
+//! sends the output or error back as the result of the call and asks the model again,
+//! until it answers with text. The number of rounds of calls is bounded by
+//! `AUTOGPT_TOOL_STEPS`, which defaults to 8.
+//!
+//! Providers without native function calling answer without tools.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::tools::ToolCall;
+//! use autogpt::common::utils::{Tool, ToolName};
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let tool = Tool::from_fn(ToolName::Calc, "Evaluates an arithmetic expression.", |input| {
+//!     format!("{input} = 4")
+//! });
+//!
+//! let definition = tool.definition();
+//! assert_eq!(definition.name, "calc");
+//! assert_eq!(definition.parameters["required"], json!(["input"]));
+//!
+//! let call = ToolCall::new("call_0", "calc", json!({ "input": "2 + 2" }));
+//! assert_eq!(tool.respond(call.arguments).await, "2 + 2 = 4");
+//! # }
+//! ```
+
+use crate::common::usage::Completion;
+use crate::common::utils::{Communication, Tool, ToolName};
+use crate::traits::tool::ToolHandler;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::env::var;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Number of rounds of tool calls used when `AUTOGPT_TOOL_STEPS` is not set.
+pub const DEFAULT_TOOL_STEPS: usize = 8;
+
+/// A function offered to the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Name the model calls the function by.
+    pub name: String,
+    /// What the function does, shown to the model.
+    pub description: String,
+    /// JSON schema of the arguments.
+    pub parameters: Value,
+}
+
+/// A function call requested by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Identifier of the call, echoed back with its result.
+    pub id: String,
+    /// Name of the called function.
+    pub name: String,
+    /// Arguments of the call, as a JSON object.
+    pub arguments: Value,
+}
+
+/// A round of tool calls made by the model, along with their results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolExchange {
+    /// Text the model replied with alongside the calls, often empty.
+    pub text: String,
+    /// The calls, in the order the model made them.
+    pub calls: Vec<ToolCall>,
+    /// The output of each call, in the same order.
+    pub results: Vec<String>,
+}
+
+/// The reply of the model to a conversation with tools.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolReply {
+    /// The text of the reply, with its model and usage.
+    pub completion: Completion,
+    /// The tools the model called, empty when the text is its final answer.
+    pub calls: Vec<ToolCall>,
+}
+
+impl ToolCall {
+    /// Creates a call.
+    pub fn new(id: impl Into<String>, name: impl Into<String>, arguments: Value) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            arguments,
+        }
+    }
+
+    /// Returns the string passed to a tool taking a single `input`, as `input_argument` does.
+    pub fn input(&self) -> String {
+        input_argument(&self.arguments)
+    }
+}
+
+impl ToolReply {
+    /// Creates a reply without tool calls.
+    pub fn answer(completion: Completion) -> Self {
+        Self {
+            completion,
+            calls: Vec::new(),
+        }
+    }
+}
+
+impl Tool {
+    /// Creates a tool run by `handler`.
+    pub fn new(
+        name: ToolName,
+        description: impl Into<Cow<'static, str>>,
+        handler: impl ToolHandler + 'static,
+    ) -> Self {
+        Self {
+            name,
+            description: description.into(),
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Creates a tool from a function of its `input` argument.
+    pub fn from_fn(
+        name: ToolName,
+        description: impl Into<Cow<'static, str>>,
+        invoke: fn(&str) -> String,
+    ) -> Self {
+        Self::new(name, description, invoke)
+    }
+
+    /// Creates a tool from a function pointer, as the removed `invoke` field did.
+    #[deprecated(note = "use `Tool::from_fn`, or `Tool::new` with an async `ToolHandler`")]
+    pub fn with_invoke(
+        name: ToolName,
+        description: impl Into<Cow<'static, str>>,
+        invoke: fn(&str) -> String,
+    ) -> Self {
+        Self::from_fn(name, description, invoke)
+    }
+
+    /// Returns the function definition the tool is offered to the model as.
+    pub fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: tool_name(&self.name),
+            description: self.description.to_string(),
+            parameters: self.handler.parameters(),
+        }
+    }
+
+    /// Runs the tool with the given JSON arguments.
+    pub async fn call(&self, arguments: Value) -> Result<Value> {
+        self.handler.call(arguments).await
+    }
+
+    /// Runs the tool and renders its result as the text sent back to the model. An error is
+    /// rendered as its message rather than returned, so that the model can react to it.
+    pub async fn respond(&self, arguments: Value) -> String {
+        match self.call(arguments).await {
+            Ok(Value::String(output)) => output,
+            Ok(output) => output.to_string(),
+            Err(err) => {
+                warn!("Tool `{}` failed: {err}", tool_name(&self.name));
+                format!("Error: {err}")
+            }
+        }
+    }
+
+    /// Runs a tool taking a single `input` argument, as `respond` does.
+    pub async fn run(&self, input: &str) -> String {
+        self.respond(json!({ "input": input })).await
+    }
+}
+
+/// Returns the argument schema of tools taking a single string `input`.
+pub fn input_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "input": {
+                "type": "string",
+                "description": "Input passed to the tool.",
+            },
+        },
+        "required": ["input"],
+    })
+}
+
+/// Returns the `input` argument of a call, or the whole arguments if the model did not follow
+/// the schema.
+pub fn input_argument(arguments: &Value) -> String {
+    match &arguments["input"] {
+        Value::String(input) => input.clone(),
+        Value::Null => match arguments {
+            Value::String(input) => input.clone(),
+            arguments => arguments.to_string(),
+        },
+        input => input.to_string(),
+    }
+}
+
+/// Returns the function name of a tool, restricted to the characters every provider accepts.
+pub fn tool_name(name: &ToolName) -> String {
+    let name = match name {
+        ToolName::Plugin(name) => name.clone(),
+        name => format!("{name:?}"),
+    };
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+/// Returns the tool a call is addressed to.
+pub fn find_tool<'a>(tools: &'a [Tool], call: &ToolCall) -> Option<&'a Tool> {
+    tools.iter().find(|tool| tool_name(&tool.name) == call.name)
+}
+
+/// Returns how many rounds of tool calls an agent makes before giving up on an answer.
+pub fn tool_steps() -> usize {
+    var("AUTOGPT_TOOL_STEPS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_TOOL_STEPS)
+}
+
+/// Renders a conversation and its tool exchanges as plain messages, for providers without
+/// native function calling.
+pub fn tool_transcript(
+    messages: &[Communication],
+    exchanges: &[ToolExchange],
+) -> Vec<Communication> {
+    let mut transcript = messages.to_vec();
+    for exchange in exchanges {
+        let calls = exchange
+            .calls
+            .iter()
+            .map(|call| format!("Calling `{}` with {}", call.name, call.arguments))
+            .collect::<Vec<_>>()
+            .join("\n");
+        transcript.push(Communication::assistant(
+            format!("{}\n{calls}", exchange.text).trim().to_string(),
+        ));
+
+        let results = exchange
+            .calls
+            .iter()
+            .zip(&exchange.results)
+            .map(|(call, result)| format!("Result of `{}`: {result}", call.name))
+            .collect::<Vec<_>>()
+            .join("\n");
+        transcript.push(Communication::user(results));
+    }
+    transcript
+}
@@ -0,0 +1,478 @@
+//! # Token usage.
+//!
+//! Every text generation made by an agent is recorded in its `UsageTracker`, keyed by model:
+//!
+//! - Replies from `LlmProvider::completion` carry the prompt and completion token counts
+//!   reported by the provider when its API returns them (OpenAI-compatible servers, Gemini and
+//!   Anthropic).
+//! - Other replies, and every stream, are counted with `estimate_tokens`.
+//!
+//! A `PriceTable` turns the counts into a cost in US dollars. It starts from the list prices of
+//! common hosted models, which change over time, and can be extended or overridden with a JSON
+//! file named by `AUTOGPT_PRICES`, giving the price per million tokens of each model:
+//!
+//! ```json
+//! {
+//!   "gemini-2.0-flash": { "prompt": 0.1, "completion": 0.4 },
+//!   "llama3.2": { "prompt": 0.0, "completion": 0.0 }
+//! }
+//! ```
+//!
+//! Models are matched against the table by substring, in the same way as context windows. Models
+//! without a price, such as local ones, are reported as unpriced and add nothing to the cost.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::usage::{Price, PriceTable, Usage, UsageTracker};
+//!
+//! let tracker = UsageTracker::new();
+//! tracker.record("gpt-4o", Usage::new(1_000_000, 100_000));
+//!
+//! let prices = PriceTable::new().price("gpt-4o", Price::new(2.5, 10.0));
+//! let report = tracker.report();
+//!
+//! assert_eq!(report.total().total(), 1_100_000);
+//! assert_eq!(report.cost(&prices), 3.5);
+//! ```
+
+use crate::common::tokens::{CHARS_PER_TOKEN, estimate_message_tokens, estimate_tokens};
+use crate::common::utils::Communication;
+use crate::traits::provider::TextStream;
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env::var;
+use std::fmt::Write;
+use std::ops::{Add, AddAssign};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// List prices in US dollars per million prompt and completion tokens, matched in order against
+/// the lowercased model name.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.0-flash-lite", 0.075, 0.3),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+    ("grok-beta", 5.0, 15.0),
+    ("grok-3-mini", 0.3, 0.5),
+    ("grok-3", 3.0, 15.0),
+];
+
+/// Token counts of one or more model calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Tokens sent to the model.
+    pub prompt_tokens: u64,
+    /// Tokens generated by the model.
+    pub completion_tokens: u64,
+}
+
+impl Usage {
+    /// Creates a usage record from prompt and completion token counts.
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    /// Estimates the usage of a call from its conversation and reply.
+    pub fn estimate(messages: &[Communication], reply: &str) -> Self {
+        Self::new(
+            estimate_message_tokens(messages) as u64,
+            estimate_tokens(reply) as u64,
+        )
+    }
+
+    /// Returns the number of prompt and completion tokens together.
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl Add for Usage {
+    type Output = Usage;
+
+    fn add(self, other: Usage) -> Usage {
+        Usage::new(
+            self.prompt_tokens + other.prompt_tokens,
+            self.completion_tokens + other.completion_tokens,
+        )
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        *self = *self + other;
+    }
+}
+
+/// A generated reply along with the model that wrote it and the tokens it took.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Completion {
+    /// The generated text.
+    pub text: String,
+    /// The model that generated the text, if the provider reported it.
+    pub model: Option<String>,
+    /// The tokens used by the call, if the provider reported them.
+    pub usage: Option<Usage>,
+}
+
+impl Completion {
+    /// Creates a completion without model or usage information.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the model that generated the text.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Sets the tokens used by the call.
+    pub fn usage(mut self, usage: Usage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+}
+
+/// Usage of a single model, accumulated over several calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Number of calls made to the model.
+    pub requests: u64,
+    /// Tokens used by those calls.
+    pub usage: Usage,
+}
+
+/// Token usage accumulated over several calls, per model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Usage of each model, keyed by model name.
+    pub models: BTreeMap<String, ModelUsage>,
+}
+
+impl UsageReport {
+    /// Adds a call to `model` that used `usage`.
+    pub fn record(&mut self, model: &str, usage: Usage) {
+        let entry = self.models.entry(model.to_string()).or_default();
+        entry.requests += 1;
+        entry.usage += usage;
+    }
+
+    /// Returns this report with the calls of `other` added to it.
+    pub fn merge(mut self, other: &UsageReport) -> Self {
+        for (model, other) in &other.models {
+            let entry = self.models.entry(model.clone()).or_default();
+            entry.requests += other.requests;
+            entry.usage += other.usage;
+        }
+        self
+    }
+
+    /// Returns the calls made since `earlier`, a previous report of the same tracker.
+    pub fn since(&self, earlier: &UsageReport) -> Self {
+        let models = self
+            .models
+            .iter()
+            .filter_map(|(model, current)| {
+                let before = earlier.models.get(model).copied().unwrap_or_default();
+                let requests = current.requests.saturating_sub(before.requests);
+                (requests > 0).then(|| {
+                    let usage = Usage::new(
+                        current
+                            .usage
+                            .prompt_tokens
+                            .saturating_sub(before.usage.prompt_tokens),
+                        current
+                            .usage
+                            .completion_tokens
+                            .saturating_sub(before.usage.completion_tokens),
+                    );
+                    (model.clone(), ModelUsage { requests, usage })
+                })
+            })
+            .collect();
+
+        Self { models }
+    }
+
+    /// Returns whether no calls were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Returns the number of calls across every model.
+    pub fn requests(&self) -> u64 {
+        self.models.values().map(|model| model.requests).sum()
+    }
+
+    /// Returns the tokens used across every model.
+    pub fn total(&self) -> Usage {
+        self.models
+            .values()
+            .fold(Usage::default(), |total, model| total + model.usage)
+    }
+
+    /// Returns the cost in US dollars of the priced models.
+    pub fn cost(&self, prices: &PriceTable) -> f64 {
+        self.models
+            .iter()
+            .filter_map(|(model, usage)| prices.cost(model, usage.usage))
+            .sum()
+    }
+
+    /// Formats one line per model and a total line, with the cost of each.
+    pub fn summary(&self, prices: &PriceTable) -> String {
+        let mut summary = String::new();
+
+        for (model, usage) in &self.models {
+            let cost = match prices.cost(model, usage.usage) {
+                Some(cost) => format!("${cost:.4}"),
+                None => "unpriced".to_string(),
+            };
+            let _ = writeln!(
+                summary,
+                "{model}: {}, {cost}",
+                describe(usage.requests, usage.usage)
+            );
+        }
+
+        let _ = write!(
+            summary,
+            "Total: {}, ${:.4}",
+            describe(self.requests(), self.total()),
+            self.cost(prices)
+        );
+        summary
+    }
+}
+
+fn describe(requests: u64, usage: Usage) -> String {
+    format!(
+        "{requests} {}, {} prompt + {} completion tokens",
+        if requests == 1 { "request" } else { "requests" },
+        usage.prompt_tokens,
+        usage.completion_tokens
+    )
+}
+
+/// Shared record of the tokens used by an agent.
+///
+/// Clones share their state, so the usage of an agent can be read through any copy of it.
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    report: Arc<Mutex<UsageReport>>,
+}
+
+impl PartialEq for UsageTracker {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.report, &other.report) || self.report() == other.report()
+    }
+}
+
+impl UsageTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, UsageReport> {
+        self.report
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Adds a call to `model` that used `usage`.
+    pub fn record(&self, model: &str, usage: Usage) {
+        self.lock().record(model, usage);
+    }
+
+    /// Adds a completed call, estimating whatever the provider did not report.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_model` - Model recorded when the completion does not name one.
+    /// * `messages` - The conversation that was sent.
+    /// * `completion` - The reply.
+    pub fn record_completion(
+        &self,
+        default_model: &str,
+        messages: &[Communication],
+        completion: &Completion,
+    ) {
+        let model = completion.model.as_deref().unwrap_or(default_model);
+        let usage = completion
+            .usage
+            .unwrap_or_else(|| Usage::estimate(messages, &completion.text));
+        self.record(model, usage);
+    }
+
+    /// Wraps a stream so that its estimated usage is recorded once it ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model the usage is recorded for.
+    /// * `request` - The prompt that was sent.
+    /// * `deltas` - The text deltas of the reply.
+    pub fn meter(&self, model: &str, request: &str, deltas: TextStream) -> TextStream {
+        let prompt_tokens = estimate_message_tokens(&[Communication::user(request.to_string())]);
+        let tracker = self.clone();
+        let model = model.to_string();
+
+        Box::pin(stream::unfold(Some((deltas, 0usize)), move |state| {
+            let tracker = tracker.clone();
+            let model = model.clone();
+            async move {
+                let (mut deltas, chars) = state?;
+                match deltas.next().await {
+                    Some(delta) => {
+                        let chars = chars + delta.as_ref().map_or(0, |text| text.chars().count());
+                        Some((delta, Some((deltas, chars))))
+                    }
+                    None => {
+                        let completion_tokens = chars.div_ceil(CHARS_PER_TOKEN);
+                        tracker.record(
+                            &model,
+                            Usage::new(prompt_tokens as u64, completion_tokens as u64),
+                        );
+                        None
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Returns a copy of the usage recorded so far.
+    pub fn report(&self) -> UsageReport {
+        self.lock().clone()
+    }
+
+    /// Returns the usage recorded so far and clears it.
+    pub fn take(&self) -> UsageReport {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Replaces the usage recorded so far with `report`.
+    pub fn replace(&self, report: UsageReport) {
+        *self.lock() = report;
+    }
+}
+
+/// Price of a model in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    /// Price per million prompt tokens.
+    pub prompt: f64,
+    /// Price per million completion tokens.
+    pub completion: f64,
+}
+
+impl Price {
+    /// Creates a price from the cost per million prompt and completion tokens.
+    pub fn new(prompt: f64, completion: f64) -> Self {
+        Self { prompt, completion }
+    }
+
+    /// Returns the cost of `usage` in US dollars.
+    pub fn cost(&self, usage: Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt
+            + usage.completion_tokens as f64 * self.completion)
+            / 1_000_000.0
+    }
+}
+
+/// Prices of models, matched by substring of the model name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTable {
+    /// Model name fragments and their prices, checked in order.
+    pub prices: Vec<(String, Price)>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        Self {
+            prices: DEFAULT_PRICES
+                .iter()
+                .map(|(model, prompt, completion)| {
+                    (model.to_string(), Price::new(*prompt, *completion))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PriceTable {
+    /// Creates an empty table, in which every model is unpriced.
+    pub fn new() -> Self {
+        Self { prices: Vec::new() }
+    }
+
+    /// Builds the default table, with the prices of the file named by `AUTOGPT_PRICES` taking
+    /// precedence.
+    ///
+    /// A file that cannot be read or parsed is logged and ignored, leaving the default table.
+    pub fn from_env() -> Self {
+        let Ok(path) = var("AUTOGPT_PRICES") else {
+            return Self::default();
+        };
+
+        let prices: HashMap<String, Price> = match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+        {
+            Ok(prices) => prices,
+            Err(err) => {
+                warn!("Ignoring the price table `{path}`: {err}");
+                return Self::default();
+            }
+        };
+
+        // Apply the shortest names first, so that the most specific ones end up at the front.
+        let mut prices: Vec<(String, Price)> = prices.into_iter().collect();
+        prices.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)));
+        prices
+            .into_iter()
+            .fold(Self::default(), |table, (model, price)| {
+                table.price(model, price)
+            })
+    }
+
+    /// Sets the price of the models whose name contains `model`, ahead of the existing entries.
+    pub fn price(mut self, model: impl Into<String>, price: Price) -> Self {
+        self.prices.insert(0, (model.into().to_lowercase(), price));
+        self
+    }
+
+    /// Returns the price of `model`, if it is listed.
+    pub fn lookup(&self, model: &str) -> Option<Price> {
+        let model = model.to_lowercase();
+        self.prices
+            .iter()
+            .find(|(name, _)| model.contains(name.as_str()))
+            .map(|(_, price)| *price)
+    }
+
+    /// Returns the cost of `usage` with `model` in US dollars, if the model is listed.
+    pub fn cost(&self, model: &str, usage: Usage) -> Option<f64> {
+        self.lookup(model).map(|price| price.cost(usage))
+    }
+}
@@ -72,6 +72,7 @@ use crate::traits::agent::Agent;
 use colored::Colorize;
 #[cfg(feature = "cli")]
 use indicatif::{ProgressBar, ProgressStyle};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::borrow::Cow;
@@ -116,14 +117,32 @@ use anthropic_ai_sdk::{
     types::message::{Message as AnthMessage, MessageError},
 };
 
+#[cfg(all(not(feature = "oai"), feature = "cld"))]
+use anthropic_ai_sdk::types::message::Role;
+
 use chrono::prelude::*;
 use std::collections::HashMap;
 #[cfg(feature = "xai")]
 use x_ai::{chat_compl::Message as XaiMessage, client::XaiClient, traits::ClientConfig};
 
+use crate::common::config::LlmConfig;
+use crate::common::tools::{ToolDefinition, ToolExchange, ToolReply, tool_transcript};
+use crate::common::usage::{Completion, Usage};
+#[cfg(feature = "cli")]
+use crate::common::usage::{PriceTable, UsageReport};
+use crate::providers::fallback::FallbackClient;
+use crate::providers::local::LocalClient;
+use crate::providers::mock::MockClient;
+use crate::traits::evaluator::Evaluator;
+use crate::traits::provider::{LlmProvider, TextStream};
+use crate::traits::tool::ToolHandler;
+use anyhow::Result;
+use async_trait::async_trait;
 use derivative::Derivative;
+use std::sync::Arc;
 #[cfg(feature = "cli")]
 use std::time::Duration;
+use tokio::sync::Mutex;
 
 /// Enum representing supported AI clients.
 #[derive(Debug, Clone)]
@@ -143,6 +162,18 @@ pub enum ClientType {
     /// XAI Grok client.
     #[cfg(feature = "xai")]
     Xai(XaiClient),
+
+    /// OpenAI-compatible local model server.
+    Local(LocalClient),
+
+    /// Scripted or cassette-backed client for offline tests.
+    Mock(MockClient),
+
+    /// Chain of clients tried in order until one succeeds.
+    Fallback(FallbackClient),
+
+    /// Any other backend implementing `LlmProvider`.
+    Custom(Arc<Mutex<dyn LlmProvider>>),
 }
 
 impl Default for ClientType {
@@ -152,8 +183,54 @@ impl Default for ClientType {
 }
 
 impl ClientType {
+    /// Returns the name of the provider, as accepted by `from_provider`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(_) => "openai",
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(_) => "gemini",
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(_) => "anthropic",
+            #[cfg(feature = "xai")]
+            ClientType::Xai(_) => "xai",
+            ClientType::Local(_) => "local",
+            ClientType::Mock(_) => "mock",
+            ClientType::Fallback(_) => "fallback",
+            ClientType::Custom(_) => "custom",
+        }
+    }
+
+    /// Wraps a third-party `LlmProvider` implementation into a `ClientType`.
+    pub fn custom<P: LlmProvider + 'static>(provider: P) -> Self {
+        ClientType::Custom(Arc::new(Mutex::new(provider)))
+    }
+
+    /// Builds the client selected by the `AI_PROVIDER` environment variable, defaulting to Gemini.
+    ///
+    /// A comma-separated list, such as `gemini,openai,local`, builds a fallback chain.
     pub fn from_env() -> Self {
         let provider = var("AI_PROVIDER").unwrap_or_else(|_| "gemini".to_string());
+        ClientType::from_provider(&provider)
+    }
+
+    /// Builds the client for the given provider name (`openai`, `gemini`, `anthropic`, `xai`,
+    /// `local` or `mock`), reading credentials from the environment.
+    ///
+    /// A comma-separated list of names builds a `FallbackClient` trying them in order.
+    pub fn from_provider(provider: &str) -> Self {
+        let provider = provider.trim();
+        if provider.contains(',') {
+            return ClientType::Fallback(FallbackClient::from_providers(provider));
+        }
+
+        if provider == "local" {
+            return ClientType::Local(LocalClient::from_env());
+        }
+
+        if provider == "mock" {
+            return ClientType::Mock(MockClient::from_env());
+        }
 
         #[cfg(feature = "oai")]
         if provider == "openai" {
@@ -193,12 +270,280 @@ impl ClientType {
         {
             panic!(
                 "Invalid AI_PROVIDER `{provider}` or missing required feature flags. \
-                Make sure to enable at least one of: `oai`, `gem`, `cld`, `xai`."
+                Make sure to enable at least one of: `oai`, `gem`, `cld`, `xai`, \
+                or set `AI_PROVIDER` to `local` or `mock`."
             );
         }
     }
 }
 
+#[async_trait]
+impl LlmProvider for ClientType {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.chat(request).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.chat(request).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.chat(request).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.chat(request).await,
+            ClientType::Local(client) => client.chat(request).await,
+            ClientType::Mock(client) => client.chat(request).await,
+            ClientType::Fallback(client) => client.chat(request).await,
+            ClientType::Custom(client) => client.lock().await.chat(request).await,
+        }
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.complete(messages).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.complete(messages).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.complete(messages).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.complete(messages).await,
+            ClientType::Local(client) => client.complete(messages).await,
+            ClientType::Mock(client) => client.complete(messages).await,
+            ClientType::Fallback(client) => client.complete(messages).await,
+            ClientType::Custom(client) => client.lock().await.complete(messages).await,
+        }
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.complete_with(messages, config).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.complete_with(messages, config).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.complete_with(messages, config).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.complete_with(messages, config).await,
+            ClientType::Local(client) => client.complete_with(messages, config).await,
+            ClientType::Mock(client) => client.complete_with(messages, config).await,
+            ClientType::Fallback(client) => client.complete_with(messages, config).await,
+            ClientType::Custom(client) => client.lock().await.complete_with(messages, config).await,
+        }
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        let mut completion = match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.completion(messages, config).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.completion(messages, config).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.completion(messages, config).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.completion(messages, config).await,
+            ClientType::Local(client) => client.completion(messages, config).await,
+            ClientType::Mock(client) => client.completion(messages, config).await,
+            ClientType::Fallback(client) => client.completion(messages, config).await,
+            ClientType::Custom(client) => client.lock().await.completion(messages, config).await,
+        }?;
+
+        if completion.model.is_none() {
+            completion.model = Some(config.model_or(self.name()).to_string());
+        }
+        if completion.usage.is_none() {
+            completion.usage = Some(Usage::estimate(messages, &completion.text));
+        }
+        Ok(completion)
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let mut reply = match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            ClientType::Local(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            ClientType::Mock(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            ClientType::Fallback(client) => {
+                client
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+            ClientType::Custom(client) => {
+                client
+                    .lock()
+                    .await
+                    .complete_with_tools(messages, exchanges, tools, config)
+                    .await
+            }
+        }?;
+
+        let completion = &mut reply.completion;
+        if completion.model.is_none() {
+            completion.model = Some(config.model_or(self.name()).to_string());
+        }
+        if completion.usage.is_none() {
+            let messages = tool_transcript(messages, exchanges);
+            completion.usage = Some(Usage::estimate(&messages, &completion.text));
+        }
+        Ok(reply)
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.stream(request).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.stream(request).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.stream(request).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.stream(request).await,
+            ClientType::Local(client) => client.stream(request).await,
+            ClientType::Mock(client) => client.stream(request).await,
+            ClientType::Fallback(client) => client.stream(request).await,
+            ClientType::Custom(client) => client.lock().await.stream(request).await,
+        }
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.stream_with(request, config).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.stream_with(request, config).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.stream_with(request, config).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.stream_with(request, config).await,
+            ClientType::Local(client) => client.stream_with(request, config).await,
+            ClientType::Mock(client) => client.stream_with(request, config).await,
+            ClientType::Fallback(client) => client.stream_with(request, config).await,
+            ClientType::Custom(client) => client.lock().await.stream_with(request, config).await,
+        }
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.embed(content).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.embed(content).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.embed(content).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.embed(content).await,
+            ClientType::Local(client) => client.embed(content).await,
+            ClientType::Mock(client) => client.embed(content).await,
+            ClientType::Fallback(client) => client.embed(content).await,
+            ClientType::Custom(client) => client.lock().await.embed(content).await,
+        }
+    }
+
+    async fn image(&mut self, request: &str) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.image(request).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.image(request).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.image(request).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.image(request).await,
+            ClientType::Local(client) => client.image(request).await,
+            ClientType::Mock(client) => client.image(request).await,
+            ClientType::Fallback(client) => client.image(request).await,
+            ClientType::Custom(client) => client.lock().await.image(request).await,
+        }
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.describe_image(prompt, image, config).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.describe_image(prompt, image, config).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.describe_image(prompt, image, config).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.describe_image(prompt, image, config).await,
+            ClientType::Local(client) => client.describe_image(prompt, image, config).await,
+            ClientType::Mock(client) => client.describe_image(prompt, image, config).await,
+            ClientType::Fallback(client) => client.describe_image(prompt, image, config).await,
+            ClientType::Custom(client) => {
+                client
+                    .lock()
+                    .await
+                    .describe_image(prompt, image, config)
+                    .await
+            }
+        }
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        match self {
+            #[cfg(feature = "oai")]
+            ClientType::OpenAI(client) => client.context_window(config).await,
+            #[cfg(feature = "gem")]
+            ClientType::Gemini(client) => client.context_window(config).await,
+            #[cfg(feature = "cld")]
+            ClientType::Anthropic(client) => client.context_window(config).await,
+            #[cfg(feature = "xai")]
+            ClientType::Xai(client) => client.context_window(config).await,
+            ClientType::Local(client) => client.context_window(config).await,
+            ClientType::Mock(client) => client.context_window(config).await,
+            ClientType::Fallback(client) => client.context_window(config).await,
+            ClientType::Custom(client) => client.lock().await.context_window(config).await,
+        }
+    }
+}
+
 /// Represents a communication between agents.
 #[derive(Eq, Debug, PartialEq, Default, Clone, Hash, Serialize, Deserialize)]
 pub struct Communication {
@@ -208,6 +553,32 @@ pub struct Communication {
     pub content: Cow<'static, str>,
 }
 
+impl Communication {
+    /// Creates a `system` message, used for instructions that frame the conversation.
+    pub fn system(content: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            role: Cow::Borrowed("system"),
+            content: content.into(),
+        }
+    }
+
+    /// Creates a `user` message.
+    pub fn user(content: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            role: Cow::Borrowed("user"),
+            content: content.into(),
+        }
+    }
+
+    /// Creates an `assistant` message, i.e. a previous reply of the model.
+    pub fn assistant(content: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            role: Cow::Borrowed("assistant"),
+            content: content.into(),
+        }
+    }
+}
+
 /// Represents the status of an agent.
 #[derive(Debug, PartialEq, Default, Clone)]
 pub enum Status {
@@ -220,6 +591,14 @@ pub enum Status {
     InUnitTesting,
     /// Agent has finished its task.
     Completed,
+    /// Agent stopped on an error, with its reason.
+    Failed(Cow<'static, str>),
+    /// Agent is waiting on something outside its control, such as a collaborator.
+    Blocked,
+    /// Agent is suspended until it is resumed.
+    Paused,
+    /// Agent was stopped before finishing its task.
+    Cancelled,
 }
 
 /// Represents a route object.
@@ -238,7 +617,7 @@ pub struct Route {
 }
 
 /// Represents the scope of a project.
-#[derive(Eq, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, Hash)]
+#[derive(Eq, Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Default, Hash)]
 pub struct Scope {
     /// Indicates if CRUD operations are required.
     pub crud: bool,
@@ -278,26 +657,43 @@ impl Task {
     }
 }
 
+/// Extracts the first complete JSON object from `text`, skipping any surrounding prose or code
+/// fences. Braces inside string literals are ignored.
 pub fn extract_json_string(text: &str) -> Option<String> {
-    if let Some(start_index) = text.find("{\n  \"crud\"") {
-        let mut end_index = start_index + 1;
-        let mut open_braces_count = 1;
+    extract_balanced(text, '{', '}')
+}
 
-        for (i, c) in text[start_index + 1..].char_indices() {
+/// Returns the first span of `text` that opens with `open` and closes with the matching `close`,
+/// outside of JSON string literals.
+pub(crate) fn extract_balanced(text: &str, open: char, close: char) -> Option<String> {
+    let start_index = text.find(open)?;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in text[start_index..].char_indices() {
+        if in_string {
             match c {
-                '{' => open_braces_count += 1,
-                '}' => {
-                    open_braces_count -= 1;
-                    if open_braces_count == 0 {
-                        end_index = start_index + i + 2;
-                        break;
-                    }
-                }
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
                 _ => {}
             }
+            continue;
         }
 
-        return Some(text[start_index..end_index].to_string());
+        match c {
+            '"' => in_string = true,
+            _ if c == open => depth += 1,
+            _ if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    let end_index = start_index + i + c.len_utf8();
+                    return Some(text[start_index..end_index].to_string());
+                }
+            }
+            _ => {}
+        }
     }
 
     None
@@ -532,6 +928,21 @@ pub fn setup_logging() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Logs the token usage and cost of a run, priced with `PriceTable::from_env`.
+///
+/// Nothing is logged when the run made no model calls.
+#[cfg(feature = "cli")]
+pub fn log_usage(usage: &UsageReport) {
+    if usage.is_empty() {
+        return;
+    }
+
+    let prices = PriceTable::from_env();
+    for line in usage.summary(&prices).lines() {
+        info!("{}", format!("[*] \"AGI\": 💰 {line}").bright_blue());
+    }
+}
+
 #[cfg(feature = "cli")]
 pub async fn ask_to_run_command(
     agent: AgentGPT,
@@ -847,9 +1258,14 @@ pub struct Tool {
     pub name: ToolName,
     /// A brief description of the tool's function.
     pub description: Cow<'static, str>,
-    /// A function pointer to invoke the tool with a string input.
-    #[derivative(Default(value = "noop_tool"))]
-    pub invoke: fn(&str) -> String,
+    /// The handler running the tool.
+    #[derivative(
+        Debug = "ignore",
+        PartialEq = "ignore",
+        Hash = "ignore",
+        Default(value = "Arc::new(noop_tool as fn(&str) -> String)")
+    )]
+    pub handler: Arc<dyn ToolHandler>,
 }
 
 /// Represents a simple structured knowledge base for storing facts.
@@ -873,6 +1289,8 @@ pub struct Goal {
     pub description: String,
     pub priority: u8,
     pub completed: bool,
+    /// Positions in the plan of the goals that must be completed before this one.
+    pub dependencies: Vec<usize>,
 }
 
 /// Represents the personality and behavioral traits of the agent.
@@ -892,25 +1310,53 @@ pub struct Persona {
 pub struct Reflection {
     /// A log of recent activities or messages.
     pub recent_logs: Vec<Cow<'static, str>>,
-    /// A function for evaluating the agent's internal state.
-    #[derivative(Default(value = "default_eval_fn"))]
-    pub evaluation_fn: fn(&dyn Agent) -> Cow<'static, str>,
+    /// The evaluator of the agent's recent work.
+    #[derivative(
+        Debug = "ignore",
+        PartialEq = "ignore",
+        Hash = "ignore",
+        Default(value = "Arc::new(default_eval_fn as fn(&dyn Agent) -> Cow<'static, str>)")
+    )]
+    pub evaluator: Arc<dyn Evaluator>,
 }
 
 /// A scheduler for managing the agent's future tasks.
-#[derive(Eq, Debug, PartialEq, Default, Clone, Hash)]
+#[derive(Eq, Debug, PartialEq, Default, Clone, Hash, Serialize, Deserialize)]
 pub struct TaskScheduler {
-    /// A list of scheduled tasks with specific times.
+    /// A list of scheduled tasks with specific times, the earliest first.
     pub scheduled_tasks: Vec<ScheduledTask>,
+    /// The last id given to a task, so that ids of cancelled tasks are never reused.
+    #[serde(default)]
+    pub last_id: u64,
 }
 
 /// Represents a task that is scheduled to occur at a certain time.
-#[derive(Eq, Debug, PartialEq, Default, Clone, Hash)]
+#[derive(Eq, Debug, PartialEq, Default, Clone, Hash, Serialize, Deserialize)]
 pub struct ScheduledTask {
+    /// Identifier of the task in its scheduler.
+    #[serde(default)]
+    pub id: u64,
+    /// Position of the agent running the task.
+    #[serde(default)]
+    pub agent: Cow<'static, str>,
     /// The scheduled time for the task.
     pub time: DateTime<Utc>,
     /// The goal associated with the task.
     pub task: Task,
+    /// How the task repeats after it runs, if it does.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+}
+
+/// How a scheduled task repeats.
+#[derive(Eq, Debug, PartialEq, Clone, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    /// A cron expression in UTC, such as `0 9 * * Mon-Fri`, with an optional leading seconds
+    /// field and trailing year field.
+    Cron(String),
+    /// A fixed number of seconds between runs.
+    Every(u64),
 }
 
 /// Represents a sensor or input modality that the agent can use.
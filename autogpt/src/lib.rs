@@ -7,6 +7,8 @@ pub mod macros;
 pub mod prelude;
 #[cfg(feature = "gpt")]
 pub mod prompts;
+pub mod providers;
+pub mod tools;
 pub mod traits;
 
 #[cfg(feature = "net")]
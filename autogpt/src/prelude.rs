@@ -1,33 +1,45 @@
 #![doc = include_str!("../INSTALLATION.md")]
 
-#[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
 use {futures::future::join_all, tokio::task, tracing::error};
 
-#[cfg(feature = "img")]
+#[cfg(all(feature = "gpt", feature = "img"))]
 pub use crate::agents::designer::DesignerGPT;
-#[cfg(feature = "git")]
+#[cfg(all(feature = "gpt", feature = "git"))]
 pub use crate::agents::git::GitGPT;
-#[cfg(feature = "mail")]
+#[cfg(all(feature = "gpt", feature = "mail"))]
 pub use crate::agents::mailer::MailerGPT;
 
 #[cfg(feature = "gpt")]
 pub use {
-    crate::agents::architect::ArchitectGPT, crate::agents::backend::BackendGPT,
-    crate::agents::frontend::FrontendGPT, crate::agents::manager::ManagerGPT,
-    crate::agents::optimizer::OptimizerGPT,
+    crate::agents::architect::ArchitectGPT, crate::agents::autonomous::AutonomousGPT,
+    crate::agents::backend::BackendGPT, crate::agents::frontend::FrontendGPT,
+    crate::agents::manager::ManagerGPT, crate::agents::optimizer::OptimizerGPT,
 };
 
 #[allow(unused)]
 pub use {
     crate::agents,
     crate::agents::agent::AgentGPT,
+    crate::common::cache::ResponseCache,
+    crate::common::config::LlmConfig,
+    crate::common::lifecycle::AgentEvent,
+    crate::common::reflection::{Critic, Evaluation},
+    crate::common::retry::RetryPolicy,
+    crate::common::scheduler::Scheduler,
+    crate::common::tokens::{TokenBudget, TruncationPolicy},
+    crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, ToolReply},
+    crate::common::usage::{PriceTable, Usage, UsageReport, UsageTracker},
     crate::common::utils::{
         AgentMessage, Capability, ClientType, Communication, ContextManager, Knowledge, Persona,
-        Planner, Reflection, Scope, Status, Task, TaskScheduler, Tool,
+        Planner, Recurrence, Reflection, ScheduledTask, Scope, Status, Task, TaskScheduler, Tool,
+        ToolName,
     },
     crate::traits::agent::Agent,
     crate::traits::composite::AgentFunctions,
-    crate::traits::functions::{AsyncFunctions, Collaborate, Executor, Functions, ReqResponse},
+    crate::traits::evaluator::Evaluator,
+    crate::traits::functions::{AsyncFunctions, Collaborate, Executor, Functions},
+    crate::traits::provider::{LlmProvider, TextStream},
+    crate::traits::tool::ToolHandler,
     anyhow::{Result, anyhow},
     async_trait::async_trait,
     auto_derive::Auto,
@@ -48,10 +60,18 @@ use tracing::debug;
 
 #[cfg(feature = "mem")]
 pub use {
-    crate::common::memory::load_long_term_memory, crate::common::memory::long_term_memory_context,
+    crate::common::memory::load_long_term_memory,
+    crate::common::memory::long_term_memory_context,
+    crate::common::memory::relevant_memory_context,
     crate::common::memory::save_long_term_memory,
+    crate::common::memory::save_shared_memory,
+    crate::common::memory::{FileStore, MemoryMatch, MemoryRecord, RecallPolicy},
+    crate::traits::memory::MemoryStore,
 };
 
+#[cfg(feature = "pinecone")]
+pub use crate::common::memory::PineconeStore;
+
 #[cfg(feature = "oai")]
 pub use {openai_dive::v1::models::FlagshipModel, openai_dive::v1::resources::chat::*};
 
@@ -117,6 +137,12 @@ pub struct AutoGPT {
     /// Scope permission: whether agents can access external resources or services.
     /// `true` grants permission to interact with external endpoints.
     pub external: bool,
+
+    /// Prices used to compute the cost of a run.
+    pub prices: PriceTable,
+
+    /// Tokens used by the agents during the most recent run.
+    pub usage: UsageTracker,
 }
 
 impl Default for AutoGPT {
@@ -130,6 +156,8 @@ impl Default for AutoGPT {
             crud: true,
             auth: false,
             external: true,
+            prices: PriceTable::from_env(),
+            usage: UsageTracker::default(),
         }
     }
 }
@@ -172,7 +200,11 @@ impl AutoGPT {
         self
     }
 
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
+    pub fn prices(mut self, prices: PriceTable) -> Self {
+        self.prices = prices;
+        self
+    }
+
     pub fn with<A>(mut self, agents: A) -> Self
     where
         A: Into<Vec<Arc<Mutex<Box<dyn AgentFunctions>>>>>,
@@ -181,7 +213,6 @@ impl AutoGPT {
         self
     }
 
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
     pub fn build(self) -> Result<Self> {
         Ok(Self {
             id: self.id,
@@ -192,16 +223,28 @@ impl AutoGPT {
             crud: self.crud,
             auth: self.auth,
             external: self.external,
+            prices: self.prices,
+            usage: self.usage,
         })
     }
 
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
+    /// Returns the tokens used by the agents during the most recent run, per model.
+    pub fn usage(&self) -> UsageReport {
+        self.usage.report()
+    }
+
+    /// Returns the cost in US dollars of the most recent run.
+    pub fn cost(&self) -> f64 {
+        self.usage.report().cost(&self.prices)
+    }
+
     pub async fn run(&self) -> Result<String> {
         if self.agents.is_empty() {
             return Err(anyhow!("No agents to run."));
         }
 
         let mut handles = Vec::with_capacity(self.agents.len());
+        let mut trackers = Vec::with_capacity(self.agents.len());
 
         let execute = self.execute;
         let browse = self.browse;
@@ -212,7 +255,12 @@ impl AutoGPT {
 
         for (i, agent_arc) in self.agents.iter().cloned().enumerate() {
             let agent_clone = Arc::clone(&agent_arc);
-            let agent_objective = agent_arc.lock().await.get_agent().objective().clone();
+            let (agent_objective, tracker) = {
+                let agent = agent_arc.lock().await;
+                let agent = agent.get_agent();
+                (agent.objective().clone(), agent.usage.clone())
+            };
+            trackers.push((tracker.report(), tracker));
 
             let tasks = Arc::new(Mutex::new(Task {
                 description: agent_objective.clone(),
@@ -256,6 +304,14 @@ impl AutoGPT {
 
         let results = join_all(handles).await;
 
+        let usage = trackers
+            .iter()
+            .fold(UsageReport::default(), |usage, (before, tracker)| {
+                usage.merge(&tracker.report().since(before))
+            });
+        debug!("Run usage:\n{}", usage.summary(&self.prices));
+        self.usage.replace(usage);
+
         let failures: Vec<_> = results
             .into_iter()
             .enumerate()
@@ -5,6 +5,7 @@
 //! ## Sub-modules
 //!
 //! - `architect`: Contains prompts related to the architect agent.
+//! - `autonomous`: Contains prompts related to the autonomous agent loop.
 //! - `backend`: Contains prompts related to the backend agent.
 //! - `designer`: Contains prompts related to the designer agent.
 //! - `frontend`: Contains prompts related to the frontend agent.
@@ -12,6 +13,7 @@
 //!
 
 pub mod architect;
+pub mod autonomous;
 pub mod backend;
 pub mod designer;
 pub mod frontend;
@@ -0,0 +1,82 @@
+//! # Providers module.
+//!
+//! This module contains the `LlmProvider` implementations for the model backends
+//! supported out of the box. Each backend lives behind its own feature flag.
+//!
+//! ## Sub-modules
+//!
+//! - `gemini`: Google Gemini, enabled by the `gem` feature.
+//! - `openai`: OpenAI, enabled by the `oai` feature.
+//! - `anthropic`: Anthropic Claude, enabled by the `cld` feature.
+//! - `xai`: xAI Grok, enabled by the `xai` feature.
+//! - `local`: Any OpenAI-compatible server (Ollama, llama.cpp, vLLM), always available.
+//! - `mock`: Scripted and cassette-backed responses for offline tests, always available.
+//! - `fallback`: Chain of providers tried in order until one succeeds, always available.
+//! - `sse`: Server-sent events decoding shared by the streaming implementations.
+//!
+
+#[cfg(feature = "gem")]
+pub mod gemini;
+
+#[cfg(feature = "oai")]
+pub mod openai;
+
+#[cfg(feature = "cld")]
+pub mod anthropic;
+
+#[cfg(feature = "xai")]
+pub mod xai;
+
+pub mod fallback;
+pub mod local;
+pub mod mock;
+pub mod sse;
+
+#[cfg(any(feature = "gem", feature = "cld"))]
+use crate::common::utils::Communication;
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Returns the media type of an encoded image, detected from its first bytes, and the image
+/// in base64, as inlined in multimodal requests.
+pub(crate) fn inline_image(image: &[u8]) -> (&'static str, String) {
+    let mime = match image {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => "image/webp",
+        _ => "image/jpeg",
+    };
+    (mime, STANDARD.encode(image))
+}
+
+/// Splits a conversation into its system instructions, joined by blank lines, and the
+/// remaining turns, for APIs that take the system prompt outside of the message list.
+#[cfg(any(feature = "gem", feature = "cld"))]
+pub(crate) fn split_system(messages: &[Communication]) -> (Option<String>, Vec<&Communication>) {
+    let (system, turns): (Vec<&Communication>, Vec<&Communication>) = messages
+        .iter()
+        .partition(|message| message.role == "system");
+
+    let system = (!system.is_empty()).then(|| {
+        system
+            .iter()
+            .map(|message| message.content.as_ref())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    });
+
+    (system, turns)
+}
@@ -0,0 +1,252 @@
+//! # Anthropic provider.
+//!
+//! `LlmProvider` implementation for the `anthropic-ai-sdk` client. Requests use the Messages
+//! REST API directly, with the key, version and base URL of the client, so that system
+//! instructions can be passed in the top-level `system` field. The model defaults to `claude-3-7-sonnet-latest` and the reply to 1024 tokens; the
+//! `seed` setting is not supported by the API and is ignored. Tools are offered as `tools` and
+//! called through `tool_use` content blocks.
+
+use crate::common::config::LlmConfig;
+use crate::common::retry::check_status;
+use crate::common::tokens::model_context_window;
+use crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::{Completion, Usage};
+use crate::common::utils::Communication;
+use crate::providers::{split_system, sse};
+use crate::traits::provider::{LlmProvider, TextStream};
+use anthropic_ai_sdk::client::AnthropicClient;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::{StreamExt, future};
+use reqwest::Response;
+use serde_json::{Value, json};
+
+/// Model used when the configuration does not name one.
+const ANTHROPIC_MODEL: &str = "claude-3-7-sonnet-latest";
+
+/// Reply length used when the configuration does not set one, as the API requires it.
+const ANTHROPIC_MAX_TOKENS: u32 = 1024;
+
+/// Posts a conversation to the Messages API, followed by its tool exchanges and offering
+/// `tools`.
+async fn post_messages(
+    client: &AnthropicClient,
+    messages: &[Communication],
+    exchanges: &[ToolExchange],
+    tools: &[ToolDefinition],
+    config: &LlmConfig,
+    stream: bool,
+) -> Result<Response> {
+    if client.get_api_key().is_empty() {
+        return Err(anyhow!("Claude: Missing API key, set ANTHROPIC_API_KEY."));
+    }
+
+    let (system, turns) = split_system(messages);
+    let mut turns: Vec<Value> = turns
+        .into_iter()
+        .map(|message| {
+            let role = if message.role == "assistant" {
+                "assistant"
+            } else {
+                "user"
+            };
+            json!({ "role": role, "content": message.content })
+        })
+        .collect();
+    for exchange in exchanges {
+        let mut blocks = Vec::new();
+        if !exchange.text.is_empty() {
+            blocks.push(json!({ "type": "text", "text": exchange.text }));
+        }
+        blocks.extend(exchange.calls.iter().map(|call| {
+            json!({
+                "type": "tool_use",
+                "id": call.id,
+                "name": call.name,
+                "input": call.arguments,
+            })
+        }));
+        turns.push(json!({ "role": "assistant", "content": blocks }));
+
+        let results: Vec<Value> = exchange
+            .calls
+            .iter()
+            .zip(&exchange.results)
+            .map(|(call, result)| {
+                json!({ "type": "tool_result", "tool_use_id": call.id, "content": result })
+            })
+            .collect();
+        turns.push(json!({ "role": "user", "content": results }));
+    }
+
+    let mut body = json!({
+        "model": config.model_or(ANTHROPIC_MODEL),
+        "messages": turns,
+        "max_tokens": config.max_tokens.unwrap_or(ANTHROPIC_MAX_TOKENS),
+        "stream": stream,
+    });
+    if let Some(system) = system {
+        body["system"] = json!(system);
+    }
+    if let Some(temperature) = config.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = config.top_p {
+        body["top_p"] = json!(top_p);
+    }
+    if !config.stop.is_empty() {
+        body["stop_sequences"] = json!(config.stop);
+    }
+    // The Messages API has no JSON mode outside of tool use, so `config.schema` is only
+    // enforced through the prompt built by `generate_structured`.
+    if !tools.is_empty() {
+        body["tools"] = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "input_schema": tool.parameters,
+                })
+            })
+            .collect();
+    }
+
+    let response = client
+        .get_client()
+        .post(format!(
+            "{}/messages",
+            client.get_api_base_url().trim_end_matches('/')
+        ))
+        .header("x-api-key", client.get_api_key())
+        .header("anthropic-version", client.get_api_version())
+        .json(&body)
+        .send()
+        .await?;
+
+    let context = if stream {
+        "Claude: Failed to stream content"
+    } else {
+        "Claude: Failed to generate content"
+    };
+    check_status(context, response).await
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.complete(&[Communication::user(request.to_string())])
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.complete_with(messages, &LlmConfig::default()).await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        Ok(self.completion(messages, config).await?.text)
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        Ok(self
+            .complete_with_tools(messages, &[], &[], config)
+            .await?
+            .completion)
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let response: Value = post_messages(self, messages, exchanges, tools, config, false)
+            .await?
+            .json()
+            .await?;
+
+        let blocks = response["content"].as_array().cloned().unwrap_or_default();
+        let text = blocks
+            .iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let calls = blocks
+            .iter()
+            .filter(|block| block["type"] == "tool_use")
+            .map(|block| {
+                ToolCall::new(
+                    block["id"].as_str().unwrap_or_default(),
+                    block["name"].as_str().unwrap_or_default(),
+                    block["input"].clone(),
+                )
+            })
+            .collect();
+        let model = response["model"]
+            .as_str()
+            .unwrap_or_else(|| config.model_or(ANTHROPIC_MODEL));
+        let usage = Usage::new(
+            response["usage"]["input_tokens"]
+                .as_u64()
+                .unwrap_or_default(),
+            response["usage"]["output_tokens"]
+                .as_u64()
+                .unwrap_or_default(),
+        );
+
+        Ok(ToolReply {
+            completion: Completion::new(text).model(model).usage(usage),
+            calls,
+        })
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.stream_with(request, &LlmConfig::default()).await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        let messages = [Communication::user(request.to_string())];
+        let response = post_messages(self, &messages, &[], &[], config, true).await?;
+
+        Ok(Box::pin(sse::events(response).filter_map(|data| {
+            future::ready(match data {
+                Ok(data) => text_delta(&data),
+                Err(err) => Some(Err(err)),
+            })
+        })))
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        model_context_window(config.model_or(ANTHROPIC_MODEL))
+    }
+}
+
+/// Extracts the text of a `content_block_delta` event, or the message of an `error` event.
+fn text_delta(data: &str) -> Option<Result<String>> {
+    let event: Value = match serde_json::from_str(data) {
+        Ok(event) => event,
+        Err(err) => return Some(Err(anyhow!("Claude: Failed to parse stream event: {err}"))),
+    };
+
+    match event["type"].as_str() {
+        Some("content_block_delta") => event["delta"]["text"]
+            .as_str()
+            .map(|text| Ok(text.to_string())),
+        Some("error") => Some(Err(anyhow!(
+            "Claude: {}",
+            event["error"]["message"]
+                .as_str()
+                .unwrap_or("Stream failed.")
+        ))),
+        _ => None,
+    }
+}
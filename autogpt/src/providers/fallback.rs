@@ -0,0 +1,254 @@
+//! # Fallback provider.
+//!
+//! Sends each request to an ordered chain of providers and returns the first successful reply,
+//! so that an outage of the primary provider does not abort a run. A provider is skipped when
+//! it returns an error or does not answer within the configured timeout.
+//!
+//! Embeddings are always taken from the first provider, since vectors produced by different
+//! models cannot be compared with each other. The context window of the chain is the smallest
+//! one of its providers, so that a request budgeted for the chain fits every one of them.
+//!
+//! The model named by an `LlmConfig` only applies to the first provider, since model names are
+//! specific to each provider. The other providers get the same sampling settings with their
+//! default model.
+//!
+//! A chain is built from the environment when `AI_PROVIDER` holds a comma-separated list:
+//!
+//! - `AI_PROVIDER`: Providers in order of preference, e.g. `gemini,openai,local`.
+//! - `AI_PROVIDER_TIMEOUT`: Seconds to wait for each provider before moving on, unset by default.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::retry::ProviderError;
+//! use autogpt::common::utils::ClientType;
+//! use autogpt::providers::fallback::FallbackClient;
+//! use autogpt::providers::mock::MockClient;
+//! use autogpt::traits::provider::LlmProvider;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let primary = MockClient::new().fail(ProviderError::new(503, "Service unavailable"));
+//! let backup = MockClient::new().then("Hello from the backup!");
+//!
+//! let mut client = ClientType::Fallback(
+//!     FallbackClient::new()
+//!         .provider(ClientType::Mock(primary))
+//!         .provider(ClientType::Mock(backup)),
+//! );
+//!
+//! assert_eq!(client.chat("Say hello").await?, "Hello from the backup!");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::config::LlmConfig;
+use crate::common::tokens::DEFAULT_CONTEXT_WINDOW;
+use crate::common::tools::{ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::Completion;
+use crate::common::utils::{ClientType, Communication};
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use std::env::var;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Client that falls back to the next provider of a chain when one fails.
+#[derive(Debug, Clone, Default)]
+pub struct FallbackClient {
+    /// Providers in order of preference.
+    pub providers: Vec<ClientType>,
+    /// How long to wait for each provider before moving on to the next one.
+    pub timeout: Option<Duration>,
+}
+
+impl FallbackClient {
+    /// Creates an empty chain. Every call fails until providers are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `client` to the chain.
+    pub fn provider(mut self, client: ClientType) -> Self {
+        self.providers.push(client);
+        self
+    }
+
+    /// Sets how long to wait for each provider before moving on to the next one.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Builds a chain from a comma-separated list of provider names, reading the timeout from
+    /// `AI_PROVIDER_TIMEOUT`.
+    pub fn from_providers(providers: &str) -> Self {
+        let mut chain = providers
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .fold(Self::new(), |chain, name| {
+                chain.provider(ClientType::from_provider(name))
+            });
+
+        if let Some(seconds) = var("AI_PROVIDER_TIMEOUT")
+            .ok()
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|seconds| *seconds > 0.0 && seconds.is_finite())
+        {
+            chain.timeout = Some(Duration::from_secs_f64(seconds));
+        }
+
+        chain
+    }
+
+    /// Runs `call` against each provider in turn, along with its position in the chain, until
+    /// one succeeds.
+    async fn first_success<'a, T, F, Fut>(&'a mut self, action: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut(usize, &'a mut ClientType) -> Fut,
+        Fut: Future<Output = Result<T>> + 'a,
+    {
+        let mut failures = Vec::new();
+        let count = self.providers.len();
+
+        for (index, client) in self.providers.iter_mut().enumerate() {
+            let name = client.name();
+            let result = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, call(index, client))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("Timed out after {timeout:?}."))),
+                None => call(index, client).await,
+            };
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    if index + 1 < count {
+                        warn!(
+                            "Fallback: {name} failed to {action}, trying the next provider: {err}"
+                        );
+                    }
+                    failures.push(format!("{name}: {err}"));
+                }
+            }
+        }
+
+        if failures.is_empty() {
+            return Err(anyhow!("Fallback: No providers configured."));
+        }
+        Err(anyhow!(
+            "Fallback: Every provider failed to {action}. {}",
+            failures.join("; ")
+        ))
+    }
+}
+
+/// Returns `config` without its model, for the providers after the first one.
+fn secondary(config: &LlmConfig) -> LlmConfig {
+    LlmConfig {
+        model: None,
+        ..config.clone()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FallbackClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.first_success("generate content", |_, client| client.chat(request))
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.first_success("generate content", |_, client| client.complete(messages))
+            .await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        let secondary = secondary(config);
+        self.first_success("generate content", |index, client| {
+            client.complete_with(messages, if index == 0 { config } else { &secondary })
+        })
+        .await
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        let secondary = secondary(config);
+        self.first_success("generate content", |index, client| {
+            client.completion(messages, if index == 0 { config } else { &secondary })
+        })
+        .await
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let secondary = secondary(config);
+        self.first_success("generate content", |index, client| {
+            let config = if index == 0 { config } else { &secondary };
+            client.complete_with_tools(messages, exchanges, tools, config)
+        })
+        .await
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.first_success("stream content", |_, client| client.stream(request))
+            .await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        let secondary = secondary(config);
+        self.first_success("stream content", |index, client| {
+            client.stream_with(request, if index == 0 { config } else { &secondary })
+        })
+        .await
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        match self.providers.first_mut() {
+            Some(client) => client.embed(content).await,
+            None => Err(anyhow!("Fallback: No providers configured.")),
+        }
+    }
+
+    async fn image(&mut self, request: &str) -> Result<Vec<u8>> {
+        self.first_success("generate an image", |_, client| client.image(request))
+            .await
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        let secondary = secondary(config);
+        self.first_success("describe an image", |index, client| {
+            client.describe_image(prompt, image, if index == 0 { config } else { &secondary })
+        })
+        .await
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        let mut smallest: Option<usize> = None;
+        for client in &self.providers {
+            let window = client.context_window(config).await;
+            smallest = Some(smallest.map_or(window, |smallest| smallest.min(window)));
+        }
+        smallest.unwrap_or(DEFAULT_CONTEXT_WINDOW)
+    }
+}
@@ -0,0 +1,377 @@
+//! # Gemini provider.
+//!
+//! `LlmProvider` implementation for the `gems` client. Chat requests and streams use the
+//! `generateContent` and `streamGenerateContent` REST endpoints directly, so that system
+//! instructions and previous model turns keep their roles and every `LlmConfig` setting is
+//! sent. Tools are offered as function declarations. Requests are authenticated with the key
+//! of the client, and the model defaults to the client's; models the client does not know,
+//! such as newer releases, are selected with `LlmConfig::model`.
+
+use crate::common::config::LlmConfig;
+use crate::common::retry::check_status;
+use crate::common::tokens::model_context_window;
+use crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::{Completion, Usage};
+use crate::common::utils::Communication;
+use crate::providers::{inline_image, split_system, sse};
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::{StreamExt, future};
+use gems::{
+    Client as GeminiClient,
+    embed::EmbeddingBuilder,
+    imagen::ImageGenBuilder,
+    messages::{Content, Message},
+    models::Model,
+    traits::CTrait,
+};
+use reqwest::{Client as ReqClient, Response};
+use serde_json::{Value, json};
+
+/// Base URL of the Gemini REST API.
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Returns the model requests are sent to.
+fn gemini_model(client: &GeminiClient, config: &LlmConfig) -> String {
+    match &config.model {
+        Some(model) => model.clone(),
+        None => client.get_model().to_string(),
+    }
+}
+
+/// Returns the API key of `client`.
+fn api_key(client: &GeminiClient) -> Result<String> {
+    client
+        .get_api_key()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| anyhow!("Gemini: Missing API key, set GEMINI_API_KEY."))
+}
+
+/// Builds a `generateContent` body, mapping `assistant` turns to Gemini's `model` role and
+/// appending the tool exchanges as function calls and responses.
+fn generate_content_body(
+    messages: &[Communication],
+    exchanges: &[ToolExchange],
+    tools: &[ToolDefinition],
+    config: &LlmConfig,
+) -> Value {
+    let (system, turns) = split_system(messages);
+    let mut contents: Vec<Value> = turns
+        .into_iter()
+        .map(|message| {
+            let role = if message.role == "assistant" {
+                "model"
+            } else {
+                "user"
+            };
+            json!({ "role": role, "parts": [{ "text": message.content }] })
+        })
+        .collect();
+
+    for exchange in exchanges {
+        let mut parts = Vec::new();
+        if !exchange.text.is_empty() {
+            parts.push(json!({ "text": exchange.text }));
+        }
+        parts.extend(
+            exchange.calls.iter().map(
+                |call| json!({ "functionCall": { "name": call.name, "args": call.arguments } }),
+            ),
+        );
+        contents.push(json!({ "role": "model", "parts": parts }));
+
+        let responses: Vec<Value> = exchange
+            .calls
+            .iter()
+            .zip(&exchange.results)
+            .map(|(call, result)| {
+                json!({
+                    "functionResponse": { "name": call.name, "response": { "output": result } }
+                })
+            })
+            .collect();
+        contents.push(json!({ "role": "user", "parts": responses }));
+    }
+
+    let mut body = json!({ "contents": contents });
+    if let Some(system) = system {
+        body["system_instruction"] = json!({ "parts": [{ "text": system }] });
+    }
+    if !tools.is_empty() {
+        let declarations: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                })
+            })
+            .collect();
+        body["tools"] = json!([{ "functionDeclarations": declarations }]);
+    }
+
+    let mut generation = json!({});
+    if let Some(temperature) = config.temperature {
+        generation["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = config.top_p {
+        generation["topP"] = json!(top_p);
+    }
+    if let Some(max_tokens) = config.max_tokens {
+        generation["maxOutputTokens"] = json!(max_tokens);
+    }
+    if !config.stop.is_empty() {
+        generation["stopSequences"] = json!(config.stop);
+    }
+    if let Some(seed) = config.seed {
+        generation["seed"] = json!(seed);
+    }
+    if let Some(schema) = &config.schema {
+        generation["responseMimeType"] = json!("application/json");
+        generation["responseJsonSchema"] = schema.clone();
+    }
+    if generation
+        .as_object()
+        .is_some_and(|generation| !generation.is_empty())
+    {
+        body["generationConfig"] = generation;
+    }
+    body
+}
+
+/// Posts a conversation to `generateContent`, or to `streamGenerateContent` as server-sent
+/// events when `stream` is set.
+async fn post_contents(
+    client: &GeminiClient,
+    messages: &[Communication],
+    exchanges: &[ToolExchange],
+    tools: &[ToolDefinition],
+    config: &LlmConfig,
+    stream: bool,
+) -> Result<Response> {
+    let api_key = api_key(client)?;
+    let model = gemini_model(client, config);
+    let (method, context) = if stream {
+        (
+            "streamGenerateContent?alt=sse",
+            "Gemini: Failed to stream content",
+        )
+    } else {
+        ("generateContent", "Gemini: Failed to generate content")
+    };
+
+    let response = ReqClient::new()
+        .post(format!("{GEMINI_BASE_URL}/models/{model}:{method}"))
+        .header("x-goog-api-key", api_key)
+        .json(&generate_content_body(messages, exchanges, tools, config))
+        .send()
+        .await?;
+
+    check_status(context, response).await
+}
+
+/// Concatenates the text parts of a `generateContent` response or stream chunk.
+fn candidate_text(response: &Value) -> String {
+    response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part["text"].as_str())
+        .collect()
+}
+
+#[async_trait]
+impl LlmProvider for GeminiClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.complete(&[Communication::user(request.to_string())])
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.complete_with(messages, &LlmConfig::default()).await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        Ok(self.completion(messages, config).await?.text)
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        Ok(self
+            .complete_with_tools(messages, &[], &[], config)
+            .await?
+            .completion)
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let response: Value = post_contents(self, messages, exchanges, tools, config, false)
+            .await?
+            .json()
+            .await?;
+
+        let model = response["modelVersion"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| gemini_model(self, config));
+        let usage = Usage::new(
+            response["usageMetadata"]["promptTokenCount"]
+                .as_u64()
+                .unwrap_or_default(),
+            response["usageMetadata"]["candidatesTokenCount"]
+                .as_u64()
+                .unwrap_or_default(),
+        );
+
+        // Gemini does not always identify function calls, so number them when it does not.
+        let calls = response["candidates"][0]["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|part| part.get("functionCall"))
+            .enumerate()
+            .map(|(index, call)| {
+                let id = call["id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("call_{index}"));
+                ToolCall::new(
+                    id,
+                    call["name"].as_str().unwrap_or_default(),
+                    call["args"].clone(),
+                )
+            })
+            .collect();
+
+        Ok(ToolReply {
+            completion: Completion::new(candidate_text(&response))
+                .model(model)
+                .usage(usage),
+            calls,
+        })
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.stream_with(request, &LlmConfig::default()).await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        let messages = [Communication::user(request.to_string())];
+        let response = post_contents(self, &messages, &[], &[], config, true).await?;
+
+        Ok(Box::pin(sse::events(response).filter_map(|data| {
+            future::ready(match data {
+                Ok(data) => text_delta(&data),
+                Err(err) => Some(Err(err)),
+            })
+        })))
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        let params = EmbeddingBuilder::default()
+            .model(Model::Embedding)
+            .input(Message::User {
+                content: Content::Text(content.into()),
+                name: None,
+            })
+            .build()?;
+
+        let model = self.get_model();
+        self.set_model(Model::Embedding);
+        let response = self.embeddings().create(params).await;
+        self.set_model(model);
+
+        let embedding = response
+            .map_err(|err| anyhow!("Gemini: Failed to embed content: {err}"))?
+            .embedding
+            .ok_or_else(|| anyhow!("Gemini: No embedding returned."))?;
+
+        Ok(embedding.values)
+    }
+
+    async fn image(&mut self, request: &str) -> Result<Vec<u8>> {
+        let model = self.get_model();
+        self.set_model(Model::FlashExpImage);
+
+        let input = Message::User {
+            content: Content::Text(request.into()),
+            name: None,
+        };
+
+        let params = ImageGenBuilder::default()
+            .model(Model::FlashExpImage)
+            .input(input)
+            .build()?;
+
+        let image_bytes = self.images().generate(params).await;
+        self.set_model(model);
+
+        image_bytes.map_err(|err| anyhow!("Gemini: Failed to generate image: {err}"))
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        let (mime, data) = inline_image(image);
+        let mut body = generate_content_body(&[], &[], &[], config);
+        body["contents"] = json!([{
+            "role": "user",
+            "parts": [
+                { "text": prompt },
+                { "inline_data": { "mime_type": mime, "data": data } },
+            ],
+        }]);
+
+        let response = ReqClient::new()
+            .post(format!(
+                "{GEMINI_BASE_URL}/models/{}:generateContent",
+                gemini_model(self, config)
+            ))
+            .header("x-goog-api-key", api_key(self)?)
+            .json(&body)
+            .send()
+            .await?;
+        let response: Value = check_status("Gemini: Failed to describe image", response)
+            .await?
+            .json()
+            .await?;
+
+        Ok(candidate_text(&response))
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        model_context_window(&gemini_model(self, config))
+    }
+}
+
+/// Extracts the text of a stream chunk, or the message of an error chunk.
+fn text_delta(data: &str) -> Option<Result<String>> {
+    let chunk: Value = match serde_json::from_str(data) {
+        Ok(chunk) => chunk,
+        Err(err) => return Some(Err(anyhow!("Gemini: Failed to parse stream chunk: {err}"))),
+    };
+
+    if let Some(message) = chunk["error"]["message"].as_str() {
+        return Some(Err(anyhow!("Gemini: {message}")));
+    }
+
+    let text = candidate_text(&chunk);
+    (!text.is_empty()).then_some(Ok(text))
+}
@@ -0,0 +1,417 @@
+//! # Local provider.
+//!
+//! `LlmProvider` implementation for any server exposing the OpenAI-compatible
+//! `/chat/completions` and `/embeddings` endpoints, such as Ollama, llama.cpp or vLLM.
+//! Conversations keep their `system`, `user` and `assistant` roles, and any other role is sent
+//! as `user`, since OpenAI-compatible servers reject unknown ones.
+//!
+//! The client is configured through the following environment variables:
+//!
+//! - `LOCAL_BASE_URL`: Base URL of the API, defaults to `http://127.0.0.1:11434/v1`.
+//! - `LOCAL_MODEL`: Chat model name, defaults to `llama3.2`.
+//! - `LOCAL_EMBEDDING_MODEL`: Embedding model name, defaults to `nomic-embed-text`.
+//! - `LOCAL_API_KEY`: Optional bearer token for servers that require one.
+//! - `LOCAL_CONTEXT_LENGTH`: Context window of the served model, when the default for its name
+//!   does not match the server configuration.
+//!
+//! Requests sent through `complete_with` and `stream_with` use the model and sampling settings
+//! of the given `LlmConfig` instead. Tools are offered through the `tools` field of the request,
+//! and images are inlined as `image_url` content parts holding a data URL.
+
+use crate::common::config::LlmConfig;
+use crate::common::retry::check_status;
+use crate::common::tokens::model_context_window;
+use crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::{Completion, Usage};
+use crate::common::utils::Communication;
+use crate::providers::{inline_image, sse};
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use reqwest::{Client as ReqClient, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::env::var;
+
+/// Default base URL, matching Ollama's OpenAI-compatible endpoint.
+pub const DEFAULT_LOCAL_BASE_URL: &str = "http://127.0.0.1:11434/v1";
+
+/// Client for an OpenAI-compatible model server.
+#[derive(Debug, Clone)]
+pub struct LocalClient {
+    /// Base URL of the API, without the trailing endpoint path.
+    pub base_url: String,
+    /// Model used for chat completions.
+    pub model: String,
+    /// Model used for embeddings.
+    pub embedding_model: String,
+    /// Optional bearer token sent with every request.
+    pub api_key: Option<String>,
+    /// Context window of the chat model, inferred from its name when unset.
+    pub context_length: Option<usize>,
+    /// Provider name used as the prefix of error messages.
+    label: &'static str,
+    /// Underlying HTTP client.
+    http: ReqClient,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletion {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatChoiceMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoiceMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ChatToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatToolCall {
+    id: String,
+    function: ChatFunction,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatFunction {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f64>,
+}
+
+impl Default for LocalClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCAL_BASE_URL, "llama3.2")
+    }
+}
+
+impl LocalClient {
+    /// Creates a new client for the given base URL and chat model.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the API, e.g. `http://127.0.0.1:11434/v1`.
+    /// * `model` - Name of the chat model served at that URL.
+    ///
+    /// # Returns
+    ///
+    /// (`LocalClient`): A client using `nomic-embed-text` for embeddings and no API key.
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+            embedding_model: "nomic-embed-text".to_string(),
+            api_key: None,
+            context_length: None,
+            label: "Local",
+            http: ReqClient::new(),
+        }
+    }
+
+    /// Creates a new client from the `LOCAL_*` environment variables.
+    pub fn from_env() -> Self {
+        let base_url = var("LOCAL_BASE_URL").unwrap_or_else(|_| DEFAULT_LOCAL_BASE_URL.to_string());
+        let model = var("LOCAL_MODEL").unwrap_or_else(|_| "llama3.2".to_string());
+
+        let mut client = Self::new(base_url, model);
+        if let Ok(embedding_model) = var("LOCAL_EMBEDDING_MODEL") {
+            client.embedding_model = embedding_model;
+        }
+        client.api_key = var("LOCAL_API_KEY").ok().filter(|key| !key.is_empty());
+        client.context_length = var("LOCAL_CONTEXT_LENGTH")
+            .ok()
+            .and_then(|length| length.parse().ok());
+        client
+    }
+
+    /// Sets the embedding model.
+    pub fn embedding_model(mut self, embedding_model: impl Into<String>) -> Self {
+        self.embedding_model = embedding_model.into();
+        self
+    }
+
+    /// Sets the bearer token sent with every request.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sets the context window of the chat model.
+    pub fn context_length(mut self, tokens: usize) -> Self {
+        self.context_length = Some(tokens);
+        self
+    }
+
+    /// Sets the provider name used in error messages, for hosted APIs reached through this
+    /// client.
+    #[cfg(any(feature = "oai", feature = "xai"))]
+    pub(crate) fn label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    fn post(&self, endpoint: &str, body: &Value) -> RequestBuilder {
+        let request = self
+            .http
+            .post(format!("{}/{}", self.base_url, endpoint))
+            .json(body);
+
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Posts a `chat/completions` body and parses the reply, with any tool calls it makes.
+    async fn send_chat(&self, body: &Value, config: &LlmConfig) -> Result<ToolReply> {
+        let response = self.post("chat/completions", body).send().await?;
+        let context = format!("{}: Failed to generate content", self.label);
+        let response = check_status(&context, response).await?;
+
+        let completion: ChatCompletion = response.json().await?;
+
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| anyhow!("{}: No choices returned.", self.label))?;
+
+        let mut reply = Completion::new(message.content.unwrap_or_default()).model(
+            completion
+                .model
+                .unwrap_or_else(|| config.model_or(&self.model).to_string()),
+        );
+        if let Some(usage) = completion.usage {
+            reply = reply.usage(Usage::new(usage.prompt_tokens, usage.completion_tokens));
+        }
+
+        let calls = message
+            .tool_calls
+            .into_iter()
+            .map(|call| {
+                // Arguments arrive as a JSON-encoded string, which some servers leave malformed.
+                let arguments = serde_json::from_str(&call.function.arguments)
+                    .unwrap_or(Value::String(call.function.arguments));
+                ToolCall::new(call.id, call.function.name, arguments)
+            })
+            .collect();
+
+        Ok(ToolReply {
+            completion: reply,
+            calls,
+        })
+    }
+
+    fn chat_body(&self, messages: &[Communication], config: &LlmConfig, stream: bool) -> Value {
+        let mut body = json!({
+            "model": config.model_or(&self.model),
+            "messages": messages.iter().map(chat_message).collect::<Vec<_>>(),
+            "stream": stream,
+        });
+
+        if let Some(temperature) = config.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = config.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(max_tokens) = config.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if !config.stop.is_empty() {
+            body["stop"] = json!(config.stop);
+        }
+        if let Some(seed) = config.seed {
+            body["seed"] = json!(seed);
+        }
+        if let Some(schema) = &config.schema {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": { "name": "reply", "schema": schema },
+            });
+        }
+        body
+    }
+}
+
+/// Renders a communication as an OpenAI chat message, with a role the API accepts.
+fn chat_message(message: &Communication) -> Value {
+    let role = match message.role.as_ref() {
+        "system" | "assistant" => message.role.as_ref(),
+        _ => "user",
+    };
+    json!({ "role": role, "content": message.content })
+}
+
+/// Renders a conversation and its tool exchanges as OpenAI chat messages.
+fn tool_messages(messages: &[Communication], exchanges: &[ToolExchange]) -> Vec<Value> {
+    let mut rendered: Vec<Value> = messages.iter().map(chat_message).collect();
+    for exchange in exchanges {
+        let calls: Vec<Value> = exchange
+            .calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                })
+            })
+            .collect();
+        rendered.push(json!({
+            "role": "assistant",
+            "content": exchange.text,
+            "tool_calls": calls,
+        }));
+        for (call, result) in exchange.calls.iter().zip(&exchange.results) {
+            rendered.push(json!({
+                "role": "tool",
+                "tool_call_id": call.id,
+                "content": result,
+            }));
+        }
+    }
+    rendered
+}
+
+#[async_trait]
+impl LlmProvider for LocalClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.complete(&[Communication::user(request.to_string())])
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.complete_with(messages, &LlmConfig::default()).await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        Ok(self.completion(messages, config).await?.text)
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        let body = self.chat_body(messages, config, false);
+        Ok(self.send_chat(&body, config).await?.completion)
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let mut body = self.chat_body(messages, config, false);
+        body["messages"] = json!(tool_messages(messages, exchanges));
+        if !tools.is_empty() {
+            body["tools"] = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": tool.name,
+                            "description": tool.description,
+                            "parameters": tool.parameters,
+                        },
+                    })
+                })
+                .collect();
+        }
+        self.send_chat(&body, config).await
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.stream_with(request, &LlmConfig::default()).await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        let body = self.chat_body(&[Communication::user(request.to_string())], config, true);
+        let response = self.post("chat/completions", &body).send().await?;
+        let context = format!("{}: Failed to stream content", self.label);
+        let response = check_status(&context, response).await?;
+
+        Ok(sse::openai_deltas(response))
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        let (mime, data) = inline_image(image);
+        let mut body = self.chat_body(&[], config, false);
+        body["messages"] = json!([{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": prompt },
+                { "type": "image_url", "image_url": { "url": format!("data:{mime};base64,{data}") } },
+            ],
+        }]);
+        Ok(self.send_chat(&body, config).await?.completion.text)
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        let body = json!({
+            "model": self.embedding_model,
+            "input": content,
+        });
+
+        let response = self.post("embeddings", &body).send().await?;
+        let context = format!("{}: Failed to embed content", self.label);
+        let response = check_status(&context, response).await?;
+
+        let embeddings: EmbeddingResponse = response.json().await?;
+
+        embeddings
+            .data
+            .into_iter()
+            .next()
+            .map(|data| data.embedding)
+            .ok_or_else(|| anyhow!("{}: No embedding returned.", self.label))
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        self.context_length
+            .unwrap_or_else(|| model_context_window(config.model_or(&self.model)))
+    }
+}
@@ -0,0 +1,429 @@
+//! # Mock provider.
+//!
+//! Deterministic `LlmProvider` for running agents without network access. A `MockClient`
+//! works in one of three modes:
+//!
+//! - **Script**: replies are taken from rules keyed by a prompt fragment, then from a queue
+//!   consumed in call order, then from an optional fallback reply. Failures can be queued
+//!   too, to exercise retries.
+//! - **Record**: every `chat` call is forwarded to a real client and the exchange is appended
+//!   to a JSON cassette file.
+//! - **Replay**: replies are served from a previously recorded cassette file.
+//!
+//! Streaming resolves the reply like `chat` does and yields it one word at a time.
+//!
+//! Tool calls are scripted as replies too: `then_call` queues a reply holding a `tool_calls`
+//! list, which `complete_with_tools` turns into calls rather than text.
+//!
+//! Embeddings are derived from the hashed words of the content, so they are stable across
+//! runs and similar texts produce similar vectors.
+//!
+//! Clones share their state, so a test can keep a handle to inspect `calls` and `configs` after the client
+//! has been moved into an agent.
+//!
+//! When built from the environment with `AI_PROVIDER=mock`, the following variables apply:
+//!
+//! - `MOCK_CASSETTE`: Path of the cassette file to replay or record.
+//! - `MOCK_MODE`: `replay` (default when a cassette is set) or `record`.
+//! - `MOCK_PROVIDER`: Provider wrapped in record mode, defaults to `gemini`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::utils::ClientType;
+//! use autogpt::providers::mock::MockClient;
+//! use autogpt::traits::provider::LlmProvider;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mock = MockClient::new()
+//!     .on("language", "python")
+//!     .then("first")
+//!     .then("second");
+//!
+//! let mut client = ClientType::Mock(mock.clone());
+//!
+//! assert_eq!(client.chat("Which language?").await?, "python");
+//! assert_eq!(client.chat("Anything").await?, "first");
+//! assert_eq!(client.chat("Anything").await?, "second");
+//! assert_eq!(mock.calls().len(), 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::config::LlmConfig;
+use crate::common::retry::ProviderError;
+use crate::common::tokens::DEFAULT_CONTEXT_WINDOW;
+use crate::common::tools::{ToolCall, ToolDefinition, ToolExchange, ToolReply, tool_transcript};
+use crate::common::usage::Completion;
+use crate::common::utils::{ClientType, Communication};
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::env::var;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Number of dimensions of the embeddings produced by the mock.
+pub const MOCK_EMBEDDING_DIMENSIONS: usize = 256;
+
+/// A single recorded prompt and reply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The prompt sent to the provider.
+    pub request: String,
+    /// The text the provider replied with.
+    pub response: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MockMode {
+    Script,
+    Replay,
+    Record(PathBuf),
+}
+
+#[derive(Debug)]
+struct MockState {
+    mode: MockMode,
+    rules: Vec<(String, String)>,
+    queue: VecDeque<std::result::Result<String, ProviderError>>,
+    fallback: Option<String>,
+    cassette: Vec<Interaction>,
+    used: Vec<bool>,
+    calls: Vec<String>,
+    configs: Vec<LlmConfig>,
+    context_length: usize,
+}
+
+/// Reply scripted by `then_call`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ScriptedCalls {
+    tool_calls: Vec<ToolCall>,
+}
+
+/// Scripted, recording or replaying model client.
+#[derive(Debug, Clone)]
+pub struct MockClient {
+    state: Arc<Mutex<MockState>>,
+    inner: Option<Box<ClientType>>,
+}
+
+impl Default for MockClient {
+    fn default() -> Self {
+        Self::with_mode(MockMode::Script, Vec::new(), None)
+    }
+}
+
+impl MockClient {
+    /// Creates an empty scripted client. Every call fails until replies are scripted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_mode(mode: MockMode, cassette: Vec<Interaction>, inner: Option<ClientType>) -> Self {
+        let used = vec![false; cassette.len()];
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                mode,
+                rules: Vec::new(),
+                queue: VecDeque::new(),
+                fallback: None,
+                cassette,
+                used,
+                calls: Vec::new(),
+                configs: Vec::new(),
+                context_length: DEFAULT_CONTEXT_WINDOW,
+            })),
+            inner: inner.map(Box::new),
+        }
+    }
+
+    /// Replies with `response` whenever the prompt contains `fragment`.
+    ///
+    /// Rules are checked in insertion order and are never consumed.
+    pub fn on(self, fragment: impl Into<String>, response: impl Into<String>) -> Self {
+        self.lock().rules.push((fragment.into(), response.into()));
+        self
+    }
+
+    /// Queues `response` as the reply to the next call that matches no rule.
+    pub fn then(self, response: impl Into<String>) -> Self {
+        self.lock().queue.push_back(Ok(response.into()));
+        self
+    }
+
+    /// Queues a call of the tool named `name` with `input` as the reply to the next call that
+    /// matches no rule.
+    pub fn then_call(self, name: impl Into<String>, input: impl Into<String>) -> Self {
+        let calls = ScriptedCalls {
+            tool_calls: vec![ToolCall::new(
+                "call_0",
+                name,
+                json!({ "input": input.into() }),
+            )],
+        };
+        let reply = serde_json::to_string(&calls).unwrap_or_default();
+        self.then(reply)
+    }
+
+    /// Queues `error` as the failure of the next call that matches no rule.
+    pub fn fail(self, error: ProviderError) -> Self {
+        self.lock().queue.push_back(Err(error));
+        self
+    }
+
+    /// Replies with `response` when no rule matches and the queue is empty.
+    pub fn fallback(self, response: impl Into<String>) -> Self {
+        self.lock().fallback = Some(response.into());
+        self
+    }
+
+    /// Sets the context window reported to token budgets, to exercise truncation in tests.
+    pub fn context_length(self, tokens: usize) -> Self {
+        self.lock().context_length = tokens;
+        self
+    }
+
+    /// Creates a client that serves the replies stored in a cassette file.
+    ///
+    /// Each call is answered by the first unused interaction with the same prompt, or by the
+    /// next unused interaction in recording order if the prompt changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid cassette.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let cassette: Vec<Interaction> = serde_json::from_str(&content)?;
+        Ok(Self::with_mode(MockMode::Replay, cassette, None))
+    }
+
+    /// Creates a client that forwards calls to `inner` and appends every exchange to a
+    /// cassette file, which is rewritten after each call.
+    pub fn record(inner: ClientType, path: impl Into<PathBuf>) -> Self {
+        Self::with_mode(MockMode::Record(path.into()), Vec::new(), Some(inner))
+    }
+
+    /// Builds a client from the `MOCK_*` environment variables.
+    pub fn from_env() -> Self {
+        let Ok(cassette) = var("MOCK_CASSETTE") else {
+            return Self::new();
+        };
+
+        match var("MOCK_MODE").as_deref() {
+            Ok("record") => {
+                let provider = var("MOCK_PROVIDER").unwrap_or_else(|_| "gemini".to_string());
+                Self::record(ClientType::from_provider(&provider), cassette)
+            }
+            _ => Self::replay(&cassette)
+                .unwrap_or_else(|err| panic!("Failed to load cassette `{cassette}`: {err}")),
+        }
+    }
+
+    /// Returns every prompt received so far, in call order.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    /// Returns the configuration passed with every `complete_with` and `stream_with` call, in
+    /// call order.
+    pub fn configs(&self) -> Vec<LlmConfig> {
+        self.lock().configs.clone()
+    }
+
+    /// Returns the interactions recorded or loaded so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock().cassette.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn scripted_reply(state: &mut MockState, request: &str) -> Result<String> {
+        if let Some((_, response)) = state
+            .rules
+            .iter()
+            .find(|(fragment, _)| request.contains(fragment.as_str()))
+        {
+            return Ok(response.clone());
+        }
+
+        match state.queue.pop_front() {
+            Some(reply) => Ok(reply?),
+            None => state
+                .fallback
+                .clone()
+                .ok_or_else(|| anyhow!("Mock: No scripted response left for prompt.")),
+        }
+    }
+
+    fn replayed_reply(state: &mut MockState, request: &str) -> Option<String> {
+        let position = state
+            .cassette
+            .iter()
+            .zip(&state.used)
+            .position(|(interaction, used)| !used && interaction.request == request)
+            .or_else(|| {
+                let next = state.used.iter().position(|used| !used);
+                if next.is_some() {
+                    warn!("Mock: No recorded interaction for this prompt, replaying in order.");
+                }
+                next
+            })?;
+
+        state.used[position] = true;
+        Some(state.cassette[position].response.clone())
+    }
+
+    fn save(path: &Path, cassette: &[Interaction]) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(cassette)?)?;
+        Ok(())
+    }
+}
+
+/// Hashes the words of `content` into a normalized bag-of-words vector.
+fn hashed_embedding(content: &str) -> Vec<f64> {
+    let mut values = vec![0.0; MOCK_EMBEDDING_DIMENSIONS];
+
+    for word in content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let mut hasher = DefaultHasher::new();
+        word.to_lowercase().hash(&mut hasher);
+        values[(hasher.finish() as usize) % MOCK_EMBEDDING_DIMENSIONS] += 1.0;
+    }
+
+    let norm = values.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        values.iter_mut().for_each(|v| *v /= norm);
+    }
+    values
+}
+
+#[async_trait]
+impl LlmProvider for MockClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        let mode = {
+            let mut state = self.lock();
+            state.calls.push(request.to_string());
+            state.mode.clone()
+        };
+
+        match mode {
+            MockMode::Script => Self::scripted_reply(&mut self.lock(), request),
+            MockMode::Replay => Self::replayed_reply(&mut self.lock(), request)
+                .ok_or_else(|| anyhow!("Mock: Cassette exhausted.")),
+            MockMode::Record(path) => {
+                let inner = self
+                    .inner
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("Mock: No client to record from."))?;
+                let response = inner.chat(request).await?;
+
+                let mut state = self.lock();
+                state.cassette.push(Interaction {
+                    request: request.to_string(),
+                    response: response.clone(),
+                });
+                state.used.push(true);
+                Self::save(&path, &state.cassette)?;
+
+                Ok(response)
+            }
+        }
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        self.lock().configs.push(config.clone());
+        self.complete(messages).await
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        _tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let messages = tool_transcript(messages, exchanges);
+        let reply = self.complete_with(&messages, config).await?;
+
+        Ok(match serde_json::from_str::<ScriptedCalls>(&reply) {
+            Ok(scripted) => ToolReply {
+                completion: Completion::new(""),
+                calls: scripted.tool_calls,
+            },
+            Err(_) => ToolReply::answer(Completion::new(reply)),
+        })
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        let reply = self.chat(request).await?;
+        let deltas: Vec<Result<String>> = reply
+            .split_inclusive(' ')
+            .map(|delta| Ok(delta.to_string()))
+            .collect();
+
+        Ok(Box::pin(stream::iter(deltas)))
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        self.lock().configs.push(config.clone());
+        self.stream(request).await
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.embed(content).await,
+            None => Ok(hashed_embedding(content)),
+        }
+    }
+
+    async fn image(&mut self, request: &str) -> Result<Vec<u8>> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.image(request).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.describe_image(prompt, image, config).await,
+            None => {
+                self.lock().configs.push(config.clone());
+                self.chat(prompt).await
+            }
+        }
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        match self.inner.as_ref() {
+            Some(inner) => inner.context_window(config).await,
+            None => self.lock().context_length,
+        }
+    }
+}
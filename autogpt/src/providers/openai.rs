@@ -0,0 +1,116 @@
+//! # OpenAI provider.
+//!
+//! `LlmProvider` implementation for the `openai_dive` client. Chat requests and streams go
+//! through the OpenAI-compatible `LocalClient`, pointed at the client's base URL and key, so
+//! that every `LlmConfig` setting is sent as-is. The model defaults to `gpt-4o`.
+
+use crate::common::config::LlmConfig;
+use crate::common::tokens::model_context_window;
+use crate::common::tools::{ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::Completion;
+use crate::common::utils::Communication;
+use crate::providers::local::LocalClient;
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use openai_dive::v1::{
+    api::Client as OpenAIClient,
+    models::{EmbeddingModel, FlagshipModel},
+    resources::embedding::{
+        EmbeddingEncodingFormat, EmbeddingInput, EmbeddingOutput, EmbeddingParametersBuilder,
+    },
+};
+
+/// Builds an OpenAI-compatible client sharing the base URL and key of `client`.
+fn compatible(client: &OpenAIClient) -> LocalClient {
+    LocalClient::new(&client.base_url, FlagshipModel::Gpt4O.to_string())
+        .api_key(&client.api_key)
+        .label("OpenAI")
+}
+
+#[async_trait]
+impl LlmProvider for OpenAIClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.complete(&[Communication::user(request.to_string())])
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.complete_with(messages, &LlmConfig::default()).await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        compatible(self).complete_with(messages, config).await
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        compatible(self).completion(messages, config).await
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        compatible(self)
+            .complete_with_tools(messages, exchanges, tools, config)
+            .await
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.stream_with(request, &LlmConfig::default()).await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        compatible(self).stream_with(request, config).await
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        compatible(self).describe_image(prompt, image, config).await
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        let parameters = EmbeddingParametersBuilder::default()
+            .model(EmbeddingModel::TextEmbedding3Small.to_string())
+            .input(EmbeddingInput::String(content.to_string()))
+            .encoding_format(EmbeddingEncodingFormat::Float)
+            .build()?;
+
+        let response = self
+            .embeddings()
+            .create(parameters)
+            .await
+            .map_err(|err| anyhow!("OpenAI: Failed to embed content: {err}"))?;
+
+        let embedding = response
+            .data
+            .first()
+            .ok_or_else(|| anyhow!("OpenAI: No embedding returned."))?;
+
+        match &embedding.embedding {
+            EmbeddingOutput::Float(vec) => Ok(vec.clone()),
+            EmbeddingOutput::Base64(_) => Err(anyhow!(
+                "OpenAI: Expected embedding as Float, found Base64."
+            )),
+        }
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        model_context_window(config.model_or(&FlagshipModel::Gpt4O.to_string()))
+    }
+}
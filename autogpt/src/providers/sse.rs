@@ -0,0 +1,94 @@
+//! # Server-sent events.
+//!
+//! Helpers for turning a streaming HTTP response into text deltas. `events` splits the body
+//! into the payloads of its `data:` lines, and `openai_deltas` decodes the chunk format used by
+//! OpenAI and every API that mirrors it (xAI, Ollama, llama.cpp, vLLM).
+
+use crate::traits::provider::TextStream;
+use anyhow::{Result, anyhow};
+use futures::{Stream, StreamExt, future, stream};
+use reqwest::Response;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Splits a server-sent events body into the payloads of its `data:` lines.
+///
+/// Lines may span several network chunks; a payload is only yielded once its line is complete.
+pub fn events(response: Response) -> impl Stream<Item = Result<String>> + Send {
+    let body = response.bytes_stream();
+    let state = (body, Vec::<u8>::new(), VecDeque::<String>::new(), false);
+
+    stream::unfold(
+        state,
+        |(mut body, mut buffer, mut pending, mut done)| async move {
+            loop {
+                if let Some(data) = pending.pop_front() {
+                    return Some((Ok(data), (body, buffer, pending, done)));
+                }
+                if done {
+                    return None;
+                }
+
+                match body.next().await {
+                    Some(Ok(chunk)) => {
+                        buffer.extend_from_slice(&chunk);
+                        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                            let line: Vec<u8> = buffer.drain(..=end).collect();
+                            pending.extend(data_line(&line));
+                        }
+                    }
+                    Some(Err(err)) => {
+                        done = true;
+                        return Some((
+                            Err(anyhow!("Failed to read stream: {err}")),
+                            (body, buffer, pending, done),
+                        ));
+                    }
+                    None => {
+                        done = true;
+                        pending.extend(data_line(&std::mem::take(&mut buffer)));
+                    }
+                }
+            }
+        },
+    )
+}
+
+fn data_line(line: &[u8]) -> Option<String> {
+    let line = String::from_utf8_lossy(line);
+    let data = line.trim_end_matches(['\r', '\n']).strip_prefix("data:")?;
+    Some(data.strip_prefix(' ').unwrap_or(data).to_string())
+}
+
+/// Decodes an OpenAI-style chat completion stream into text deltas.
+///
+/// The stream ends at the `[DONE]` sentinel. Error objects sent by the server are yielded as
+/// errors.
+pub fn openai_deltas(response: Response) -> TextStream {
+    Box::pin(
+        events(response)
+            .take_while(|data| future::ready(!matches!(data, Ok(data) if data == "[DONE]")))
+            .filter_map(|data| {
+                future::ready(match data {
+                    Ok(data) => openai_delta(&data),
+                    Err(err) => Some(Err(err)),
+                })
+            }),
+    )
+}
+
+fn openai_delta(data: &str) -> Option<Result<String>> {
+    let chunk: Value = match serde_json::from_str(data) {
+        Ok(chunk) => chunk,
+        Err(err) => return Some(Err(anyhow!("Failed to parse stream chunk: {err}"))),
+    };
+
+    if let Some(message) = chunk["error"]["message"].as_str() {
+        return Some(Err(anyhow!("{message}")));
+    }
+
+    chunk["choices"][0]["delta"]["content"]
+        .as_str()
+        .filter(|content| !content.is_empty())
+        .map(|content| Ok(content.to_string()))
+}
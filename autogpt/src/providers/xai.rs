@@ -0,0 +1,115 @@
+//! # xAI provider.
+//!
+//! `LlmProvider` implementation for the `x-ai` client. The xAI API is OpenAI-compatible, so
+//! chat requests and streams go through a `LocalClient` pointed at the base URL of the client,
+//! with its key. The model defaults to `grok-beta` and the temperature to `0.0`.
+
+use crate::common::config::LlmConfig;
+use crate::common::tokens::model_context_window;
+use crate::common::tools::{ToolDefinition, ToolExchange, ToolReply};
+use crate::common::usage::Completion;
+use crate::common::utils::Communication;
+use crate::providers::local::LocalClient;
+use crate::traits::provider::{LlmProvider, TextStream};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use reqwest::Method;
+use x_ai::client::XaiClient;
+use x_ai::traits::ClientConfig;
+
+/// Model used when the configuration does not name one.
+const XAI_MODEL: &str = "grok-beta";
+
+/// Builds an OpenAI-compatible client sharing the base URL and key of `client`.
+fn compatible(client: &XaiClient) -> Result<LocalClient> {
+    let api_key = client
+        .get_api_key()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| anyhow!("XAI: Missing API key, set XAI_API_KEY."))?;
+    // The base URL is only exposed through the requests the client builds, as `<base>/<path>`.
+    let request = client
+        .request(Method::GET, "")
+        .map_err(|err| anyhow!("XAI: {err}"))?
+        .build()?;
+    let base_url = request.url().as_str().trim_end_matches('/');
+
+    Ok(LocalClient::new(base_url, XAI_MODEL)
+        .api_key(api_key)
+        .label("XAI"))
+}
+
+/// Applies the xAI defaults to `config`.
+fn with_defaults(config: &LlmConfig) -> LlmConfig {
+    let mut config = config.clone();
+    config.temperature.get_or_insert(0.0);
+    config
+}
+
+#[async_trait]
+impl LlmProvider for XaiClient {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.complete(&[Communication::user(request.to_string())])
+            .await
+    }
+
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.complete_with(messages, &LlmConfig::default()).await
+    }
+
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        compatible(self)?
+            .complete_with(messages, &with_defaults(config))
+            .await
+    }
+
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        compatible(self)?
+            .completion(messages, &with_defaults(config))
+            .await
+    }
+
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        compatible(self)?
+            .complete_with_tools(messages, exchanges, tools, &with_defaults(config))
+            .await
+    }
+
+    async fn stream(&mut self, request: &str) -> Result<TextStream> {
+        self.stream_with(request, &LlmConfig::default()).await
+    }
+
+    async fn stream_with(&mut self, request: &str, config: &LlmConfig) -> Result<TextStream> {
+        compatible(self)?
+            .stream_with(request, &with_defaults(config))
+            .await
+    }
+
+    async fn describe_image(
+        &mut self,
+        prompt: &str,
+        image: &[u8],
+        config: &LlmConfig,
+    ) -> Result<String> {
+        compatible(self)?
+            .describe_image(prompt, image, &with_defaults(config))
+            .await
+    }
+
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        model_context_window(config.model_or(XAI_MODEL))
+    }
+}
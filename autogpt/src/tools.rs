@@ -0,0 +1,122 @@
+//! # Tools module.
+//!
+//! This module contains working `ToolHandler`s for the entries of the `ToolName` catalogue that
+//! run offline. `builtin` returns all of them as `Tool`s, and `AgentGPT::add_builtin_tools`
+//! attaches them to an agent in one call.
+//!
+//! Tools touching the file system are scoped to a workspace directory, `AUTOGPT_WORKSPACE` by
+//! default: paths are resolved relative to it and may not leave it. The shell only runs the
+//! programs of an allowlist, read from `AUTOGPT_SHELL_ALLOWLIST` as a comma-separated list
+//! when set, and the git tool only runs a fixed set of subcommands. Neither is a sandbox: an
+//! allowed program can still read files outside the workspace when given their paths.
+//!
+//! ## Sub-modules
+//!
+//! - `calc`: Contains the `Calc` and `Math` expression evaluators.
+//! - `text`: Contains the `Regex`, `Format` and `Convert` text and unit tools.
+//! - `files`: Contains the `Read`, `Write` and `Sheet` tools, scoped to the workspace.
+//! - `system`: Contains the `Shell` and `Git` tools, restricted to allowlists.
+//! - `pad`: Contains the `Pad` scratchpad, kept in memory.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::utils::ToolName;
+//! use autogpt::tools::builtin;
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let tools = builtin(std::env::temp_dir().join("autogpt-tools-example"));
+//! let calc = tools.iter().find(|tool| tool.name == ToolName::Calc).unwrap();
+//!
+//! assert_eq!(calc.run("2 * (3 + 4)").await, "14");
+//! # }
+//! ```
+
+pub mod calc;
+pub mod files;
+pub mod pad;
+pub mod system;
+pub mod text;
+
+use crate::common::utils::{Tool, ToolName};
+use std::env::var;
+use std::path::PathBuf;
+
+/// Returns the workspace directory the built-in tools are scoped to by default.
+pub fn workspace() -> PathBuf {
+    PathBuf::from(var("AUTOGPT_WORKSPACE").unwrap_or_else(|_| "workspace/".to_string()))
+}
+
+/// Returns every built-in tool, scoped to `workspace`.
+///
+/// # Arguments
+///
+/// * `workspace` - Directory the file, shell and git tools work in.
+///
+/// # Returns
+///
+/// (`Vec<Tool>`): The `Calc`, `Math`, `Regex`, `Format`, `Convert`, `Read`, `Write`,
+/// `Sheet`, `Shell`, `Git` and `Pad` tools.
+pub fn builtin(workspace: impl Into<PathBuf>) -> Vec<Tool> {
+    let workspace = workspace.into();
+    vec![
+        Tool::new(
+            ToolName::Calc,
+            "Evaluates an arithmetic expression with + - * / % ^ and parentheses.",
+            calc::Calc,
+        ),
+        Tool::new(
+            ToolName::Math,
+            "Evaluates a math expression with functions such as sqrt, ln, sin or max, the \
+             constants pi and e, and optional variables.",
+            calc::Math,
+        ),
+        Tool::new(
+            ToolName::Regex,
+            "Finds the matches of a regular expression in a text, or replaces them.",
+            text::Regex,
+        ),
+        Tool::new(
+            ToolName::Format,
+            "Reformats a text: pretty or compact JSON, or a change of case.",
+            text::Format,
+        ),
+        Tool::new(
+            ToolName::Convert,
+            "Converts a value between units of length, mass, volume, time, data or temperature.",
+            text::Convert,
+        ),
+        Tool::new(
+            ToolName::Read,
+            "Reads a file of the workspace, or lists a directory.",
+            files::Read::new(&workspace),
+        ),
+        Tool::new(
+            ToolName::Write,
+            "Writes or appends to a file of the workspace.",
+            files::Write::new(&workspace),
+        ),
+        Tool::new(
+            ToolName::Sheet,
+            "Inspects a CSV file of the workspace: columns, rows, filters and aggregates.",
+            files::Sheet::new(&workspace),
+        ),
+        Tool::new(
+            ToolName::Shell,
+            "Runs an allowlisted command in the workspace.",
+            system::Shell::new(&workspace),
+        ),
+        Tool::new(
+            ToolName::Git,
+            "Runs a git subcommand in the workspace repository.",
+            system::Git::new(&workspace),
+        ),
+        Tool::new(
+            ToolName::Pad,
+            "Keeps notes in a scratchpad for the rest of the task.",
+            pad::Pad::default(),
+        ),
+    ]
+}
@@ -0,0 +1,371 @@
+//! # Calculator tools.
+//!
+//! `Calc` evaluates arithmetic: numbers, `+ - * / % ^` and parentheses, with the usual
+//! precedence and `^` binding tightest. `Math` adds functions such as `sqrt`, `ln`, `sin` or
+//! `max`, the constants `pi` and `e`, and variables passed along with the expression.
+//!
+//! Results that are whole numbers are returned as integers, so that `2 * 3` reads `6` rather
+//! than `6.0`. Division by zero, results that are not finite and expressions nested deeper
+//! than `MAX_NESTING` are errors.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::tools::calc::{evaluate, evaluate_math};
+//! use std::collections::HashMap;
+//!
+//! assert_eq!(evaluate("1 + 2 * 3 ^ 2").unwrap(), 19.0);
+//! assert!(evaluate("sqrt(4)").is_err());
+//!
+//! let variables = HashMap::from([("r".to_string(), 2.0)]);
+//! assert_eq!(evaluate_math("max(r ^ 2, 3)", &variables).unwrap(), 4.0);
+//! ```
+
+use crate::common::tools::input_argument;
+use crate::traits::tool::ToolHandler;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Evaluates arithmetic expressions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Calc;
+
+/// Evaluates math expressions with functions, constants and variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Math;
+
+#[async_trait]
+impl ToolHandler for Calc {
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let expression = input_argument(&arguments);
+        Ok(number(evaluate(&expression)?))
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Math {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression to evaluate, such as `sqrt(x ^ 2 + 1)`.",
+                },
+                "variables": {
+                    "type": "object",
+                    "description": "Values of the variables used in the expression.",
+                    "additionalProperties": { "type": "number" },
+                },
+            },
+            "required": ["expression"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let expression = arguments["expression"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing `expression`."))?;
+        let mut variables = HashMap::new();
+        if let Some(values) = arguments["variables"].as_object() {
+            for (name, value) in values {
+                let value = value
+                    .as_f64()
+                    .ok_or_else(|| anyhow!("Variable `{name}` is not a number."))?;
+                variables.insert(name.clone(), value);
+            }
+        }
+        Ok(number(evaluate_math(expression, &variables)?))
+    }
+}
+
+/// Evaluates an arithmetic expression.
+///
+/// # Errors
+///
+/// Returns an error if the expression is malformed, uses a function or a name, divides by
+/// zero or does not evaluate to a finite number.
+pub fn evaluate(expression: &str) -> Result<f64> {
+    Parser::new(expression, None)?.parse()
+}
+
+/// Evaluates a math expression, which may use functions, `pi`, `e` and `variables`.
+///
+/// # Errors
+///
+/// Returns an error if the expression is malformed, uses an unknown function or name, divides
+/// by zero or does not evaluate to a finite number.
+pub fn evaluate_math(expression: &str, variables: &HashMap<String, f64>) -> Result<f64> {
+    Parser::new(expression, Some(variables))?.parse()
+}
+
+/// Returns a result as a JSON number, an integer when it is a whole number.
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(char),
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "`{value}`"),
+            Token::Name(name) => write!(f, "`{name}`"),
+            Token::Operator(operator) => write!(f, "`{operator}`"),
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+        }
+    }
+}
+
+/// Deepest nesting of parentheses, signs and exponents an expression may use, so that hostile
+/// input cannot overflow the stack of the evaluator.
+pub const MAX_NESTING: usize = 256;
+
+/// Recursive descent evaluator. `variables` is `None` for plain arithmetic.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+    variables: Option<&'a HashMap<String, f64>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(expression: &str, variables: Option<&'a HashMap<String, f64>>) -> Result<Self> {
+        Ok(Self {
+            tokens: tokenize(expression)?,
+            position: 0,
+            depth: 0,
+            variables,
+        })
+    }
+
+    fn parse(mut self) -> Result<f64> {
+        if self.tokens.is_empty() {
+            bail!("The expression is empty.");
+        }
+        let value = self.expression()?;
+        if let Some(token) = self.tokens.get(self.position) {
+            bail!("Unexpected {token} at token {}.", self.position + 1);
+        }
+        if !value.is_finite() {
+            bail!("The result is not a finite number.");
+        }
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => bail!("Expected {expected}, found {token}."),
+            None => bail!("Expected {expected} at the end of the expression."),
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64> {
+        let mut value = self.term()?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+            let rhs = self.term()?;
+            value = if operator == '+' {
+                value + rhs
+            } else {
+                value - rhs
+            };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64> {
+        let mut value = self.unary()?;
+        while let Some(Token::Operator(operator @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.position += 1;
+            let rhs = self.unary()?;
+            value = match operator {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => bail!("Division by zero."),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64> {
+        // Every recursion of the grammar goes through here.
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            bail!("The expression is nested more than {MAX_NESTING} levels deep.");
+        }
+        let value = match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.position += 1;
+                self.unary().map(|value| -value)
+            }
+            Some(Token::Operator('+')) => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        };
+        self.depth -= 1;
+        value
+    }
+
+    fn power(&mut self) -> Result<f64> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Operator('^')) {
+            self.position += 1;
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::Open) => {
+                let value = self.expression()?;
+                self.expect(Token::Close)?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) => {
+                let Some(variables) = self.variables else {
+                    bail!("Unknown name `{name}`: only numbers and operators are supported.");
+                };
+                if self.peek() == Some(&Token::Open) {
+                    self.position += 1;
+                    let mut arguments = Vec::new();
+                    if self.peek() != Some(&Token::Close) {
+                        arguments.push(self.expression()?);
+                        while self.peek() == Some(&Token::Comma) {
+                            self.position += 1;
+                            arguments.push(self.expression()?);
+                        }
+                    }
+                    self.expect(Token::Close)?;
+                    return function(&name, &arguments);
+                }
+                match (variables.get(&name), name.as_str()) {
+                    (Some(value), _) => Ok(*value),
+                    (None, "pi") => Ok(std::f64::consts::PI),
+                    (None, "e") => Ok(std::f64::consts::E),
+                    (None, _) => bail!("Unknown variable `{name}`."),
+                }
+            }
+            Some(token) => bail!("Unexpected {token}."),
+            None => bail!("The expression ends unexpectedly."),
+        }
+    }
+}
+
+/// Applies a math function.
+fn function(name: &str, arguments: &[f64]) -> Result<f64> {
+    let unary = |f: fn(f64) -> f64| match arguments {
+        [x] => Ok(f(*x)),
+        _ => Err(anyhow!("`{name}` takes one argument.")),
+    };
+    match name {
+        "sqrt" => unary(f64::sqrt),
+        "cbrt" => unary(f64::cbrt),
+        "abs" => unary(f64::abs),
+        "exp" => unary(f64::exp),
+        "ln" => unary(f64::ln),
+        "log" | "log10" => unary(f64::log10),
+        "log2" => unary(f64::log2),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" => unary(f64::asin),
+        "acos" => unary(f64::acos),
+        "atan" => unary(f64::atan),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "pow" => match arguments {
+            [base, exponent] => Ok(base.powf(*exponent)),
+            _ => bail!("`pow` takes two arguments."),
+        },
+        "min" | "max" if arguments.is_empty() => bail!("`{name}` takes at least one argument."),
+        "min" => Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => bail!("Unknown function `{name}`."),
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        i += 1;
+        match c {
+            c if c.is_whitespace() => {}
+            '0'..='9' | '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // An exponent, as in `1.5e-3`, but not the constant in `2e`.
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let digits = if matches!(chars.get(i + 1), Some('+' | '-')) {
+                        i + 2
+                    } else {
+                        i + 1
+                    };
+                    if chars.get(digits).is_some_and(char::is_ascii_digit) {
+                        i = digits;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value = literal
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid number `{literal}`."))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Name(chars[start..i].iter().collect()));
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' => tokens.push(Token::Operator(c)),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            _ => bail!("Unexpected character `{c}`."),
+        }
+    }
+    Ok(tokens)
+}
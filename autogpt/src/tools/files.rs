@@ -0,0 +1,314 @@
+//! # File tools.
+//!
+//! - `Read` reads a text file, or lists a directory.
+//! - `Write` writes or appends to a file, creating its parent directories.
+//! - `Sheet` inspects a CSV file: its columns, its rows, optionally filtered on the value of
+//!   a column, and the count, sum, mean, min or max of a column.
+//!
+//! All three are scoped to a root directory: paths are relative to it, and absolute paths,
+//! `..` components and symbolic links leading out of it are rejected. So are paths going
+//! through a `.git` directory, whose configuration and hooks name programs git runs.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::tools::files::resolve;
+//! use std::path::Path;
+//!
+//! let root = Path::new("workspace");
+//! assert_eq!(resolve(root, "notes/todo.md").unwrap(), root.join("notes/todo.md"));
+//! assert!(resolve(root, "../secrets").is_err());
+//! assert!(resolve(root, "/etc/passwd").is_err());
+//! assert!(resolve(root, ".git/config").is_err());
+//! ```
+
+use crate::tools::text::string;
+use crate::traits::tool::ToolHandler;
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Upper bound on the bytes of a file returned by `Read`.
+pub const MAX_READ_BYTES: usize = 64 * 1024;
+
+/// Number of rows returned by `Sheet` when no limit is given.
+pub const DEFAULT_ROWS: usize = 20;
+
+/// Reads files of a directory.
+#[derive(Debug, Clone)]
+pub struct Read {
+    root: PathBuf,
+}
+
+/// Writes files of a directory.
+#[derive(Debug, Clone)]
+pub struct Write {
+    root: PathBuf,
+}
+
+/// Inspects CSV files of a directory.
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    root: PathBuf,
+}
+
+impl Read {
+    /// Creates a tool reading files under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Write {
+    /// Creates a tool writing files under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Sheet {
+    /// Creates a tool reading CSV files under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+/// Resolves a path relative to `root`.
+///
+/// # Errors
+///
+/// Returns an error if the path is absolute, contains `..` or a `.git` component, or goes
+/// through a symbolic link leading out of `root`.
+pub fn resolve(root: &Path, path: &str) -> Result<PathBuf> {
+    let mut resolved = root.to_path_buf();
+    for component in Path::new(path.trim()).components() {
+        match component {
+            Component::Normal(part) if part.eq_ignore_ascii_case(".git") => {
+                bail!("`{path}` is inside a git directory, which the tools do not touch.")
+            }
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            _ => {
+                bail!("`{path}` is outside the workspace: use a path relative to it without `..`.")
+            }
+        }
+    }
+
+    if let Ok(root) = root.canonicalize() {
+        let existing = resolved
+            .ancestors()
+            .find_map(|ancestor| ancestor.canonicalize().ok());
+        if existing.is_some_and(|existing| !existing.starts_with(&root)) {
+            bail!("`{path}` leads outside the workspace.");
+        }
+    }
+    Ok(resolved)
+}
+
+/// Returns the path schema shared by the file tools.
+fn path_schema() -> Value {
+    json!({ "type": "string", "description": "Path relative to the workspace." })
+}
+
+#[async_trait]
+impl ToolHandler for Read {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "path": path_schema() },
+            "required": ["path"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let path = string(&arguments, "path")?;
+        let resolved = resolve(&self.root, path)?;
+
+        if resolved.is_dir() {
+            let mut entries = Vec::new();
+            let mut dir = fs::read_dir(&resolved).await?;
+            while let Some(entry) = dir.next_entry().await? {
+                let mut name = entry.file_name().to_string_lossy().to_string();
+                if entry.file_type().await?.is_dir() {
+                    name.push('/');
+                }
+                entries.push(name);
+            }
+            entries.sort();
+            return Ok(json!(entries));
+        }
+
+        let bytes = fs::read(&resolved)
+            .await
+            .with_context(|| format!("Failed to read `{path}`"))?;
+        let mut text =
+            String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_READ_BYTES)]).to_string();
+        if bytes.len() > MAX_READ_BYTES {
+            text.push_str(&format!(
+                "\n[Truncated: showing {MAX_READ_BYTES} of {} bytes.]",
+                bytes.len()
+            ));
+        }
+        Ok(Value::String(text))
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Write {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": path_schema(),
+                "content": { "type": "string", "description": "Text to write." },
+                "append": {
+                    "type": "boolean",
+                    "description": "Appends to the file instead of replacing it.",
+                },
+            },
+            "required": ["path", "content"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let path = string(&arguments, "path")?;
+        let content = string(&arguments, "content")?;
+        let append = arguments["append"].as_bool().unwrap_or(false);
+        let resolved = resolve(&self.root, path)?;
+
+        if let Some(parent) = resolved.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&resolved)
+            .await
+            .with_context(|| format!("Failed to open `{path}`"))?;
+        file.write_all(content.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(Value::String(format!(
+            "Wrote {} bytes to `{path}`.",
+            content.len()
+        )))
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Sheet {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": path_schema(),
+                "operation": {
+                    "type": "string",
+                    "enum": ["columns", "rows", "count", "sum", "mean", "min", "max"],
+                    "description": "What to return.",
+                },
+                "column": {
+                    "type": "string",
+                    "description": "Column aggregated by `sum`, `mean`, `min` and `max`, or filtered on with `value`.",
+                },
+                "value": {
+                    "type": "string",
+                    "description": "Keeps only the rows whose `column` equals this value.",
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of rows returned by `rows`.",
+                },
+            },
+            "required": ["path", "operation"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let path = string(&arguments, "path")?;
+        let operation = string(&arguments, "operation")?;
+        let bytes = fs::read(resolve(&self.root, path)?)
+            .await
+            .with_context(|| format!("Failed to read `{path}`"))?;
+
+        let mut reader = csv::Reader::from_reader(bytes.as_slice());
+        let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        if operation == "columns" {
+            return Ok(json!(headers));
+        }
+
+        let index = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| anyhow!("Unknown column `{name}`, expected one of {headers:?}."))
+        };
+        let column = arguments["column"].as_str().map(index).transpose()?;
+        let filter = match (column, arguments["value"].as_str()) {
+            (Some(column), Some(value)) => Some((column, value)),
+            (None, Some(_)) => bail!("Filtering on `value` requires a `column`."),
+            _ => None,
+        };
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            if filter.is_none_or(|(column, value)| record.get(column) == Some(value)) {
+                rows.push(record);
+            }
+        }
+
+        match operation {
+            "rows" => {
+                let limit = arguments["limit"]
+                    .as_u64()
+                    .map_or(DEFAULT_ROWS, |limit| limit as usize);
+                let rows: Vec<Value> = rows
+                    .iter()
+                    .take(limit)
+                    .map(|record| {
+                        let row: Map<String, Value> = headers
+                            .iter()
+                            .cloned()
+                            .zip(record.iter().map(|cell| json!(cell)))
+                            .collect();
+                        Value::Object(row)
+                    })
+                    .collect();
+                Ok(json!(rows))
+            }
+            "count" => Ok(json!(rows.len())),
+            "sum" | "mean" | "min" | "max" => {
+                let column = column.ok_or_else(|| anyhow!("`{operation}` requires a `column`."))?;
+                let mut values = Vec::new();
+                for record in &rows {
+                    let cell = record.get(column).unwrap_or_default().trim();
+                    if cell.is_empty() {
+                        continue;
+                    }
+                    let value: f64 = cell.parse().map_err(|_| {
+                        anyhow!("`{cell}` in column `{}` is not a number.", headers[column])
+                    })?;
+                    values.push(value);
+                }
+                if values.is_empty() && operation != "sum" {
+                    bail!("Column `{}` has no values.", headers[column]);
+                }
+
+                let sum: f64 = values.iter().sum();
+                Ok(json!(match operation {
+                    "sum" => sum,
+                    "mean" => sum / values.len() as f64,
+                    "min" => values.iter().copied().fold(f64::INFINITY, f64::min),
+                    _ => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                }))
+            }
+            _ => bail!("Unknown operation `{operation}`."),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! # Scratchpad tool.
+//!
+//! `Pad` keeps named notes in memory, so that the model can write down intermediate results
+//! during a task and read them back in later steps. Clones of a `Tool` built from a pad share
+//! its notes. Notes are lost when the last clone is dropped.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::common::utils::{Tool, ToolName};
+//! use autogpt::tools::pad::Pad;
+//! use serde_json::json;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let pad = Tool::new(ToolName::Pad, "Keeps notes.", Pad::default());
+//!
+//! pad.respond(json!({ "action": "write", "key": "plan", "text": "1. Parse" })).await;
+//! pad.respond(json!({ "action": "append", "key": "plan", "text": "2. Test" })).await;
+//!
+//! let plan = pad.respond(json!({ "action": "read", "key": "plan" })).await;
+//! assert_eq!(plan, "1. Parse\n2. Test");
+//! # }
+//! ```
+
+use crate::traits::tool::ToolHandler;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Key of the note used when none is given.
+pub const DEFAULT_NOTE: &str = "default";
+
+/// An in-memory scratchpad of named notes.
+#[derive(Debug, Default)]
+pub struct Pad {
+    notes: Mutex<BTreeMap<String, String>>,
+}
+
+impl Pad {
+    /// Returns a copy of the notes.
+    pub fn notes(&self) -> BTreeMap<String, String> {
+        self.notes.lock().expect("pad lock poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Pad {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["write", "append", "read", "list", "clear"],
+                    "description": "`write` replaces a note, `append` adds a line to it, `list` returns the keys and `clear` deletes a note.",
+                },
+                "key": { "type": "string", "description": "Name of the note." },
+                "text": { "type": "string", "description": "Text written by `write` and `append`." },
+            },
+            "required": ["action"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let action = arguments["action"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing string argument `action`."))?;
+        let key = arguments["key"]
+            .as_str()
+            .unwrap_or(DEFAULT_NOTE)
+            .to_string();
+        let text = || {
+            arguments["text"]
+                .as_str()
+                .ok_or_else(|| anyhow!("`{action}` requires a `text`."))
+        };
+
+        let mut notes = self.notes.lock().expect("pad lock poisoned");
+        match action {
+            "write" => {
+                notes.insert(key.clone(), text()?.to_string());
+                Ok(json!(format!("Wrote note `{key}`.")))
+            }
+            "append" => {
+                let text = text()?;
+                let note = notes.entry(key.clone()).or_default();
+                if !note.is_empty() {
+                    note.push('\n');
+                }
+                note.push_str(text);
+                Ok(json!(format!("Appended to note `{key}`.")))
+            }
+            "read" => notes
+                .get(&key)
+                .map(|note| json!(note))
+                .ok_or_else(|| anyhow!("No note named `{key}`.")),
+            "list" => Ok(json!(notes.keys().collect::<Vec<_>>())),
+            "clear" => {
+                notes.remove(&key);
+                Ok(json!(format!("Cleared note `{key}`.")))
+            }
+            _ => bail!("Unknown action `{action}`."),
+        }
+    }
+}
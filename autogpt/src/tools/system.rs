@@ -0,0 +1,312 @@
+//! # System tools.
+//!
+//! - `Shell` runs a command in the workspace, provided its program is on an allowlist and its
+//!   arguments are relative paths inside the workspace. The command is split like a shell
+//!   would, but it is not run by one: pipes, redirections and variables are passed to the
+//!   program as plain arguments.
+//! - `Git` runs one of a fixed set of git subcommands in the workspace, rejecting the options
+//!   that would run other programs or write outside the repository. Hooks and the file system
+//!   monitor are turned off, so the repository configuration cannot run programs either.
+//!
+//! Both return the exit status and the output of the command, so a failing command is a
+//! result the model can read rather than an error. Commands running for longer than their
+//! timeout, 30 seconds by default, are killed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::tools::system::Shell;
+//!
+//! let shell = Shell::new("workspace").with_allowlist(["ls", "cat"]);
+//! assert!(shell.check("cat notes.md").is_ok());
+//! assert!(shell.check("rm -rf notes").is_err());
+//! assert!(shell.check("/bin/ls").is_err());
+//! assert!(shell.check("cat /etc/passwd").is_err());
+//! assert!(shell.check("cat -n/etc/passwd").is_err());
+//! ```
+
+use crate::tools::files::resolve;
+use crate::tools::text::string;
+use crate::traits::tool::ToolHandler;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::env::var;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// Programs the shell runs when `AUTOGPT_SHELL_ALLOWLIST` is not set. They only read files
+/// and print text.
+pub const DEFAULT_SHELL_ALLOWLIST: &[&str] = &[
+    "ls", "cat", "echo", "pwd", "wc", "head", "tail", "grep", "diff", "cut", "tr",
+];
+
+/// Subcommands the git tool runs.
+pub const GIT_SUBCOMMANDS: &[&str] = &[
+    "status", "log", "diff", "show", "branch", "init", "add", "commit",
+];
+
+/// Git options rejected because they run other programs, or read or write outside the
+/// repository.
+const GIT_FORBIDDEN_OPTIONS: &[&str] = &[
+    "--output",
+    "--ext-diff",
+    "--textconv",
+    "--exec",
+    "--upload-pack",
+    "--receive-pack",
+    "--git-dir",
+    "--work-tree",
+    "--template",
+    "--separate-git-dir",
+    "--open-files-in-pager",
+    "--no-index",
+];
+
+/// Time after which a command is killed, unless overridden.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound on the bytes of each output stream returned to the model.
+pub const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Runs allowlisted commands in a directory.
+#[derive(Debug, Clone)]
+pub struct Shell {
+    root: PathBuf,
+    allowlist: Vec<String>,
+    timeout: Duration,
+}
+
+/// Runs git subcommands in a directory.
+#[derive(Debug, Clone)]
+pub struct Git {
+    root: PathBuf,
+    timeout: Duration,
+}
+
+/// Returns the programs the shell runs, from `AUTOGPT_SHELL_ALLOWLIST` or the default list.
+pub fn shell_allowlist() -> Vec<String> {
+    match var("AUTOGPT_SHELL_ALLOWLIST") {
+        Ok(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|program| !program.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => DEFAULT_SHELL_ALLOWLIST
+            .iter()
+            .map(|program| program.to_string())
+            .collect(),
+    }
+}
+
+impl Shell {
+    /// Creates a shell running the programs of `shell_allowlist` under `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            allowlist: shell_allowlist(),
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+        }
+    }
+
+    /// Replaces the programs the shell runs.
+    pub fn with_allowlist(mut self, programs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowlist = programs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the time after which a command is killed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Splits a command into its program and arguments, and checks that the program is
+    /// allowed and that its arguments stay in the workspace.
+    ///
+    /// Every argument that is not an option, the value of every `--option=value`, and every
+    /// value a short option could carry attached, as in `-f/etc/passwd`, is resolved like a
+    /// path with `files::resolve`, so absolute paths and `..` are rejected even where the
+    /// program would read them as a pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is empty or has unbalanced quotes, if its program is a
+    /// path or is not on the allowlist, or if an argument leads outside the workspace.
+    pub fn check(&self, command: &str) -> Result<Vec<String>> {
+        let words =
+            shlex::split(command).ok_or_else(|| anyhow!("Unbalanced quotes in `{command}`."))?;
+        let program = words
+            .first()
+            .ok_or_else(|| anyhow!("The command is empty."))?;
+        if program.contains('/') || !self.allowlist.contains(program) {
+            bail!(
+                "`{program}` is not allowed, use one of: {}.",
+                self.allowlist.join(", ")
+            );
+        }
+        for word in &words[1..] {
+            if let Some(option) = word.strip_prefix("--") {
+                if let Some((_, value)) = option.split_once('=') {
+                    resolve(&self.root, value)?;
+                }
+            } else if let Some(options) = word.strip_prefix('-') {
+                // Short options can be grouped, and the last one can carry its value attached,
+                // so any tail of the group may be read as a path.
+                for (at, _) in options.char_indices() {
+                    resolve(&self.root, &options[at..])?;
+                }
+            } else {
+                resolve(&self.root, word)?;
+            }
+        }
+        Ok(words)
+    }
+}
+
+impl Git {
+    /// Creates a git tool working in the repository at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+        }
+    }
+
+    /// Sets the time after which a command is killed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Shell {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": format!(
+                        "Command to run, without pipes or redirections. Allowed programs: {}.",
+                        self.allowlist.join(", ")
+                    ),
+                },
+            },
+            "required": ["command"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let words = self.check(string(&arguments, "command")?)?;
+        tokio::fs::create_dir_all(&self.root).await?;
+
+        let mut command = Command::new(&words[0]);
+        command.args(&words[1..]).current_dir(&self.root);
+        run(command, self.timeout).await
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Git {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "subcommand": { "type": "string", "enum": GIT_SUBCOMMANDS },
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Arguments of the subcommand, such as [\"-m\", \"Fix typo\"] for a commit.",
+                },
+            },
+            "required": ["subcommand"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let subcommand = string(&arguments, "subcommand")?;
+        if !GIT_SUBCOMMANDS.contains(&subcommand) {
+            bail!(
+                "`git {subcommand}` is not allowed, use one of: {}.",
+                GIT_SUBCOMMANDS.join(", ")
+            );
+        }
+
+        let args: Vec<String> = match &arguments["args"] {
+            Value::Null => Vec::new(),
+            Value::Array(args) => args
+                .iter()
+                .map(|arg| match arg {
+                    Value::String(arg) => arg.clone(),
+                    arg => arg.to_string(),
+                })
+                .collect(),
+            Value::String(args) => {
+                shlex::split(args).ok_or_else(|| anyhow!("Unbalanced quotes in `{args}`."))?
+            }
+            args => bail!("`args` must be an array of strings, found {args}."),
+        };
+        if let Some(arg) = args.iter().find(|arg| {
+            GIT_FORBIDDEN_OPTIONS
+                .iter()
+                .any(|option| arg.starts_with(option))
+        }) {
+            bail!("The `{arg}` option is not allowed.");
+        }
+
+        tokio::fs::create_dir_all(&self.root).await?;
+        let mut command = Command::new("git");
+        command
+            .arg("--no-pager")
+            .args([
+                "-c",
+                "core.fsmonitor=false",
+                "-c",
+                "core.hooksPath=/dev/null",
+            ])
+            .arg(subcommand)
+            .args(&args)
+            .current_dir(&self.root)
+            .env("GIT_TERMINAL_PROMPT", "0");
+        run(command, self.timeout).await
+    }
+}
+
+/// Runs a command and returns its exit status and output.
+async fn run(mut command: Command, limit: Duration) -> Result<Value> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = timeout(limit, command.output()).await.map_err(|_| {
+        anyhow!(
+            "The command did not finish within {} seconds.",
+            limit.as_secs()
+        )
+    })??;
+
+    Ok(json!({
+        "status": output.status.code(),
+        "stdout": truncate(&output.stdout),
+        "stderr": truncate(&output.stderr),
+    }))
+}
+
+/// Returns an output stream as text, cut to `MAX_OUTPUT_BYTES`.
+fn truncate(bytes: &[u8]) -> String {
+    let mut text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_OUTPUT_BYTES)]).to_string();
+    if bytes.len() > MAX_OUTPUT_BYTES {
+        text.push_str(&format!(
+            "\n[Truncated: showing {MAX_OUTPUT_BYTES} of {} bytes.]",
+            bytes.len()
+        ));
+    }
+    text
+}
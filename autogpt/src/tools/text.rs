@@ -0,0 +1,392 @@
+//! # Text tools.
+//!
+//! - `Regex` finds the matches of a regular expression in a text, with their positions and
+//!   capture groups, or replaces them when given a replacement.
+//! - `Format` pretty-prints or compacts JSON, and changes the case of a text to upper, lower,
+//!   title, snake, kebab, camel or pascal case.
+//! - `Convert` converts values between units of the same dimension: length, mass, volume,
+//!   time, data and temperature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use autogpt::tools::text::{convert, format};
+//!
+//! assert_eq!(format("parse HTTP response", "snake").unwrap(), "parse_http_response");
+//! assert_eq!(convert(100.0, "C", "F").unwrap(), 212.0);
+//! assert!(convert(1.0, "km", "kg").is_err());
+//! ```
+
+use crate::traits::tool::ToolHandler;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+/// Upper bound on the number of matches returned by `Regex`.
+pub const MAX_MATCHES: usize = 100;
+
+/// Finds or replaces the matches of a regular expression.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Regex;
+
+/// Reformats JSON or changes the case of a text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Format;
+
+/// Converts values between units.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Convert;
+
+#[async_trait]
+impl ToolHandler for Regex {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": { "type": "string", "description": "Regular expression, in Rust syntax." },
+                "text": { "type": "string", "description": "Text to search." },
+                "replacement": {
+                    "type": "string",
+                    "description": "Replaces every match when set, with `$1` or `$name` for groups.",
+                },
+            },
+            "required": ["pattern", "text"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let pattern = string(&arguments, "pattern")?;
+        let text = string(&arguments, "text")?;
+        let regex = regex::Regex::new(pattern)?;
+
+        if let Some(replacement) = arguments["replacement"].as_str() {
+            return Ok(Value::String(
+                regex.replace_all(text, replacement).into_owned(),
+            ));
+        }
+
+        let matches: Vec<Value> = regex
+            .captures_iter(text)
+            .take(MAX_MATCHES)
+            .map(|captures| {
+                let whole = captures.get(0).expect("group 0 always matches");
+                let groups: Vec<Value> = captures
+                    .iter()
+                    .skip(1)
+                    .map(|group| group.map_or(Value::Null, |group| json!(group.as_str())))
+                    .collect();
+                json!({
+                    "match": whole.as_str(),
+                    "start": whole.start(),
+                    "end": whole.end(),
+                    "groups": groups,
+                })
+            })
+            .collect();
+        Ok(Value::Array(matches))
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Format {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Text to reformat." },
+                "style": {
+                    "type": "string",
+                    "enum": ["json", "compact", "upper", "lower", "title", "snake", "kebab", "camel", "pascal", "trim"],
+                    "description": "Target format.",
+                },
+            },
+            "required": ["text", "style"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let text = string(&arguments, "text")?;
+        let style = string(&arguments, "style")?;
+        Ok(Value::String(format(text, style)?))
+    }
+}
+
+#[async_trait]
+impl ToolHandler for Convert {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "value": { "type": "number", "description": "Value to convert." },
+                "from": { "type": "string", "description": "Unit of the value, such as `km` or `lb`." },
+                "to": { "type": "string", "description": "Unit to convert to." },
+            },
+            "required": ["value", "from", "to"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let value = arguments["value"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("`value` must be a number."))?;
+        let from = string(&arguments, "from")?;
+        let to = string(&arguments, "to")?;
+        Ok(json!({ "value": convert(value, from, to)?, "unit": to }))
+    }
+}
+
+/// Returns a string argument.
+pub(crate) fn string<'a>(arguments: &'a Value, name: &str) -> Result<&'a str> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing string argument `{name}`."))
+}
+
+/// Reformats `text` in the given style.
+///
+/// # Errors
+///
+/// Returns an error for an unknown style, or for text that is not JSON in the `json` and
+/// `compact` styles.
+pub fn format(text: &str, style: &str) -> Result<String> {
+    let words = || words(text);
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first
+                .to_uppercase()
+                .chain(chars.flat_map(char::to_lowercase))
+                .collect()
+        })
+    };
+
+    Ok(match style.to_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(&serde_json::from_str::<Value>(text)?)?,
+        "compact" => serde_json::from_str::<Value>(text)?.to_string(),
+        "upper" => text.to_uppercase(),
+        "lower" => text.to_lowercase(),
+        "trim" => text.trim().to_string(),
+        "title" => words()
+            .iter()
+            .map(|word| capitalize(word))
+            .collect::<Vec<_>>()
+            .join(" "),
+        "snake" => words().join("_").to_lowercase(),
+        "kebab" => words().join("-").to_lowercase(),
+        "pascal" => words().iter().map(|word| capitalize(word)).collect(),
+        "camel" => {
+            let words = words();
+            words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.to_lowercase()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect()
+        }
+        _ => bail!("Unknown style `{style}`."),
+    })
+}
+
+/// Splits a text into words at separators and at lowercase to uppercase transitions, so that
+/// `parseHTTPResponse` and `parse_http_response` give the same words.
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let boundary = i > 0
+                && c.is_uppercase()
+                && (chars[i - 1].is_lowercase()
+                    || (chars[i - 1].is_uppercase()
+                        && chars.get(i + 1).is_some_and(|next| next.is_lowercase())));
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c);
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Physical dimension of a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Time,
+    Data,
+    Temperature,
+}
+
+/// Units with their dimension and their size in the base unit of the dimension: meters,
+/// kilograms, liters, seconds and bytes. Temperatures are handled by `to_kelvin`.
+const UNITS: &[(&[&str], Dimension, f64)] = &[
+    (
+        &["m", "meter", "meters", "metre", "metres"],
+        Dimension::Length,
+        1.0,
+    ),
+    (
+        &["km", "kilometer", "kilometers"],
+        Dimension::Length,
+        1000.0,
+    ),
+    (
+        &["cm", "centimeter", "centimeters"],
+        Dimension::Length,
+        0.01,
+    ),
+    (
+        &["mm", "millimeter", "millimeters"],
+        Dimension::Length,
+        0.001,
+    ),
+    (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    (
+        &["nmi", "nautical mile", "nautical miles"],
+        Dimension::Length,
+        1852.0,
+    ),
+    (&["kg", "kilogram", "kilograms"], Dimension::Mass, 1.0),
+    (&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    (&["mg", "milligram", "milligrams"], Dimension::Mass, 1e-6),
+    (&["t", "tonne", "tonnes"], Dimension::Mass, 1000.0),
+    (
+        &["lb", "lbs", "pound", "pounds"],
+        Dimension::Mass,
+        0.453_592_37,
+    ),
+    (
+        &["oz", "ounce", "ounces"],
+        Dimension::Mass,
+        0.028_349_523_125,
+    ),
+    (
+        &["l", "liter", "liters", "litre", "litres"],
+        Dimension::Volume,
+        1.0,
+    ),
+    (
+        &["ml", "milliliter", "milliliters"],
+        Dimension::Volume,
+        0.001,
+    ),
+    (
+        &["m3", "cubic meter", "cubic meters"],
+        Dimension::Volume,
+        1000.0,
+    ),
+    (
+        &["gal", "gallon", "gallons"],
+        Dimension::Volume,
+        3.785_411_784,
+    ),
+    (&["qt", "quart", "quarts"], Dimension::Volume, 0.946_352_946),
+    (&["pt", "pint", "pints"], Dimension::Volume, 0.473_176_473),
+    (&["cup", "cups"], Dimension::Volume, 0.236_588_236_5),
+    (&["s", "sec", "second", "seconds"], Dimension::Time, 1.0),
+    (
+        &["ms", "millisecond", "milliseconds"],
+        Dimension::Time,
+        0.001,
+    ),
+    (&["min", "minute", "minutes"], Dimension::Time, 60.0),
+    (&["h", "hr", "hour", "hours"], Dimension::Time, 3600.0),
+    (&["d", "day", "days"], Dimension::Time, 86_400.0),
+    (&["wk", "week", "weeks"], Dimension::Time, 604_800.0),
+    (&["b", "byte", "bytes"], Dimension::Data, 1.0),
+    (&["kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    (&["mb", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    (&["gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    (&["tb", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    (&["kib", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    (
+        &["mib", "mebibyte", "mebibytes"],
+        Dimension::Data,
+        1_048_576.0,
+    ),
+    (
+        &["gib", "gibibyte", "gibibytes"],
+        Dimension::Data,
+        1_073_741_824.0,
+    ),
+    (
+        &["tib", "tebibyte", "tebibytes"],
+        Dimension::Data,
+        1_099_511_627_776.0,
+    ),
+    (&["c", "celsius", "°c"], Dimension::Temperature, 0.0),
+    (&["f", "fahrenheit", "°f"], Dimension::Temperature, 0.0),
+    (&["k", "kelvin"], Dimension::Temperature, 0.0),
+];
+
+/// Returns the canonical name, dimension and size of a unit.
+fn unit(name: &str) -> Result<(&'static str, Dimension, f64)> {
+    let name = name.trim().to_lowercase();
+    UNITS
+        .iter()
+        .find(|(names, _, _)| names.contains(&name.as_str()))
+        .map(|(names, dimension, size)| (names[0], *dimension, *size))
+        .ok_or_else(|| anyhow!("Unknown unit `{name}`."))
+}
+
+/// Converts a temperature to kelvin.
+fn to_kelvin(value: f64, unit: &str) -> f64 {
+    match unit {
+        "c" => value + 273.15,
+        "f" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+/// Converts a temperature from kelvin.
+fn from_kelvin(value: f64, unit: &str) -> f64 {
+    match unit {
+        "c" => value - 273.15,
+        "f" => (value - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => value,
+    }
+}
+
+/// Converts `value` from one unit to another.
+///
+/// # Errors
+///
+/// Returns an error if a unit is unknown or if the units measure different dimensions.
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64> {
+    let (from, from_dimension, from_size) = unit(from)?;
+    let (to, to_dimension, to_size) = unit(to)?;
+    if from_dimension != to_dimension {
+        bail!("Cannot convert {from_dimension:?} to {to_dimension:?}.");
+    }
+
+    let converted = if from_dimension == Dimension::Temperature {
+        from_kelvin(to_kelvin(value, from), to)
+    } else {
+        value * from_size / to_size
+    };
+    Ok(round(converted))
+}
+
+/// Rounds to 12 significant digits, to drop the noise of the floating point factors, such as
+/// the `0.30000000000000004` of `0.1 + 0.2`.
+fn round(value: f64) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let scale = 10f64.powi(11 - value.abs().log10().floor() as i32);
+    (value * scale).round() / scale
+}
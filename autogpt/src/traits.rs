@@ -6,9 +6,18 @@
 //!
 //! - `agent`: Contains definitions related to agents.
 //! - `functions`: Contains definitions of special functions for agents.
+//! - `memory`: Contains the interface implemented by the stores of long-term memory.
 //! - `composite`: Contains a composite of special functions and definitions for agents.
+//! - `evaluator`: Contains the interface implemented by the evaluators of reflections.
+//! - `provider`: Contains the provider-agnostic interface to model backends.
+//! - `tool`: Contains the interface implemented by the handlers of tools.
 //!
 
 pub mod agent;
 pub mod composite;
+pub mod evaluator;
 pub mod functions;
+#[cfg(feature = "mem")]
+pub mod memory;
+pub mod provider;
+pub mod tool;
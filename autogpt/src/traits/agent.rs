@@ -144,9 +144,11 @@ use crate::common::utils::{
     Capability, Communication, ContextManager, Knowledge, Persona, Planner, Reflection, Status,
     Task, TaskScheduler, Tool,
 };
+use anyhow::{Result, bail};
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use tracing::debug;
 
 /// A trait defining basic functionalities for agents.
 pub trait Agent: Debug {
@@ -167,6 +169,39 @@ pub trait Agent: Debug {
     /// * `status` - The new status to be assigned to the agent.
     fn update(&mut self, status: Status);
 
+    /// Updates the status of the agent, if it can move from its current status to `status`.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The new status to be assigned to the agent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, and keeps the current status, if the transition is not allowed by
+    /// `Status::can_become`.
+    fn try_update(&mut self, status: Status) -> Result<()> {
+        if !self.status().can_become(&status) {
+            bail!(
+                "An agent cannot move from {:?} to {status:?}.",
+                self.status()
+            );
+        }
+        self.update(status);
+        Ok(())
+    }
+
+    /// Marks the agent as failed with the error that stopped it. Agents that cannot fail from
+    /// their current status, such as cancelled ones, keep it.
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - The error that stopped the agent.
+    fn fail(&mut self, err: &anyhow::Error) {
+        if let Err(update) = self.try_update(Status::Failed(Cow::Owned(format!("{err:#}")))) {
+            debug!("[*] {:?}: {update}", self.position());
+        }
+    }
+
     /// Retrieves the objective of the agent.
     fn objective(&self) -> &Cow<'static, str>;
 
@@ -0,0 +1,95 @@
+//! # `Evaluator` trait.
+//!
+//! This trait is implemented by whatever evaluates an agent in its `Reflection`. Evaluators
+//! are async and may call the model through the agent's client: they look at the agent, its
+//! objective, plan and memory, and return an `Evaluation` made of a summary and of lessons,
+//! which `AgentGPT::reflect` writes into the reflection logs and the memory of the agent
+//! before its next attempt.
+//!
+//! Three kinds of evaluators are available:
+//!
+//! - Plain `fn(&dyn Agent) -> Cow<'static, str>` functions, such as `default_eval_fn`, whose
+//!   result is the summary.
+//! - Async closures taking a snapshot of the agent, wrapped with `AsyncEvaluator` or passed
+//!   to `Reflection::from_async`.
+//! - Any type implementing the trait, such as the built-in `Critic`, which asks the model to
+//!   critique the last outputs of the agent against its objective.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use anyhow::Result;
+//! use autogpt::agents::agent::AgentGPT;
+//! use autogpt::common::reflection::Evaluation;
+//! use autogpt::common::utils::{ClientType, Reflection};
+//! use autogpt::prelude::async_trait;
+//! use autogpt::traits::evaluator::Evaluator;
+//!
+//! /// Asks for shorter answers once the memory grows long.
+//! struct Brevity;
+//!
+//! #[async_trait]
+//! impl Evaluator for Brevity {
+//!     async fn evaluate(&self, agent: &AgentGPT, _client: &mut ClientType) -> Result<Evaluation> {
+//!         let mut evaluation = Evaluation::new(format!("{} messages so far.", agent.memory.len()));
+//!         if agent.memory.len() > 10 {
+//!             evaluation.lessons.push("Answer in fewer words.".to_string());
+//!         }
+//!         Ok(evaluation)
+//!     }
+//! }
+//!
+//! let mut agent = AgentGPT::new_borrowed("Answer questions", "Assistant");
+//! agent.reflection = Some(Reflection::new(Brevity));
+//!
+//! agent.reflection = Some(Reflection::from_async(|agent: AgentGPT| async move {
+//!     Ok(Evaluation::new(format!("Working on {}.", agent.objective)))
+//! }));
+//! ```
+
+use crate::agents::agent::AgentGPT;
+use crate::common::reflection::Evaluation;
+use crate::common::utils::ClientType;
+use crate::traits::agent::Agent;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::borrow::Cow;
+use std::future::Future;
+
+/// Trait implemented by the evaluator of a `Reflection`.
+#[async_trait]
+pub trait Evaluator: Send + Sync {
+    /// Evaluates the recent work of an agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The agent to evaluate.
+    /// * `client` - The client of the agent, to call the model with.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Evaluation>`): A summary of the evaluation and the lessons for the next
+    /// attempt.
+    async fn evaluate(&self, agent: &AgentGPT, client: &mut ClientType) -> Result<Evaluation>;
+}
+
+#[async_trait]
+impl Evaluator for fn(&dyn Agent) -> Cow<'static, str> {
+    async fn evaluate(&self, agent: &AgentGPT, _client: &mut ClientType) -> Result<Evaluation> {
+        Ok(Evaluation::new(self(agent)))
+    }
+}
+
+/// Evaluator running an async closure on a snapshot of the agent.
+pub struct AsyncEvaluator<F>(pub F);
+
+#[async_trait]
+impl<F, Fut> Evaluator for AsyncEvaluator<F>
+where
+    F: Fn(AgentGPT) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Evaluation>> + Send,
+{
+    async fn evaluate(&self, agent: &AgentGPT, _client: &mut ClientType) -> Result<Evaluation> {
+        (self.0)(agent.clone()).await
+    }
+}
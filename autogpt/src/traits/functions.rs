@@ -10,7 +10,7 @@
 //! use anyhow::Result;
 //! use autogpt::traits::functions::Functions;
 //! use autogpt::traits::functions::AsyncFunctions;
-//! use autogpt::traits::functions::ReqResponse;
+//! use autogpt::traits::provider::TextStream;
 //! use autogpt::common::utils::Communication;
 //! use autogpt::prelude::async_trait;
 //! use std::borrow::Cow;
@@ -97,12 +97,17 @@
 //!         ])
 //!     }
 //!
-//!     /// Retrieves the concatenated context of all communications in the agent's long-term memory.
+//!     /// Retrieves the memories most relevant to a query from the agent's long-term memory.
+//!     ///
+//!     /// # Arguments
+//!     ///
+//!     /// * `query` - The text to find memories about.
+//!     /// * `k` - The maximum number of memories to recall.
 //!     ///
 //!     /// # Returns
 //!     ///
-//!     /// (`String`): A string containing the concatenated role and content of all communications stored in the agent's long-term memory.
-//!     async fn ltm_context(&self) -> String {
+//!     /// (`String`): The recalled communications as `role: content` lines.
+//!     async fn ltm_context(&mut self, _query: &str, k: usize) -> String {
 //!         let comms = [
 //!             Communication {
 //!                 role: Cow::Borrowed("system"),
@@ -116,6 +121,7 @@
 //!
 //!         comms
 //!             .iter()
+//!             .take(k)
 //!             .map(|c| format!("{}: {}", c.role, c.content))
 //!             .collect::<Vec<_>>()
 //!             .join("\n")
@@ -130,23 +136,22 @@
 //!         Ok(Default::default())
 //!     }
 //!
-//!     async fn stream(&mut self, _request: &str) -> Result<ReqResponse> {
-//!         // TODO: Impl
-//!         Ok(ReqResponse(None))
+//!     async fn stream(&mut self, _request: &str) -> Result<TextStream> {
+//!         Ok(Box::pin(futures::stream::empty()))
 //!     }
 //! }
 //!
 
 use crate::agents::agent::AgentGPT;
-#[cfg(feature = "mem")]
-use crate::common::utils::Communication;
-use crate::common::utils::{AgentMessage, Task};
+use crate::common::config::LlmConfig;
+use crate::common::structured;
+use crate::common::utils::{AgentMessage, Communication, Task};
+use crate::traits::provider::{TextStream, transcript};
 use anyhow::Result;
 use async_trait::async_trait;
-use reqwest::Response;
-
-#[derive(Default)]
-pub struct ReqResponse(pub Option<Response>);
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use tracing::warn;
 
 /// Trait to retrieve an agent.
 pub trait Functions {
@@ -173,6 +178,9 @@ pub trait AsyncFunctions: Send + Sync {
     /// # Returns
     ///
     /// A result indicating success or failure.
+    ///
+    /// Agents deriving `Auto` refuse to run while blocked, paused or cancelled, and mark
+    /// themselves as `Failed` when the run fails.
     #[allow(async_fn_in_trait)]
     async fn execute<'a>(
         &'a mut self,
@@ -204,26 +212,145 @@ pub trait AsyncFunctions: Send + Sync {
     #[cfg(feature = "mem")]
     async fn get_ltm<'a>(&'a self) -> Result<Vec<Communication>>;
 
-    /// Retrieve the long-term memory context as a string.
+    /// Retrieve the long-term memories most relevant to a query as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to find memories about, such as the current request.
+    /// * `k` - The maximum number of memories to recall.
     ///
     /// # Returns
     ///
-    /// A string containing the concatenated context of the agent's memory.
+    /// A string containing the recalled communications, one per line.
     #[allow(async_fn_in_trait)]
     #[cfg(feature = "mem")]
-    async fn ltm_context<'a>(&'a self) -> String;
+    async fn ltm_context(&mut self, query: &str, k: usize) -> String;
 
+    /// Generate a text reply from the agent's model provider.
+    ///
+    /// Agents deriving `Auto` send the request as a single user message through
+    /// `generate_messages_with`, so that it is budgeted, cached and retried like a conversation.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The prompt to send.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the generated text.
     #[allow(async_fn_in_trait)]
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
     async fn generate(&mut self, request: &str) -> Result<String>;
 
+    /// Generate a text reply to a role-tagged conversation.
+    ///
+    /// Agents deriving `Auto` shorten the messages to the agent's token budget and forward them
+    /// to the provider's native chat format. The default implementation flattens them into a
+    /// single prompt for `generate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the generated text.
+    #[allow(async_fn_in_trait)]
+    async fn generate_messages(&mut self, messages: &[Communication]) -> Result<String> {
+        self.generate(&transcript(messages)).await
+    }
+
+    /// Generate a text reply to a role-tagged conversation, with settings layered over the
+    /// agent's own model configuration.
+    ///
+    /// Agents deriving `Auto` merge `config` into the agent's `LlmConfig` for this request only,
+    /// and send the conversation with `AgentGPT::generate_messages_with`. The default
+    /// implementation ignores `config` and calls `generate_messages`.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - Settings overriding the agent's configuration for this request.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the generated text.
+    #[allow(async_fn_in_trait)]
+    async fn generate_messages_with(
+        &mut self,
+        messages: &[Communication],
+        _config: &LlmConfig,
+    ) -> Result<String> {
+        self.generate_messages(messages).await
+    }
+
+    /// Generate a reply to a role-tagged conversation and parse it into `T`.
+    ///
+    /// The JSON schema of `T` is appended to the conversation and sent through the provider's
+    /// native JSON mode where supported. When the reply does not parse into `T`, the model is
+    /// shown the error and asked to correct its reply, up to `structured::repairs()` times.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the parsed reply, or the last parse error once the repairs run out.
+    #[allow(async_fn_in_trait)]
+    async fn generate_structured<T>(&mut self, messages: &[Communication]) -> Result<T>
+    where
+        Self: Sized,
+        T: DeserializeOwned + JsonSchema + Send,
+    {
+        let schema = structured::schema::<T>();
+        let config = LlmConfig::new().schema(schema.clone());
+        let mut messages = messages.to_vec();
+        messages.push(Communication::user(structured::instructions(&schema)));
+
+        let repairs = structured::repairs();
+        let mut repair = 0;
+        loop {
+            let reply = self.generate_messages_with(&messages, &config).await?;
+            match structured::parse::<T>(&reply) {
+                Ok(value) => return Ok(value),
+                Err(err) if repair < repairs => {
+                    repair += 1;
+                    warn!("Structured reply does not parse ({repair}/{repairs} repairs): {err}");
+                    messages.push(Communication::assistant(reply));
+                    messages.push(Communication::user(structured::repair(&err)));
+                }
+                Err(err) => {
+                    return Err(err.context(format!(
+                        "Structured reply still does not parse after {repairs} repairs"
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Generate an image from the agent's model provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The image description.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the image bytes.
     #[allow(async_fn_in_trait)]
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
     async fn imagen(&mut self, request: &str) -> Result<Vec<u8>>;
 
+    /// Stream a text reply from the agent's model provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The prompt to send.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the stream of text deltas.
     #[allow(async_fn_in_trait)]
-    #[cfg(any(feature = "oai", feature = "gem", feature = "cld", feature = "xai"))]
-    async fn stream(&mut self, request: &str) -> Result<ReqResponse>;
+    async fn stream(&mut self, request: &str) -> Result<TextStream>;
 }
 
 #[async_trait]
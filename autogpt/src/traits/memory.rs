@@ -0,0 +1,97 @@
+//! # `MemoryStore` trait.
+//!
+//! This trait abstracts over the vector stores that hold the long-term memory of agents.
+//! Records are grouped by namespace, one per agent and one per shared memory, and are found
+//! again by the similarity of their embedding to a query vector. Records past their
+//! `expires_at` are never returned, and are deleted by the store when it comes across them.
+//!
+//! Two stores are built in:
+//!
+//! - `FileStore`, an embedded store keeping each namespace in a JSON file on disk, used by
+//!   default so that long-term memory works without any external service.
+//! - `PineconeStore`, behind the `pinecone` feature, used when `PINECONE_API_KEY` and
+//!   `PINECONE_INDEX_URL` are set.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use anyhow::Result;
+//! use autogpt::common::memory::FileStore;
+//! use autogpt::common::memory::MemoryRecord;
+//! use autogpt::common::utils::Communication;
+//! use autogpt::traits::memory::MemoryStore;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let store = FileStore::new(std::env::temp_dir().join("autogpt-memory-example"));
+//! store.delete_namespace("agent-1").await?;
+//!
+//! store
+//!     .upsert(
+//!         "agent-1",
+//!         vec![
+//!             MemoryRecord::new(vec![1.0, 0.0], Communication::user("Cats purr.")),
+//!             MemoryRecord::new(vec![0.0, 1.0], Communication::user("Dogs bark.")),
+//!         ],
+//!     )
+//!     .await?;
+//!
+//! let matches = store.query("agent-1", &[0.9, 0.1], 1).await?;
+//! assert_eq!(matches[0].record.communication.content, "Cats purr.");
+//! assert_eq!(store.list("agent-1").await?.len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::memory::{MemoryMatch, MemoryRecord};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::fmt::Debug;
+
+/// Trait implemented by every long-term memory store.
+#[async_trait]
+pub trait MemoryStore: Send + Sync + Debug {
+    /// Inserts records into a namespace, replacing the records with the same identifiers.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to write to.
+    /// * `records` - The records to insert.
+    async fn upsert(&self, namespace: &str, records: Vec<MemoryRecord>) -> Result<()>;
+
+    /// Finds the records of a namespace most similar to a vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to search.
+    /// * `vector` - The embedding to compare the records with.
+    /// * `k` - The maximum number of records to return.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Vec<MemoryMatch>>`): The closest records with their similarity, the most
+    /// similar first.
+    async fn query(&self, namespace: &str, vector: &[f32], k: usize) -> Result<Vec<MemoryMatch>>;
+
+    /// Returns every record of a namespace, the oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to read.
+    async fn list(&self, namespace: &str) -> Result<Vec<MemoryRecord>>;
+
+    /// Deletes records of a namespace. Deleting missing records does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to delete from.
+    /// * `ids` - The identifiers of the records to delete.
+    async fn delete(&self, namespace: &str, ids: &[String]) -> Result<()>;
+
+    /// Deletes a namespace and all its records. Deleting a missing namespace does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to delete.
+    async fn delete_namespace(&self, namespace: &str) -> Result<()>;
+}
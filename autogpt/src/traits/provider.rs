@@ -0,0 +1,275 @@
+//! # `LlmProvider` trait.
+//!
+//! This trait abstracts over the model backends an agent can talk to. Every
+//! built-in `ClientType` variant implements it, and third-party crates can plug in
+//! their own backend by implementing it and wrapping the value with
+//! `ClientType::custom`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use anyhow::Result;
+//! use autogpt::common::utils::ClientType;
+//! use autogpt::prelude::async_trait;
+//! use autogpt::traits::provider::LlmProvider;
+//!
+//! /// A provider that answers every request with its input.
+//! #[derive(Debug)]
+//! struct EchoProvider;
+//!
+//! #[async_trait]
+//! impl LlmProvider for EchoProvider {
+//!     async fn chat(&mut self, request: &str) -> Result<String> {
+//!         Ok(request.to_string())
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let mut client = ClientType::custom(EchoProvider);
+//! assert_eq!(client.chat("ping").await?, "ping");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::config::LlmConfig;
+use crate::common::tokens::{DEFAULT_CONTEXT_WINDOW, model_context_window};
+use crate::common::tools::{ToolDefinition, ToolExchange, ToolReply, tool_transcript};
+use crate::common::usage::Completion;
+use crate::common::utils::Communication;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::Stream;
+use std::fmt::Debug;
+use std::pin::Pin;
+
+/// A stream of text deltas, in the order the model produced them.
+pub type TextStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
+/// Trait implemented by every model backend.
+///
+/// Only `chat` is required. The remaining capabilities default to an error so that
+/// providers which do not support them fail loudly instead of returning empty data.
+#[async_trait]
+pub trait LlmProvider: Send + Sync + Debug {
+    /// Sends a single user prompt and returns the model's text reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The prompt to send.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The generated text.
+    async fn chat(&mut self, request: &str) -> Result<String>;
+
+    /// Sends a role-tagged conversation and returns the model's text reply.
+    ///
+    /// Built-in providers map `system`, `user` and `assistant` messages to their native chat
+    /// format. The default implementation flattens the conversation with `transcript` and
+    /// sends it through `chat`.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The generated text.
+    async fn complete(&mut self, messages: &[Communication]) -> Result<String> {
+        self.chat(&transcript(messages)).await
+    }
+
+    /// Sends a role-tagged conversation with the given model and sampling settings.
+    ///
+    /// Built-in providers honor every setting their API supports. The default implementation
+    /// ignores `config` and calls `complete`.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The generated text.
+    async fn complete_with(
+        &mut self,
+        messages: &[Communication],
+        _config: &LlmConfig,
+    ) -> Result<String> {
+        self.complete(messages).await
+    }
+
+    /// Sends a role-tagged conversation with the given settings and returns the reply along
+    /// with the model and token counts reported by the provider.
+    ///
+    /// The default implementation calls `complete_with` and reports neither, leaving the usage
+    /// to be estimated.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Completion>`): The generated text and its usage.
+    async fn completion(
+        &mut self,
+        messages: &[Communication],
+        config: &LlmConfig,
+    ) -> Result<Completion> {
+        Ok(Completion::new(self.complete_with(messages, config).await?))
+    }
+
+    /// Sends a conversation along with the functions the model may call, and returns either
+    /// the final answer or the calls the model made.
+    ///
+    /// Built-in providers use their native function calling. The default implementation
+    /// renders the exchanges with `tool_transcript` and answers through `completion`, without
+    /// offering the tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The conversation, oldest message first.
+    /// * `exchanges` - The rounds of tool calls made so far in reply to `messages`.
+    /// * `tools` - The functions offered to the model.
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<ToolReply>`): The reply, with the calls to run before asking again.
+    async fn complete_with_tools(
+        &mut self,
+        messages: &[Communication],
+        exchanges: &[ToolExchange],
+        _tools: &[ToolDefinition],
+        config: &LlmConfig,
+    ) -> Result<ToolReply> {
+        let messages = tool_transcript(messages, exchanges);
+        Ok(ToolReply::answer(self.completion(&messages, config).await?))
+    }
+
+    /// Sends a single user prompt and streams the reply as it is generated.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The prompt to send.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<TextStream>`): The text deltas of the reply. Errors raised mid-stream are
+    /// yielded as items.
+    async fn stream(&mut self, _request: &str) -> Result<TextStream> {
+        Err(anyhow!("Streaming is not supported by this provider."))
+    }
+
+    /// Streams the reply to a single user prompt with the given model and sampling settings.
+    ///
+    /// The default implementation ignores `config` and calls `stream`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The prompt to send.
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<TextStream>`): The text deltas of the reply.
+    async fn stream_with(&mut self, request: &str, _config: &LlmConfig) -> Result<TextStream> {
+        self.stream(request).await
+    }
+
+    /// Computes an embedding vector for the given content.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The text to embed.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Vec<f64>>`): The embedding values.
+    async fn embed(&mut self, _content: &str) -> Result<Vec<f64>> {
+        Err(anyhow!("Embeddings are not supported by this provider."))
+    }
+
+    /// Generates an image from the given prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The image description.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Vec<u8>>`): The encoded image bytes.
+    async fn image(&mut self, _request: &str) -> Result<Vec<u8>> {
+        Err(anyhow!(
+            "Image generation is not supported by this provider."
+        ))
+    }
+
+    /// Answers a prompt about an image, such as a request to describe it.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The instructions or question about the image.
+    /// * `image` - The encoded image, in PNG, JPEG, GIF or WebP format.
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<String>`): The generated text.
+    async fn describe_image(
+        &mut self,
+        _prompt: &str,
+        _image: &[u8],
+        _config: &LlmConfig,
+    ) -> Result<String> {
+        Err(anyhow!(
+            "Image understanding is not supported by this provider."
+        ))
+    }
+
+    /// Returns the context window, in tokens, of the model requests sent with `config` go to.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The model and sampling settings, unset fields using the provider defaults.
+    ///
+    /// # Returns
+    ///
+    /// (`usize`): The context window of `config.model`, or `DEFAULT_CONTEXT_WINDOW` unless the
+    /// provider knows better.
+    async fn context_window(&self, config: &LlmConfig) -> usize {
+        config
+            .model
+            .as_deref()
+            .map_or(DEFAULT_CONTEXT_WINDOW, model_context_window)
+    }
+}
+
+/// Renders a conversation as plain text for providers without a native message format.
+///
+/// A conversation made of a single user message is rendered as that message alone; anything
+/// else becomes one `Role: content` paragraph per message.
+pub fn transcript(messages: &[Communication]) -> String {
+    if let [message] = messages
+        && message.role == "user"
+    {
+        return message.content.to_string();
+    }
+
+    messages
+        .iter()
+        .map(|message| {
+            let mut role = message.role.chars();
+            let role: String = match role.next() {
+                Some(first) => first.to_uppercase().chain(role).collect(),
+                None => String::new(),
+            };
+            format!("{role}: {}", message.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
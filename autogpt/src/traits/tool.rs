@@ -0,0 +1,87 @@
+//! # `ToolHandler` trait.
+//!
+//! This trait is implemented by whatever runs a `Tool`. Handlers are async, take the JSON
+//! arguments the model called the tool with and return a JSON result or an error, which is
+//! reported to the model instead of aborting the agent. Since a handler is any value, it can
+//! hold state such as an HTTP client or a database handle.
+//!
+//! Plain `fn(&str) -> String` functions implement the trait too: they receive the `input`
+//! argument and always succeed. `Tool::from_fn` builds a tool from one.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use anyhow::{Result, anyhow};
+//! use autogpt::common::utils::{Tool, ToolName};
+//! use autogpt::prelude::async_trait;
+//! use autogpt::traits::tool::ToolHandler;
+//! use serde_json::{Value, json};
+//!
+//! /// Looks up the population of a few cities.
+//! struct Population;
+//!
+//! #[async_trait]
+//! impl ToolHandler for Population {
+//!     fn parameters(&self) -> Value {
+//!         json!({
+//!             "type": "object",
+//!             "properties": { "city": { "type": "string" } },
+//!             "required": ["city"],
+//!         })
+//!     }
+//!
+//!     async fn call(&self, arguments: Value) -> Result<Value> {
+//!         match arguments["city"].as_str() {
+//!             Some("Paris") => Ok(json!(2_102_650)),
+//!             _ => Err(anyhow!("Unknown city.")),
+//!         }
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let tool = Tool::new(ToolName::KB, "Returns the population of a city.", Population);
+//! assert_eq!(tool.call(json!({ "city": "Paris" })).await?, json!(2_102_650));
+//! assert!(tool.call(json!({ "city": "Atlantis" })).await.is_err());
+//!
+//! let echo = Tool::from_fn(ToolName::Notes, "Echoes its input.", |input| input.to_string());
+//! assert_eq!(echo.run("hello").await, "hello");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::tools::{input_argument, input_schema};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Trait implemented by the handler of a `Tool`.
+#[async_trait]
+pub trait ToolHandler: Send + Sync {
+    /// Returns the JSON schema of the arguments.
+    ///
+    /// # Returns
+    ///
+    /// (`Value`): The schema, an object with a single string `input` property by default.
+    fn parameters(&self) -> Value {
+        input_schema()
+    }
+
+    /// Runs the tool.
+    ///
+    /// # Arguments
+    ///
+    /// * `arguments` - The arguments the model called the tool with, following `parameters`.
+    ///
+    /// # Returns
+    ///
+    /// (`Result<Value>`): The result of the tool, or the error to report to the model.
+    async fn call(&self, arguments: Value) -> Result<Value>;
+}
+
+#[async_trait]
+impl ToolHandler for fn(&str) -> String {
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        Ok(Value::String(self(&input_argument(&arguments))))
+    }
+}
@@ -1,6 +1,15 @@
+mod common;
+
 use autogpt::agents::agent::AgentGPT;
-use autogpt::common::utils::{Communication, Status};
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::lifecycle::AgentEvent;
+use autogpt::common::tools::ToolCall;
+use autogpt::common::utils::{ClientType, Communication, Status, Tool, ToolName};
+use autogpt::providers::mock::MockClient;
 use autogpt::traits::agent::Agent;
+use autogpt::traits::functions::{AsyncFunctions, Functions};
+use common::{offline, task};
+use serde_json::json;
 use std::borrow::Cow;
 
 #[test]
@@ -61,3 +70,117 @@ fn test_memory() {
     assert_eq!(agent.memory()[0].role, "Role");
     assert_eq!(agent.memory()[0].content, "Content");
 }
+
+#[test]
+fn test_status_transitions() {
+    let mut agent = AgentGPT::new_borrowed("Objective", "Position");
+
+    agent.try_update(Status::Paused).unwrap();
+    assert!(agent.try_update(Status::Completed).is_err());
+    assert_eq!(*agent.status(), Status::Paused);
+    assert!(agent.status().ensure_runnable().is_err());
+
+    agent.try_update(Status::Active).unwrap();
+    agent
+        .try_update(Status::Failed(Cow::Borrowed("Out of tokens")))
+        .unwrap();
+    assert!(agent.try_update(Status::Blocked).is_err());
+    assert!(agent.status().ensure_runnable().is_ok());
+
+    agent.try_update(Status::Idle).unwrap();
+    agent.try_update(Status::Cancelled).unwrap();
+    agent.try_update(Status::Cancelled).unwrap();
+    assert!(agent.try_update(Status::Active).is_err());
+    assert!(agent.status().is_final());
+
+    agent.update(Status::Active);
+    assert_eq!(*agent.status(), Status::Cancelled);
+
+    agent.update(Status::Idle);
+    assert!(!agent.status().is_final());
+}
+
+#[tokio::test]
+async fn test_agent_events() {
+    let mut agent = AgentGPT::new_borrowed("Objective", "Position");
+    let mut events = agent.subscribe();
+
+    agent.update(Status::Active);
+    agent.update(Status::Active);
+    agent.add_communication(Communication::user("Hello"));
+    agent
+        .tools
+        .push(Tool::from_fn(ToolName::Search, "Searches.", |query| {
+            format!("Results for {query}")
+        }));
+    let call = ToolCall::new("1", "search", json!({ "input": "rust" }));
+    assert_eq!(
+        agent.invoke_tool(&call).await.as_deref(),
+        Some("Results for rust")
+    );
+    assert_eq!(
+        agent
+            .invoke_tool(&ToolCall::new("2", "unknown", json!({})))
+            .await,
+        None
+    );
+
+    assert_eq!(
+        events.try_recv().unwrap(),
+        AgentEvent::StatusChanged {
+            agent: agent.id.clone(),
+            from: Status::Idle,
+            to: Status::Active,
+        }
+    );
+    assert_eq!(
+        events.try_recv().unwrap(),
+        AgentEvent::MessageAdded {
+            agent: agent.id.clone(),
+            message: Communication::user("Hello"),
+        }
+    );
+    let event = events.try_recv().unwrap();
+    assert_eq!(event.agent(), agent.id);
+    assert_eq!(
+        event,
+        AgentEvent::ToolInvoked {
+            agent: agent.id.clone(),
+            tool: "search".to_string(),
+            arguments: json!({ "input": "rust" }),
+            result: "Results for rust".to_string(),
+        }
+    );
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_autonomous_agent_honors_its_status() {
+    offline();
+
+    let mock =
+        MockClient::new().fallback(r#"{"thought": "Done.", "action": "finish", "answer": "42"}"#);
+    let mut agent = AutonomousGPT::new("Answer questions", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    agent.update(Status::Paused);
+    let err = agent.run("What is 6 times 7?").await.unwrap_err();
+    assert!(err.to_string().contains("paused"));
+    assert_eq!(agent.get_agent().status, Status::Paused);
+    assert!(mock.calls().is_empty());
+
+    agent.update(Status::Active);
+    assert_eq!(agent.run("What is 6 times 7?").await.unwrap(), "42");
+
+    agent.update(Status::Idle);
+    agent.update(Status::Cancelled);
+    assert!(
+        agent
+            .execute(&mut task("What is 6 times 7?"), false, false, 1)
+            .await
+            .is_err()
+    );
+    assert_eq!(agent.get_agent().status, Status::Cancelled);
+    assert_eq!(mock.calls().len(), 1);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::utils::{ClientType, Status, Tool, ToolName};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::Functions;
+use common::offline;
+
+#[tokio::test]
+async fn test_autonomous_agent_thinks_acts_and_observes() {
+    offline();
+
+    let mock = MockClient::new()
+        .then(r#"{"thought": "Compute it.", "action": "calc", "input": {"input": "6 * 7"}}"#)
+        .then(
+            r#"{"thought": "Done.", "action": "finish", "answer": "42", "completed_goals": [1]}"#,
+        );
+    let calc = Tool::from_fn(ToolName::Calc, "Evaluates arithmetic.", |input| {
+        format!("{input} = 42")
+    });
+    let mut agent = AutonomousGPT::new("Answer questions", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_tools(vec![calc])
+        .with_goals(["Compute the answer"]);
+
+    let answer = agent.run("What is 6 times 7?").await.unwrap();
+
+    assert_eq!(answer, "42");
+    assert_eq!(agent.steps.len(), 2);
+    assert_eq!(agent.steps[0].decision.action, "calc");
+    assert_eq!(agent.steps[0].observation, "6 * 7 = 42");
+    assert!(agent.steps[1].is_finish());
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("`calc`: Evaluates arithmetic."));
+    assert!(calls[0].contains("1. [ ] Compute the answer"));
+    assert!(calls[1].contains("Observation: 6 * 7 = 42"));
+
+    let agent = agent.get_agent();
+    assert_eq!(agent.status, Status::Completed);
+    assert!(agent.planner.as_ref().unwrap().current_plan[0].completed);
+    assert_eq!(agent.memory.last().unwrap().content, "42");
+    assert_eq!(agent.reflection.as_ref().unwrap().recent_logs.len(), 2);
+}
+
+#[tokio::test]
+async fn test_autonomous_agent_stops_on_criterion_or_step_limit() {
+    offline();
+
+    let lookup = r#"{"thought": "Look it up.", "action": "search", "input": {"input": "rust"}}"#;
+    let mut agent = AutonomousGPT::new("Research", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(MockClient::new().fallback(lookup)))
+        .with_max_steps(3);
+
+    let err = agent.run("Find the answer").await.unwrap_err();
+    assert!(err.to_string().contains("within 3 steps"));
+    assert_eq!(agent.steps.len(), 3);
+    assert!(agent.steps[0].observation.contains("Unknown tool `search`"));
+    assert!(
+        matches!(&agent.get_agent().status, Status::Failed(reason) if reason.contains("within 3 steps"))
+    );
+
+    let search = Tool::from_fn(ToolName::Search, "Searches.", |_| "found it".to_string());
+    let mut agent = agent
+        .with_tools(vec![search])
+        .with_stop(|step| step.observation.contains("found"));
+
+    assert_eq!(agent.run("Find the answer").await.unwrap(), "found it");
+    assert_eq!(agent.steps.len(), 1);
+}
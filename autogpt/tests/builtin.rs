@@ -0,0 +1,389 @@
+use autogpt::agents::agent::AgentGPT;
+use autogpt::common::utils::{Tool, ToolName};
+use autogpt::tools::builtin;
+use autogpt::tools::system::{Git, Shell};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// Returns an empty scratch directory for a test.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("autogpt-builtin-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn tool(tools: &[Tool], name: ToolName) -> &Tool {
+    tools.iter().find(|tool| tool.name == name).unwrap()
+}
+
+#[tokio::test]
+async fn test_calc_and_math() {
+    let tools = builtin(scratch("calc"));
+    let calc = tool(&tools, ToolName::Calc);
+    let math = tool(&tools, ToolName::Math);
+
+    assert_eq!(calc.run("-2 ^ 2 + 10 % 4").await, "-2");
+    assert_eq!(calc.run("7 / 2").await, "3.5");
+    assert_eq!(calc.run("1 / 0").await, "Error: Division by zero.");
+    assert!(calc.run("sqrt(4)").await.starts_with("Error: Unknown name"));
+    assert!(calc.run("(1 + 2").await.starts_with("Error: Expected"));
+    assert_eq!(
+        calc.run(&format!("{}1{}", "(".repeat(100), ")".repeat(100)))
+            .await,
+        "1"
+    );
+    for hostile in ["(".repeat(100_000), "-".repeat(100_000) + "1"] {
+        assert!(calc.run(&hostile).await.contains("nested more than 256"));
+    }
+
+    let area = math
+        .call(json!({ "expression": "round(pi * r ^ 2)", "variables": { "r": 10 } }))
+        .await
+        .unwrap();
+    assert_eq!(area, json!(314));
+    assert_eq!(
+        math.call(json!({ "expression": "max(1.5e1, log2(8), -e)" }))
+            .await
+            .unwrap(),
+        json!(15)
+    );
+    assert!(math.call(json!({ "expression": "x + 1" })).await.is_err());
+}
+
+#[tokio::test]
+async fn test_text_tools() {
+    let tools = builtin(scratch("text"));
+    let regex = tool(&tools, ToolName::Regex);
+    let format = tool(&tools, ToolName::Format);
+    let convert = tool(&tools, ToolName::Convert);
+
+    let matches = regex
+        .call(json!({ "pattern": r"(\w+)@(\w+)\.com", "text": "ann@rust.com, bob@crates.com" }))
+        .await
+        .unwrap();
+    assert_eq!(matches[1]["match"], "bob@crates.com");
+    assert_eq!(matches[1]["start"], 14);
+    assert_eq!(matches[1]["groups"], json!(["bob", "crates"]));
+    assert_eq!(
+        regex
+            .respond(json!({ "pattern": r"(\d+)", "text": "a1b22", "replacement": "<$1>" }))
+            .await,
+        "a<1>b<22>"
+    );
+    assert!(
+        regex
+            .call(json!({ "pattern": "(", "text": "" }))
+            .await
+            .is_err()
+    );
+
+    let styles = [
+        ("camel", "loadUserProfile"),
+        ("pascal", "LoadUserProfile"),
+        ("kebab", "load-user-profile"),
+        ("title", "Load User Profile"),
+    ];
+    for (style, expected) in styles {
+        assert_eq!(
+            format
+                .respond(json!({ "text": "load_user profile", "style": style }))
+                .await,
+            expected
+        );
+    }
+    assert_eq!(
+        format
+            .respond(json!({ "text": "{\"a\": [1, 2]}", "style": "compact" }))
+            .await,
+        "{\"a\":[1,2]}"
+    );
+
+    let miles = convert
+        .call(json!({ "value": 42.195, "from": "km", "to": "miles" }))
+        .await
+        .unwrap();
+    assert_eq!(miles["unit"], "miles");
+    assert!((miles["value"].as_f64().unwrap() - 26.2188).abs() < 1e-4);
+    assert_eq!(
+        convert
+            .call(json!({ "value": 1, "from": "GiB", "to": "MiB" }))
+            .await
+            .unwrap()["value"],
+        json!(1024.0)
+    );
+    assert!(
+        convert
+            .call(json!({ "value": 1, "from": "kg", "to": "s" }))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_file_tools_stay_in_the_workspace() {
+    let root = scratch("files");
+    let tools = builtin(&root);
+    let read = tool(&tools, ToolName::Read);
+    let write = tool(&tools, ToolName::Write);
+
+    write
+        .call(json!({ "path": "notes/todo.md", "content": "- parse\n" }))
+        .await
+        .unwrap();
+    write
+        .call(json!({ "path": "notes/todo.md", "content": "- test\n", "append": true }))
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(root.join("notes/todo.md")).unwrap(),
+        "- parse\n- test\n"
+    );
+    assert_eq!(
+        read.call(json!({ "path": "notes/todo.md" })).await.unwrap(),
+        json!("- parse\n- test\n")
+    );
+    assert_eq!(
+        read.call(json!({ "path": "." })).await.unwrap(),
+        json!(["notes/"])
+    );
+
+    for path in ["../escape.md", "/tmp/escape.md", "notes/../../escape.md"] {
+        let error = write.respond(json!({ "path": path, "content": "x" })).await;
+        assert!(error.contains("outside the workspace"), "{path}: {error}");
+    }
+    assert!(!root.parent().unwrap().join("escape.md").exists());
+
+    for path in [
+        ".git/config",
+        "./.git/hooks/pre-commit",
+        "notes/.GIT/config",
+    ] {
+        let error = write.respond(json!({ "path": path, "content": "x" })).await;
+        assert!(error.contains("git directory"), "{path}: {error}");
+    }
+    assert!(!root.join(".git").exists());
+    assert!(!root.join("notes/.GIT").exists());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(std::env::temp_dir(), root.join("link")).unwrap();
+        let error = read.respond(json!({ "path": "link" })).await;
+        assert!(error.contains("outside the workspace"), "{error}");
+    }
+}
+
+#[tokio::test]
+async fn test_sheet_queries_csv() {
+    let root = scratch("sheet");
+    std::fs::write(
+        root.join("sales.csv"),
+        "region,product,amount\nnorth,tea,10\nsouth,tea,30\nnorth,coffee,\"5.5\"\n",
+    )
+    .unwrap();
+    let tools = builtin(&root);
+    let sheet = tool(&tools, ToolName::Sheet);
+    let query = |arguments: Value| sheet.call(arguments);
+
+    assert_eq!(
+        query(json!({ "path": "sales.csv", "operation": "columns" }))
+            .await
+            .unwrap(),
+        json!(["region", "product", "amount"])
+    );
+    assert_eq!(
+        query(json!({ "path": "sales.csv", "operation": "rows", "column": "region", "value": "north", "limit": 1 }))
+            .await
+            .unwrap(),
+        json!([{ "region": "north", "product": "tea", "amount": "10" }])
+    );
+    assert_eq!(
+        query(json!({ "path": "sales.csv", "operation": "sum", "column": "amount" }))
+            .await
+            .unwrap(),
+        json!(45.5)
+    );
+    assert_eq!(
+        query(json!({ "path": "sales.csv", "operation": "count", "column": "product", "value": "tea" }))
+            .await
+            .unwrap(),
+        json!(2)
+    );
+    assert!(
+        query(json!({ "path": "sales.csv", "operation": "mean", "column": "product" }))
+            .await
+            .is_err()
+    );
+    assert!(
+        query(json!({ "path": "sales.csv", "operation": "max", "column": "price" }))
+            .await
+            .is_err()
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_shell_runs_only_allowlisted_programs() {
+    let root = scratch("shell");
+    std::fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+    let shell = Tool::new(
+        ToolName::Shell,
+        "Runs commands.",
+        Shell::new(&root).with_allowlist(["wc", "echo"]),
+    );
+
+    let output = shell
+        .call(json!({ "command": "wc -l a.txt" }))
+        .await
+        .unwrap();
+    assert_eq!(output["status"], 0);
+    assert_eq!(output["stdout"].as_str().unwrap().trim(), "2 a.txt");
+
+    let output = shell
+        .call(json!({ "command": "echo 'a | b' > c.txt" }))
+        .await
+        .unwrap();
+    assert_eq!(output["stdout"], "a | b > c.txt\n");
+    assert!(!root.join("c.txt").exists());
+
+    assert_eq!(
+        shell
+            .call(json!({ "command": "wc missing.txt" }))
+            .await
+            .unwrap()["status"],
+        1
+    );
+    assert!(shell.call(json!({ "command": "rm a.txt" })).await.is_err());
+    assert!(
+        shell
+            .call(json!({ "command": "/usr/bin/wc a.txt" }))
+            .await
+            .is_err()
+    );
+    for outside in [
+        "wc /etc/passwd",
+        "wc ../a.txt",
+        "wc --files0-from=/etc/hosts",
+        "wc -l/etc/passwd",
+        "wc -lc../a.txt",
+        "wc -l=/etc/passwd",
+    ] {
+        assert!(shell.call(json!({ "command": outside })).await.is_err());
+    }
+    assert!(root.join("a.txt").exists());
+}
+
+#[tokio::test]
+async fn test_git_runs_allowed_subcommands() {
+    let root = scratch("git");
+    let git = Tool::new(ToolName::Git, "Runs git.", Git::new(&root));
+
+    let init = git.call(json!({ "subcommand": "init" })).await.unwrap();
+    assert_eq!(init["status"], 0, "{init}");
+    std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+    git.call(json!({ "subcommand": "add", "args": ["main.rs"] }))
+        .await
+        .unwrap();
+
+    let status = git
+        .call(json!({ "subcommand": "status", "args": ["--short"] }))
+        .await
+        .unwrap();
+    assert_eq!(status["stdout"], "A  main.rs\n");
+
+    assert!(git.call(json!({ "subcommand": "push" })).await.is_err());
+    assert!(
+        git.call(json!({ "subcommand": "diff", "args": ["--output=/tmp/leak"] }))
+            .await
+            .is_err()
+    );
+    assert!(
+        git.call(json!({
+            "subcommand": "diff",
+            "args": ["--no-index", "/etc/passwd", "/dev/null"],
+        }))
+        .await
+        .is_err()
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_git_runs_no_hooks_or_fsmonitor() {
+    let root = scratch("git-hooks");
+    let git = Tool::new(ToolName::Git, "Runs git.", Git::new(&root));
+    git.call(json!({ "subcommand": "init" })).await.unwrap();
+
+    // Stands for a repository whose configuration and hooks were set up to run programs.
+    let monitored = root.join("monitored");
+    let hooked = root.join("hooked");
+    let mut config = std::fs::read_to_string(root.join(".git/config")).unwrap();
+    config.push_str(&format!(
+        "[core]\n\tfsmonitor = \"touch {}\"\n[user]\n\tname = Test\n\temail = test@example.com\n",
+        monitored.display()
+    ));
+    std::fs::write(root.join(".git/config"), config).unwrap();
+    let hook = root.join(".git/hooks/pre-commit");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(&hook, format!("#!/bin/sh\ntouch {}\n", hooked.display())).unwrap();
+    std::fs::set_permissions(&hook, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+    std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+    git.call(json!({ "subcommand": "status" })).await.unwrap();
+    git.call(json!({ "subcommand": "add", "args": ["main.rs"] }))
+        .await
+        .unwrap();
+    let commit = git
+        .call(json!({ "subcommand": "commit", "args": ["-m", "Add main"] }))
+        .await
+        .unwrap();
+    assert_eq!(commit["status"], 0, "{commit}");
+
+    assert!(!monitored.exists());
+    assert!(!hooked.exists());
+}
+
+#[tokio::test]
+async fn test_pad_and_agent_attachment() {
+    let mut agent = AgentGPT::default();
+    agent
+        .tools
+        .push(Tool::from_fn(ToolName::Calc, "Custom.", |_| {
+            "custom".to_string()
+        }));
+    agent.add_builtin_tools();
+    agent.add_builtin_tools();
+
+    assert_eq!(agent.tools.len(), builtin(scratch("agent")).len());
+    assert_eq!(
+        tool(&agent.tools, ToolName::Calc).run("1 + 1").await,
+        "custom"
+    );
+
+    let pad = tool(&agent.tools, ToolName::Pad).clone();
+    pad.call(json!({ "action": "write", "key": "plan", "text": "step 1" }))
+        .await
+        .unwrap();
+    let shared = tool(&agent.tools, ToolName::Pad);
+    assert_eq!(
+        shared
+            .respond(json!({ "action": "read", "key": "plan" }))
+            .await,
+        "step 1"
+    );
+    assert_eq!(
+        shared.call(json!({ "action": "list" })).await.unwrap(),
+        json!(["plan"])
+    );
+    assert!(shared.call(json!({ "action": "read" })).await.is_err());
+    assert!(
+        shared
+            .call(json!({ "action": "append", "key": "empty" }))
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        shared.call(json!({ "action": "list" })).await.unwrap(),
+        json!(["plan"])
+    );
+}
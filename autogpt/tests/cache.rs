@@ -0,0 +1,149 @@
+mod common;
+
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::common::cache::ResponseCache;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::mock::MockClient;
+#[cfg(feature = "mem")]
+use autogpt::traits::functions::Functions;
+use common::{offline, task};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Returns an empty scratch directory unique to `name`.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("autogpt-cache-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_entry_round_trip() {
+    let dir = scratch("round-trip");
+    let cache = ResponseCache::new(&dir);
+    let messages = [Communication::user("Say hello")];
+    let entry = cache.entry("generate", "mock", &LlmConfig::new(), &messages);
+
+    assert_eq!(entry.load::<String>(), None);
+    entry.store(&"Hello!");
+    assert_eq!(entry.load::<String>().as_deref(), Some("Hello!"));
+    assert!(dir.join(".gitignore").exists());
+
+    cache.clear().unwrap();
+    assert_eq!(entry.load::<String>(), None);
+}
+
+#[test]
+fn test_key_covers_request() {
+    let cache = ResponseCache::new(scratch("key"));
+    let messages = [Communication::user("Say hello")];
+    cache
+        .entry("generate", "mock", &LlmConfig::new(), &messages)
+        .store(&"Hello!");
+
+    let misses = [
+        cache.entry("embed", "mock", &LlmConfig::new(), &messages),
+        cache.entry("generate", "gemini", &LlmConfig::new(), &messages),
+        cache.entry(
+            "generate",
+            "mock",
+            &LlmConfig::new().temperature(0.5),
+            &messages,
+        ),
+        cache.entry(
+            "generate",
+            "mock",
+            &LlmConfig::new(),
+            &[Communication::user("Say goodbye")],
+        ),
+    ];
+    for entry in misses {
+        assert_eq!(entry.load::<String>(), None);
+    }
+    cache.clear().unwrap();
+}
+
+#[test]
+fn test_expired_and_bypassed_entries_miss() {
+    let dir = scratch("expiry");
+    let messages = [Communication::user("Say hello")];
+    let cache = ResponseCache::new(&dir);
+    let entry = cache.entry("generate", "mock", &LlmConfig::new(), &messages);
+    entry.store(&"Hello!");
+
+    let bypassed = ResponseCache::new(&dir).bypass(true);
+    let refreshed = bypassed.entry("generate", "mock", &LlmConfig::new(), &messages);
+    assert_eq!(refreshed.load::<String>(), None);
+    refreshed.store(&"Hi!");
+    assert_eq!(entry.load::<String>().as_deref(), Some("Hi!"));
+
+    std::thread::sleep(Duration::from_millis(20));
+    let expiring = ResponseCache::new(&dir).ttl(Duration::from_millis(10));
+    let expired = expiring.entry("generate", "mock", &LlmConfig::new(), &messages);
+    assert_eq!(expired.load::<String>(), None);
+    assert_eq!(entry.load::<String>(), None);
+}
+
+#[test]
+fn test_oldest_entries_are_evicted() {
+    let dir = scratch("eviction");
+    let cache = ResponseCache::new(&dir);
+    let entry = |text: &str| {
+        cache.entry(
+            "generate",
+            "mock",
+            &LlmConfig::new(),
+            &[Communication::user(text.to_string())],
+        )
+    };
+
+    entry("first").store(&"one");
+    let size = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|file| file.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .map(|path| std::fs::metadata(path).unwrap().len())
+        .sum::<u64>();
+    std::thread::sleep(Duration::from_millis(20));
+
+    let bounded = cache.clone().max_bytes(size + size / 2);
+    bounded
+        .entry(
+            "generate",
+            "mock",
+            &LlmConfig::new(),
+            &[Communication::user("second")],
+        )
+        .store(&"two");
+
+    assert_eq!(entry("first").load::<String>(), None);
+    assert_eq!(entry("second").load::<String>().as_deref(), Some("two"));
+    cache.clear().unwrap();
+}
+
+#[tokio::test]
+async fn test_architect_serves_cached_replies() {
+    let cache = ResponseCache::new(offline().join("cache"));
+    cache.clear().unwrap();
+
+    let mock = MockClient::new().fallback("```python\nprint('diagram')\n```");
+    for _ in 0..2 {
+        let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+            .await
+            .with_client(ClientType::Mock(mock.clone()))
+            .with_cache(cache.clone());
+        // Architects share their long-term memory, which would change the second prompt.
+        #[cfg(feature = "mem")]
+        architect.get_agent().wipe_memory().await.unwrap();
+        architect
+            .generate_diagram(&mut task("Hello world server"))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(mock.calls().len(), 1);
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the tests running agents against the mock provider.
+
+#![allow(dead_code)]
+
+use autogpt::common::utils::Task;
+use std::path::PathBuf;
+use std::sync::Once;
+use tokio::sync::Mutex;
+
+static OFFLINE: Once = Once::new();
+
+/// Held by tests that write to the shared backend workspace.
+pub static BACKEND: Mutex<()> = Mutex::const_new(());
+
+/// Points every agent created by these tests at the mock provider and a scratch workspace.
+pub fn offline() -> PathBuf {
+    let workspace = std::env::temp_dir().join(format!("autogpt-mock-{}", std::process::id()));
+    OFFLINE.call_once(|| unsafe {
+        std::env::set_var("AI_PROVIDER", "mock");
+        std::env::set_var("AUTOGPT_WORKSPACE", &workspace);
+        std::env::set_var("AUTOGPT_RETRY_INITIAL_DELAY_MS", "1");
+    });
+    workspace
+}
+
+/// Returns a task with only a description.
+pub fn task(description: &str) -> Task {
+    Task {
+        description: description.to_string().into(),
+        scope: None,
+        urls: None,
+        frontend_code: None,
+        backend_code: None,
+        api_schema: None,
+    }
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use autogpt::agents::agent::AgentGPT;
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::compaction::{CompactionPolicy, SUMMARY_HEADER, is_summary};
+use autogpt::common::retry::{ProviderError, RetryPolicy};
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::Functions;
+use common::offline;
+use std::time::Duration;
+
+/// Returns `count` alternating user and assistant turns of about 25 tokens each.
+fn turns(count: usize) -> Vec<Communication> {
+    (0..count)
+        .map(|i| {
+            let content = format!("Turn {i}: {}", "x".repeat(90));
+            if i % 2 == 0 {
+                Communication::user(content)
+            } else {
+                Communication::assistant(content)
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_compaction_keeps_a_rolling_summary() {
+    let mock = MockClient::new()
+        .then("The user asked for turns 0 to 3.")
+        .then("The user asked for turns 0 to 6.");
+    let mut client = ClientType::Mock(mock.clone());
+    let policy = CompactionPolicy::default().max_tokens(100).keep_recent(2);
+
+    let mut memory = turns(6);
+    let compacted = policy.compact(&mut client, &mut memory).await.unwrap();
+    assert_eq!(compacted, turns(6)[..4]);
+    assert_eq!(memory.len(), 3);
+    assert!(is_summary(&memory[0]));
+    assert_eq!(
+        memory[0].content,
+        format!("{SUMMARY_HEADER}The user asked for turns 0 to 3.")
+    );
+    assert_eq!(memory[1..], turns(6)[4..]);
+
+    memory.extend(turns(9).into_iter().skip(6));
+    let compacted = policy.compact(&mut client, &mut memory).await.unwrap();
+    assert_eq!(compacted, turns(7)[4..]);
+    assert_eq!(memory.len(), 3);
+    assert_eq!(
+        memory[0].content,
+        format!("{SUMMARY_HEADER}The user asked for turns 0 to 6.")
+    );
+    assert_eq!(memory[1].content, turns(9)[7].content);
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("Turn 3"));
+    assert!(!calls[0].contains("Turn 4"));
+    assert!(calls[1].contains("The user asked for turns 0 to 3."));
+    assert!(calls[1].contains("Turn 6"));
+}
+
+#[tokio::test]
+async fn test_compaction_below_threshold_or_failing() {
+    let mock = MockClient::new().fail(ProviderError::new(400, "Invalid request"));
+    let mut client = ClientType::Mock(mock.clone());
+
+    let mut memory = turns(4);
+    let policy = CompactionPolicy::default();
+    assert!(!policy.needs_compaction(&memory));
+    assert!(
+        policy
+            .compact(&mut client, &mut memory)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let few = CompactionPolicy::default().max_tokens(10).keep_recent(4);
+    assert!(!few.needs_compaction(&memory));
+
+    let small = CompactionPolicy::default().max_tokens(10).keep_recent(1);
+    assert!(small.needs_compaction(&memory));
+    assert!(small.compact(&mut client, &mut memory).await.is_err());
+    assert_eq!(memory, turns(4));
+    assert_eq!(mock.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn test_agent_compacts_its_memory() {
+    let mock = MockClient::new()
+        .fail(ProviderError::new(503, "Service unavailable"))
+        .then("Summary.");
+    let mut client = ClientType::Mock(mock);
+    let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+    agent.retry = RetryPolicy::default()
+        .initial_delay(Duration::from_millis(1))
+        .jitter(false);
+    agent.memory = turns(5);
+
+    agent.compaction = None;
+    agent.compact_memory(&mut client).await;
+    assert_eq!(agent.memory.len(), 5);
+
+    agent.compaction = Some(CompactionPolicy::default().max_tokens(10).keep_recent(2));
+    agent.compact_memory(&mut client).await;
+    assert_eq!(agent.memory.len(), 3);
+    assert_eq!(agent.memory[0].content, format!("{SUMMARY_HEADER}Summary."));
+    assert_eq!(agent.memory[1..], turns(5)[3..]);
+
+    // The summary is retried without adding the failure to the conversation, and metered.
+    assert!(
+        agent
+            .memory
+            .iter()
+            .all(|message| !message.content.contains("Service unavailable"))
+    );
+    assert_eq!(agent.usage.report().requests(), 1);
+}
+
+#[tokio::test]
+async fn test_autonomous_agent_compacts_its_memory() {
+    offline();
+
+    let mock = MockClient::new()
+        .then(r#"{"thought": "Known.", "action": "finish", "answer": "Paris"}"#)
+        .then("The user asked for the capital of France, which is Paris.")
+        .then(r#"{"thought": "Known.", "action": "finish", "answer": "Rome"}"#);
+    let mut agent = AutonomousGPT::new("Answer questions", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_compaction(CompactionPolicy::default().max_tokens(10).keep_recent(1));
+
+    agent.run("What is the capital of France?").await.unwrap();
+    assert_eq!(agent.get_agent().memory.len(), 2);
+    agent.run("What is the capital of Italy?").await.unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    assert!(calls[1].starts_with("Summarize the conversation"));
+    assert!(calls[1].contains("What is the capital of France?"));
+
+    let memory = &agent.get_agent().memory;
+    assert_eq!(memory.len(), 4);
+    assert!(is_summary(&memory[0]));
+    assert_eq!(memory[1].content, "Paris");
+    assert_eq!(memory[3].content, "Rome");
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::utils::ClientType;
+use autogpt::providers::mock::MockClient;
+use common::{offline, task};
+use std::sync::Mutex;
+
+/// Held by tests that change the process environment.
+static ENV: Mutex<()> = Mutex::new(());
+
+const VARIABLES: &[&str] = &[
+    "AUTOGPT_LLM_CONFIG",
+    "AUTOGPT_MODEL",
+    "AUTOGPT_TEMPERATURE",
+    "AUTOGPT_TOP_P",
+    "AUTOGPT_MAX_TOKENS",
+    "AUTOGPT_STOP",
+    "AUTOGPT_SEED",
+];
+
+fn clear_env() {
+    for name in VARIABLES {
+        unsafe { std::env::remove_var(name) };
+    }
+}
+
+#[test]
+fn test_merge_overrides_set_fields_only() {
+    let base = LlmConfig::new()
+        .model("llama3.2")
+        .temperature(0.7)
+        .max_tokens(512)
+        .stop(["END"]);
+    let merged = base.merge(&LlmConfig::new().temperature(0.1).seed(3));
+
+    assert_eq!(merged.model.as_deref(), Some("llama3.2"));
+    assert_eq!(merged.temperature, Some(0.1));
+    assert_eq!(merged.max_tokens, Some(512));
+    assert_eq!(merged.stop, vec!["END"]);
+    assert_eq!(merged.seed, Some(3));
+    assert_eq!(merged.model_or("gpt-4o"), "llama3.2");
+    assert_eq!(LlmConfig::new().model_or("gpt-4o"), "gpt-4o");
+}
+
+#[test]
+fn test_from_env_parses_variables() {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    clear_env();
+    unsafe {
+        std::env::set_var("AUTOGPT_MODEL", "gemini-2.5-pro");
+        std::env::set_var("AUTOGPT_TEMPERATURE", "0.5");
+        std::env::set_var("AUTOGPT_MAX_TOKENS", "not a number");
+        std::env::set_var("AUTOGPT_STOP", "```,END");
+        std::env::set_var("AUTOGPT_SEED", "42");
+    }
+
+    let config = LlmConfig::from_env();
+    clear_env();
+
+    assert_eq!(
+        config,
+        LlmConfig::new()
+            .model("gemini-2.5-pro")
+            .temperature(0.5)
+            .stop(["```", "END"])
+            .seed(42)
+    );
+}
+
+#[test]
+fn test_for_agent_applies_file_entries() {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    clear_env();
+
+    let path = std::env::temp_dir().join(format!("autogpt-config-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{
+            "temperature": 0.2,
+            "max_tokens": 1024,
+            "agents": {
+                "GitGPT": { "model": "cheap-model", "max_tokens": 256 }
+            }
+        }"#,
+    )
+    .unwrap();
+    unsafe {
+        std::env::set_var("AUTOGPT_LLM_CONFIG", &path);
+        std::env::set_var("AUTOGPT_MODEL", "strong-model");
+        std::env::set_var("AUTOGPT_TEMPERATURE", "0.4");
+    }
+
+    let git = LlmConfig::for_agent("GitGPT");
+    let backend = LlmConfig::for_agent("BackendGPT");
+    clear_env();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        git,
+        LlmConfig::new()
+            .model("cheap-model")
+            .temperature(0.4)
+            .max_tokens(256)
+    );
+    assert_eq!(
+        backend,
+        LlmConfig::new()
+            .model("strong-model")
+            .temperature(0.4)
+            .max_tokens(1024)
+    );
+}
+
+#[test]
+fn test_invalid_file_falls_back_to_the_environment() {
+    let _guard = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    clear_env();
+
+    let path = std::env::temp_dir().join(format!("autogpt-invalid-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "temperature": "warm", "#).unwrap();
+    unsafe {
+        std::env::set_var("AUTOGPT_LLM_CONFIG", &path);
+        std::env::set_var("AUTOGPT_MODEL", "strong-model");
+    }
+
+    let invalid = LlmConfig::for_agent("GitGPT");
+    std::fs::remove_file(&path).unwrap();
+    let missing = LlmConfig::from_env();
+    clear_env();
+
+    assert_eq!(invalid, LlmConfig::new().model("strong-model"));
+    assert_eq!(missing, LlmConfig::new().model("strong-model"));
+}
+
+#[tokio::test]
+async fn test_architect_sends_its_config() {
+    offline();
+
+    let config = LlmConfig::new().model("cheap-model").temperature(0.0);
+    let mock = MockClient::new().fallback("```python\nprint('diagram')\n```");
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_config(config.clone());
+
+    let mut tasks = task("Hello world server");
+    architect.generate_diagram(&mut tasks).await.unwrap();
+
+    assert!(!mock.configs().is_empty());
+    assert!(mock.configs().iter().all(|sent| *sent == config));
+}
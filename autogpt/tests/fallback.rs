@@ -0,0 +1,153 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::retry::ProviderError;
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::fallback::FallbackClient;
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::provider::{LlmProvider, TextStream};
+use futures::StreamExt;
+use std::time::Duration;
+
+/// Provider that never answers in time.
+#[derive(Debug)]
+struct Stalled;
+
+#[async_trait]
+impl LlmProvider for Stalled {
+    async fn chat(&mut self, _request: &str) -> Result<String> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok("Too late".to_string())
+    }
+
+    async fn stream(&mut self, _request: &str) -> Result<TextStream> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(Box::pin(futures::stream::empty()))
+    }
+
+    async fn embed(&mut self, _content: &str) -> Result<Vec<f64>> {
+        Ok(vec![1.0])
+    }
+
+    async fn image(&mut self, _request: &str) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    async fn context_window(&self, _config: &LlmConfig) -> usize {
+        4_096
+    }
+}
+
+#[tokio::test]
+async fn test_fallback_uses_first_successful_provider() {
+    let primary = MockClient::new().fail(ProviderError::new(503, "Service unavailable"));
+    let secondary = MockClient::new().then("From the secondary");
+    let tertiary = MockClient::new().then("From the tertiary");
+    let mut client = ClientType::Fallback(
+        FallbackClient::new()
+            .provider(ClientType::Mock(primary.clone()))
+            .provider(ClientType::Mock(secondary.clone()))
+            .provider(ClientType::Mock(tertiary.clone())),
+    );
+
+    let reply = client
+        .complete(&[
+            Communication::system("Be brief."),
+            Communication::user("Say hello"),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "From the secondary");
+    assert_eq!(primary.calls().len(), 1);
+    assert_eq!(secondary.calls().len(), 1);
+    assert!(tertiary.calls().is_empty());
+}
+
+#[tokio::test]
+async fn test_fallback_reports_every_failure() {
+    let mut client = ClientType::Fallback(
+        FallbackClient::new()
+            .provider(ClientType::Mock(
+                MockClient::new().fail(ProviderError::new(429, "Rate limited")),
+            ))
+            .provider(ClientType::Mock(MockClient::new())),
+    );
+
+    let err = client.chat("Say hello").await.unwrap_err().to_string();
+
+    assert!(err.contains("mock: Rate limited"));
+    assert!(err.contains("No scripted response left"));
+    assert!(
+        ClientType::Fallback(FallbackClient::new())
+            .chat("Say hello")
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_fallback_skips_provider_on_timeout() {
+    let backup = MockClient::new().then("Hello there").then("Streamed reply");
+    let mut client = ClientType::Fallback(
+        FallbackClient::new()
+            .provider(ClientType::custom(Stalled))
+            .provider(ClientType::Mock(backup.clone()))
+            .timeout(Duration::from_millis(20)),
+    );
+
+    assert_eq!(client.chat("Say hello").await.unwrap(), "Hello there");
+
+    let deltas: Vec<String> = client
+        .stream("Stream something")
+        .await
+        .unwrap()
+        .map(|delta| delta.unwrap())
+        .collect()
+        .await;
+    assert_eq!(deltas.concat(), "Streamed reply");
+}
+
+#[tokio::test]
+async fn test_fallback_embeds_and_sizes_with_every_provider() {
+    let mut client = ClientType::Fallback(
+        FallbackClient::new()
+            .provider(ClientType::custom(Stalled))
+            .provider(ClientType::Mock(MockClient::new().context_length(16_384))),
+    );
+
+    assert_eq!(client.embed("anything").await.unwrap(), vec![1.0]);
+    assert_eq!(client.context_window(&LlmConfig::default()).await, 4_096);
+}
+
+#[test]
+fn test_fallback_from_provider_list() {
+    let client = ClientType::from_provider("mock, local");
+
+    let ClientType::Fallback(chain) = &client else {
+        panic!("Expected a fallback chain, got {}", client.name());
+    };
+    let names: Vec<&str> = chain.providers.iter().map(ClientType::name).collect();
+    assert_eq!(names, vec!["mock", "local"]);
+}
+
+#[tokio::test]
+async fn test_fallback_keeps_model_for_first_provider_only() {
+    let primary = MockClient::new().fail(ProviderError::new(503, "Service unavailable"));
+    let secondary = MockClient::new().then("From the secondary");
+    let mut client = ClientType::Fallback(
+        FallbackClient::new()
+            .provider(ClientType::Mock(primary.clone()))
+            .provider(ClientType::Mock(secondary.clone())),
+    );
+
+    let config = LlmConfig::new().model("gemini-2.5-pro").temperature(0.3);
+    let reply = client
+        .complete_with(&[Communication::user("Say hello")], &config)
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "From the secondary");
+    assert_eq!(primary.configs(), vec![config.clone()]);
+    assert_eq!(secondary.configs(), vec![LlmConfig::new().temperature(0.3)]);
+}
@@ -6,7 +6,7 @@ use autogpt::common::utils::{Route, Scope, Task};
 use autogpt::traits::agent::Agent;
 use autogpt::traits::functions::AsyncFunctions;
 use autogpt::traits::functions::Functions;
-use autogpt::traits::functions::ReqResponse;
+use autogpt::traits::provider::TextStream;
 use serde_json::json;
 use std::borrow::Cow;
 use tracing::info;
@@ -61,7 +61,7 @@ impl AsyncFunctions for MockFunctions {
         ])
     }
 
-    async fn ltm_context(&self) -> String {
+    async fn ltm_context(&mut self, _query: &str, k: usize) -> String {
         let comms = [
             Communication {
                 role: Cow::Borrowed("system"),
@@ -75,6 +75,7 @@ impl AsyncFunctions for MockFunctions {
 
         comms
             .iter()
+            .take(k)
             .map(|c| format!("{}: {}", c.role, c.content))
             .collect::<Vec<_>>()
             .join("\n")
@@ -88,9 +89,8 @@ impl AsyncFunctions for MockFunctions {
         // TODO: Impl
         Ok(Default::default())
     }
-    async fn stream(&mut self, _request: &str) -> Result<ReqResponse> {
-        // TODO: Impl
-        Ok(ReqResponse(None))
+    async fn stream(&mut self, _request: &str) -> Result<TextStream> {
+        Ok(Box::pin(futures::stream::empty()))
     }
 }
 
@@ -0,0 +1,393 @@
+use autogpt::common::config::LlmConfig;
+use autogpt::common::retry::ProviderError;
+use autogpt::common::tools::{ToolCall, ToolExchange};
+use autogpt::common::usage::Usage;
+use autogpt::common::utils::{Communication, Tool, ToolName};
+use autogpt::providers::local::LocalClient;
+use autogpt::traits::provider::LlmProvider;
+use futures::StreamExt;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+/// Serves a single JSON response and hands back the received request body.
+async fn serve_once(response: Value) -> (String, oneshot::Receiver<String>) {
+    serve_once_as("application/json", response.to_string()).await
+}
+
+/// Serves a single response with the given content type and hands back the received request.
+async fn serve_once_as(
+    content_type: &'static str,
+    payload: String,
+) -> (String, oneshot::Receiver<String>) {
+    serve_once_with("200 OK", "", content_type, payload).await
+}
+
+/// Serves a single response with the given status line and extra headers.
+async fn serve_once_with(
+    status: &'static str,
+    headers: &'static str,
+    content_type: &'static str,
+    payload: String,
+) -> (String, oneshot::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    let _ = tx.send(format!("{head}\r\n\r\n{body}"));
+                    break;
+                }
+            }
+        }
+
+        let reply = format!(
+            "HTTP/1.1 {status}\r\n{headers}content-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            payload.len(),
+            payload
+        );
+        socket.write_all(reply.as_bytes()).await.unwrap();
+    });
+
+    (format!("http://{addr}/v1"), rx)
+}
+
+#[tokio::test]
+async fn test_local_chat_completion() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{ "message": { "role": "assistant", "content": "Hello from llama!" } }]
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2").api_key("secret");
+    let reply = client.chat("Say hello").await.unwrap();
+
+    assert_eq!(reply, "Hello from llama!");
+
+    let request = request.await.unwrap();
+    assert!(request.starts_with("POST /v1/chat/completions"));
+    assert!(
+        request
+            .to_lowercase()
+            .contains("authorization: bearer secret")
+    );
+    assert!(request.contains("\"model\":\"llama3.2\""));
+    assert!(request.contains("Say hello"));
+}
+
+#[tokio::test]
+async fn test_local_complete_sends_roles() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{ "message": { "role": "assistant", "content": "Done." } }]
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let reply = client
+        .complete(&[
+            Communication::system("You write Rust."),
+            Communication::user("Write main."),
+            Communication::assistant("fn main() {}"),
+            Communication::user("Add a print."),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "Done.");
+
+    let request = request.await.unwrap();
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+    let roles: Vec<&str> = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["role"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+    assert_eq!(body["messages"][2]["content"], "fn main() {}");
+}
+
+#[tokio::test]
+async fn test_local_complete_normalizes_roles() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{ "message": { "role": "assistant", "content": "Done." } }]
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    client
+        .complete(&[
+            Communication::system("You plan projects."),
+            Communication {
+                role: "Search the docs".into(),
+                content: "The docs say hi.".into(),
+            },
+        ])
+        .await
+        .unwrap();
+
+    let request = request.await.unwrap();
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["messages"][0]["role"], "system");
+    assert_eq!(body["messages"][1]["role"], "user");
+    assert_eq!(body["messages"][1]["content"], "The docs say hi.");
+}
+
+#[tokio::test]
+async fn test_local_complete_with_config() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{ "message": { "role": "assistant", "content": "Done." } }]
+    }))
+    .await;
+
+    let config = LlmConfig::new()
+        .model("qwen2.5-coder")
+        .temperature(0.2)
+        .top_p(0.9)
+        .max_tokens(64)
+        .stop(["```"])
+        .seed(7)
+        .schema(json!({ "type": "array" }));
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let reply = client
+        .complete_with(&[Communication::user("Write main.")], &config)
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "Done.");
+
+    let request = request.await.unwrap();
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+
+    assert_eq!(body["model"], "qwen2.5-coder");
+    assert_eq!(body["temperature"].as_f64().unwrap() as f32, 0.2);
+    assert_eq!(body["top_p"].as_f64().unwrap() as f32, 0.9);
+    assert_eq!(body["max_tokens"], 64);
+    assert_eq!(body["stop"], json!(["```"]));
+    assert_eq!(body["seed"], 7);
+    assert_eq!(body["response_format"]["type"], "json_schema");
+    assert_eq!(
+        body["response_format"]["json_schema"]["schema"],
+        json!({ "type": "array" })
+    );
+}
+
+#[tokio::test]
+async fn test_local_completion_reports_usage() {
+    let (base_url, _request) = serve_once(json!({
+        "model": "llama3.2:3b",
+        "choices": [{ "message": { "role": "assistant", "content": "Done." } }],
+        "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 }
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let completion = client
+        .completion(&[Communication::user("Write main.")], &LlmConfig::new())
+        .await
+        .unwrap();
+
+    assert_eq!(completion.text, "Done.");
+    assert_eq!(completion.model.as_deref(), Some("llama3.2:3b"));
+    assert_eq!(completion.usage, Some(Usage::new(12, 3)));
+}
+
+#[tokio::test]
+async fn test_local_rate_limit_error() {
+    let (base_url, _request) = serve_once_with(
+        "429 Too Many Requests",
+        "retry-after: 12\r\n",
+        "application/json",
+        json!({ "error": { "message": "Rate limit reached" } }).to_string(),
+    )
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let err = client.chat("Say hello").await.unwrap_err();
+    let err = err.downcast_ref::<ProviderError>().unwrap();
+
+    assert_eq!(err.status, 429);
+    assert_eq!(err.retry_after, Some(Duration::from_secs(12)));
+    assert!(err.is_transient());
+    assert!(err.message.contains("Rate limit reached"));
+}
+
+#[tokio::test]
+async fn test_local_embeddings() {
+    let (base_url, request) = serve_once(json!({
+        "data": [{ "embedding": [0.25, -0.5, 1.0] }]
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2").embedding_model("all-minilm");
+    let embedding = client.embed("some text").await.unwrap();
+
+    assert_eq!(embedding, vec![0.25, -0.5, 1.0]);
+
+    let request = request.await.unwrap();
+    assert!(request.starts_with("POST /v1/embeddings"));
+    assert!(request.contains("\"model\":\"all-minilm\""));
+}
+
+#[tokio::test]
+async fn test_local_describe_image() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{ "message": { "role": "assistant", "content": "A red square." } }]
+    }))
+    .await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let config = LlmConfig::default().model("llava");
+    let image = [0x89, b'P', b'N', b'G', 0x0d, 0x0a];
+    let reply = client
+        .describe_image("What is in this image?", &image, &config)
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "A red square.");
+
+    let request = request.await.unwrap();
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["model"], "llava");
+    let content = &body["messages"][0]["content"];
+    assert_eq!(content[0]["text"], "What is in this image?");
+    assert_eq!(
+        content[1]["image_url"]["url"],
+        "data:image/png;base64,iVBORw0K"
+    );
+}
+
+#[tokio::test]
+async fn test_local_unreachable_server() {
+    let mut client = LocalClient::new("http://127.0.0.1:9/v1", "llama3.2");
+
+    assert!(client.chat("Say hello").await.is_err());
+}
+
+#[tokio::test]
+async fn test_local_stream_deltas() {
+    let chunk =
+        |content: &str| json!({ "choices": [{ "delta": { "content": content } }] }).to_string();
+    let body = format!(
+        "data: {}\n\ndata: {}\n\n: keep-alive\n\ndata: {}\n\ndata: [DONE]\n\n",
+        json!({ "choices": [{ "delta": { "role": "assistant" } }] }),
+        chunk("Hello"),
+        chunk(", world!"),
+    );
+    let (base_url, request) = serve_once_as("text/event-stream", body).await;
+
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let deltas: Vec<String> = client
+        .stream("Say hello")
+        .await
+        .unwrap()
+        .map(|delta| delta.unwrap())
+        .collect()
+        .await;
+
+    assert_eq!(deltas, vec!["Hello", ", world!"]);
+
+    let request = request.await.unwrap();
+    assert!(request.contains("\"stream\":true"));
+}
+
+#[tokio::test]
+async fn test_local_stream_error_event() {
+    let body = format!(
+        "data: {}\n\n",
+        json!({ "error": { "message": "model not found" } })
+    );
+    let (base_url, _request) = serve_once_as("text/event-stream", body).await;
+
+    let mut client = LocalClient::new(base_url, "missing");
+    let mut stream = client.stream("Say hello").await.unwrap();
+
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("model not found"));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_local_complete_with_tools() {
+    let (base_url, request) = serve_once(json!({
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_2",
+                    "type": "function",
+                    "function": { "name": "calc", "arguments": "{\"input\":\"6 * 7\"}" }
+                }]
+            }
+        }]
+    }))
+    .await;
+
+    let tool = Tool::from_fn(ToolName::Calc, "Evaluates arithmetic.", |_| {
+        "36".to_string()
+    });
+    let exchange = ToolExchange {
+        text: String::new(),
+        calls: vec![ToolCall::new("call_1", "calc", json!({ "input": "6 * 6" }))],
+        results: vec!["36".to_string()],
+    };
+    let mut client = LocalClient::new(base_url, "llama3.2");
+    let reply = client
+        .complete_with_tools(
+            &[Communication::user("What is 6 * 6, then 6 * 7?")],
+            &[exchange],
+            &[tool.definition()],
+            &LlmConfig::new(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(reply.completion.text, "");
+    assert_eq!(
+        reply.calls,
+        vec![ToolCall::new("call_2", "calc", json!({ "input": "6 * 7" }))]
+    );
+
+    let request = request.await.unwrap();
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+
+    assert_eq!(body["tools"][0]["type"], "function");
+    assert_eq!(body["tools"][0]["function"]["name"], "calc");
+    assert_eq!(body["messages"][1]["role"], "assistant");
+    assert_eq!(body["messages"][1]["tool_calls"][0]["id"], "call_1");
+    assert_eq!(
+        body["messages"][1]["tool_calls"][0]["function"]["arguments"],
+        "{\"input\":\"6 * 6\"}"
+    );
+    assert_eq!(
+        body["messages"][2],
+        json!({ "role": "tool", "tool_call_id": "call_1", "content": "36" })
+    );
+}
@@ -0,0 +1,545 @@
+#![cfg(feature = "mem")]
+
+use async_trait::async_trait;
+use autogpt::agents::agent::AgentGPT;
+use autogpt::common::compaction::CompactionPolicy;
+use autogpt::common::memory::{
+    FileStore, HnswConfig, HnswIndex, MemoryMatch, MemoryRecord, Metric, RecallPolicy,
+    load_long_term_memory, long_term_memory_context, namespace, recall_long_term_memory,
+    relevant_memory_context, save_long_term_memory, save_shared_memory, shared_namespace,
+    with_recalled_memory,
+};
+use autogpt::common::retry::{ProviderError, RetryPolicy};
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::memory::MemoryStore;
+use autogpt::traits::provider::LlmProvider;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Returns an empty scratch directory unique to `name`.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("autogpt-memory-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn test_file_store_round_trip() {
+    let dir = scratch("round-trip");
+    let store = FileStore::new(&dir);
+
+    let cats = MemoryRecord::new(vec![1.0, 0.0, 0.0], Communication::user("Cats purr."));
+    let dogs = MemoryRecord::new(vec![0.0, 1.0, 0.0], Communication::user("Dogs bark."));
+    store
+        .upsert("agent-1", vec![cats.clone(), dogs.clone()])
+        .await
+        .unwrap();
+
+    let mut updated = dogs.clone();
+    updated.communication = Communication::user("Dogs bark loudly.");
+    store.upsert("agent-1", vec![updated]).await.unwrap();
+    assert!(dir.join(".gitignore").exists());
+
+    let reopened = FileStore::new(&dir);
+    let records = reopened.list("agent-1").await.unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0], cats);
+    assert_eq!(records[1].communication.content, "Dogs bark loudly.");
+
+    let matches = reopened
+        .query("agent-1", &[0.2, 0.9, 0.0], 1)
+        .await
+        .unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].record.id, dogs.id);
+    assert!(matches[0].score > 0.9);
+
+    assert!(store.list("agent-2").await.unwrap().is_empty());
+    store.delete_namespace("agent-1").await.unwrap();
+    store.delete_namespace("agent-1").await.unwrap();
+    assert!(
+        store
+            .query("agent-1", &[1.0, 0.0, 0.0], 5)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_file_store_keeps_namespaces_apart() {
+    let store = FileStore::new(scratch("namespaces"));
+    for name in ["team/a", "team_a", "team.a", "../team"] {
+        store
+            .upsert(
+                name,
+                vec![MemoryRecord::new(
+                    vec![1.0],
+                    Communication::user(name.to_string()),
+                )],
+            )
+            .await
+            .unwrap();
+    }
+    for name in ["team/a", "team_a", "team.a", "../team"] {
+        let records = store.list(name).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].communication.content, name);
+    }
+    assert!(!std::env::temp_dir().join("team.json").exists());
+}
+
+/// Returns an agent keeping its long-term memory in the scratch directory `name`.
+fn agent_in(name: &str) -> AgentGPT {
+    let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+    agent.ltm = Arc::new(FileStore::new(scratch(name)));
+    agent.recall = RecallPolicy::default();
+    agent
+}
+
+#[tokio::test]
+async fn test_long_term_memory_of_an_agent() {
+    let mut client = ClientType::Mock(MockClient::new());
+    let mut agent = agent_in("agent");
+
+    for content in ["The API uses JWT.", "Deploy on Fridays is forbidden."] {
+        save_long_term_memory(&mut agent, &mut client, Communication::user(content))
+            .await
+            .unwrap();
+    }
+
+    let store = agent.ltm.clone();
+    let memory = load_long_term_memory(store.as_ref(), agent.id.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        memory,
+        vec![
+            Communication::user("The API uses JWT."),
+            Communication::user("Deploy on Fridays is forbidden.")
+        ]
+    );
+    assert_eq!(
+        long_term_memory_context(store.as_ref(), agent.id.clone()).await,
+        "user: The API uses JWT.\nuser: Deploy on Fridays is forbidden."
+    );
+
+    let query: Vec<f32> = client
+        .embed("Deploy on Fridays is forbidden.")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|value| value as f32)
+        .collect();
+    let matches = store.query(&namespace(&agent.id), &query, 1).await.unwrap();
+    assert_eq!(
+        matches[0].record.communication.content,
+        "Deploy on Fridays is forbidden."
+    );
+}
+
+#[tokio::test]
+async fn test_relevant_memory_of_an_agent() {
+    let mut client = ClientType::Mock(MockClient::new());
+    let mut agent = agent_in("recall");
+
+    for content in [
+        "The API uses JWT.",
+        "The users are stored in a PostgreSQL database.",
+        "Deploy on Fridays is forbidden.",
+        "The frontend is written in Svelte.",
+    ] {
+        save_long_term_memory(&mut agent, &mut client, Communication::user(content))
+            .await
+            .unwrap();
+    }
+
+    let recalled = recall_long_term_memory(
+        &mut agent,
+        &mut client,
+        "Which database stores the users?",
+        1,
+    )
+    .await
+    .unwrap();
+    assert_eq!(recalled.len(), 1);
+    assert_eq!(
+        recalled[0].record.communication.content,
+        "The users are stored in a PostgreSQL database."
+    );
+
+    let context =
+        relevant_memory_context(&mut agent, &mut client, "When can we deploy the API?", 2).await;
+    assert_eq!(
+        context,
+        "user: The API uses JWT.\nuser: Deploy on Fridays is forbidden."
+    );
+    assert_eq!(
+        with_recalled_memory("Be brief.", &context),
+        format!("Be brief.\n\nRelevant memories from previous requests:\n{context}")
+    );
+    assert_eq!(with_recalled_memory("Be brief.", ""), "Be brief.");
+
+    let none = relevant_memory_context(&mut agent, &mut client, "Anything?", 0).await;
+    assert!(none.is_empty());
+}
+
+/// A provider whose embeddings fail with a transient error the first time.
+#[derive(Debug, Default)]
+struct FlakyEmbedder {
+    calls: usize,
+}
+
+#[async_trait]
+impl LlmProvider for FlakyEmbedder {
+    async fn chat(&mut self, request: &str) -> anyhow::Result<String> {
+        Ok(request.to_string())
+    }
+
+    async fn embed(&mut self, content: &str) -> anyhow::Result<Vec<f64>> {
+        self.calls += 1;
+        if self.calls == 1 {
+            return Err(ProviderError::new(503, "Service unavailable").into());
+        }
+        Ok(vec![content.len() as f64, 1.0])
+    }
+}
+
+#[tokio::test]
+async fn test_embeddings_retry_with_the_agent_policy() {
+    let mut client = ClientType::custom(FlakyEmbedder::default());
+    let mut agent = agent_in("embed-retry");
+    agent.retry = RetryPolicy::default()
+        .initial_delay(Duration::from_millis(1))
+        .jitter(false);
+
+    save_long_term_memory(&mut agent, &mut client, Communication::user("Use serde."))
+        .await
+        .unwrap();
+
+    let records = agent.ltm.list(&namespace(&agent.id)).await.unwrap();
+    assert_eq!(records[0].vector, [10.0, 1.0]);
+    assert!(
+        agent
+            .memory
+            .iter()
+            .all(|message| !message.content.contains("Service unavailable"))
+    );
+}
+
+/// A provider whose embeddings always fail.
+#[derive(Debug)]
+struct BrokenEmbedder;
+
+#[async_trait]
+impl LlmProvider for BrokenEmbedder {
+    async fn chat(&mut self, request: &str) -> anyhow::Result<String> {
+        Ok(request.to_string())
+    }
+
+    async fn embed(&mut self, _content: &str) -> anyhow::Result<Vec<f64>> {
+        Err(ProviderError::new(400, "Invalid request").into())
+    }
+}
+
+#[tokio::test]
+async fn test_failed_embeddings_are_not_saved() {
+    let mut agent = agent_in("embed-failure");
+    let mut client = ClientType::Mock(MockClient::new());
+    save_long_term_memory(&mut agent, &mut client, Communication::user("Use serde."))
+        .await
+        .unwrap();
+
+    let mut broken = ClientType::custom(BrokenEmbedder);
+    let saved =
+        save_long_term_memory(&mut agent, &mut broken, Communication::user("Use tokio.")).await;
+    assert!(saved.unwrap_err().to_string().contains("Invalid request"));
+    assert_eq!(
+        agent.ltm.list(&namespace(&agent.id)).await.unwrap().len(),
+        1
+    );
+
+    // Without an embedding of the query, the latest memories are recalled.
+    let recalled = recall_long_term_memory(&mut agent, &mut broken, "Which crates?", 5)
+        .await
+        .unwrap();
+    assert_eq!(recalled.len(), 1);
+    assert_eq!(recalled[0].record.communication.content, "Use serde.");
+}
+
+#[tokio::test]
+async fn test_file_store_rejects_other_dimensions() {
+    let dir = scratch("dimensions");
+    let store = FileStore::new(&dir);
+    let flat = MemoryRecord::new(vec![1.0, 0.0], Communication::user("Two dimensions."));
+    store.upsert("agent-1", vec![flat]).await.unwrap();
+
+    for vector in [vec![1.0, 0.0, 0.0], vec![]] {
+        let record = MemoryRecord::new(vector, Communication::user("Other dimensions."));
+        assert!(store.upsert("agent-1", vec![record]).await.is_err());
+    }
+    assert!(store.query("agent-1", &[1.0, 0.0, 0.0], 1).await.is_err());
+    assert_eq!(
+        store.query("agent-1", &[0.0, 1.0], 1).await.unwrap().len(),
+        1
+    );
+    assert_eq!(FileStore::new(&dir).list("agent-1").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_compaction_archives_to_long_term_memory() {
+    let mut client = ClientType::Mock(MockClient::new().then("The user wants a parser."));
+    let mut agent = AgentGPT::new_borrowed("Write a parser", "Developer");
+    agent.ltm = Arc::new(FileStore::new(scratch("archive")));
+    agent.compaction = Some(
+        CompactionPolicy::default()
+            .max_tokens(10)
+            .keep_recent(1)
+            .archive(true),
+    );
+    agent.memory = vec![
+        Communication::user("Write a JSON parser."),
+        Communication::assistant("fn parse() {}"),
+        Communication::user("Add tests."),
+    ];
+
+    agent.compact_memory(&mut client).await;
+    assert_eq!(agent.memory.len(), 2);
+    assert_eq!(
+        load_long_term_memory(agent.ltm.as_ref(), agent.id.clone())
+            .await
+            .unwrap(),
+        vec![
+            Communication::user("Write a JSON parser."),
+            Communication::assistant("fn parse() {}")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_expired_memories_are_purged() {
+    let dir = scratch("ttl");
+    let store = FileStore::new(&dir);
+    let mut expired = MemoryRecord::new(vec![1.0, 0.0], Communication::user("Expired."));
+    expired.created_at -= chrono::Duration::hours(2);
+    let expired = expired.expires_in(Duration::from_secs(3600));
+    let kept = MemoryRecord::new(vec![0.9, 0.1], Communication::user("Kept."))
+        .expires_in(Duration::from_secs(3600));
+    assert!(expired.is_expired());
+    assert!(!kept.is_expired());
+    assert!(!MemoryRecord::new(vec![1.0], Communication::user("Forever.")).is_expired());
+
+    store
+        .upsert("agent-1", vec![expired.clone(), kept.clone()])
+        .await
+        .unwrap();
+    let matches = store.query("agent-1", &[1.0, 0.0], 2).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].record.id, kept.id);
+
+    let saved = std::fs::read_to_string(dir.join("agent-1.json")).unwrap();
+    assert!(!saved.contains(&expired.id));
+    assert_eq!(store.list("agent-1").await.unwrap(), vec![kept]);
+}
+
+#[tokio::test]
+async fn test_agent_forgets_its_memories() {
+    let mut client = ClientType::Mock(MockClient::new());
+    let mut agent = agent_in("forget");
+    agent.memory_ttl = Some(Duration::from_secs(3600));
+
+    let mut ids = Vec::new();
+    for content in ["Use serde.", "Use nom.", "Write tests."] {
+        let id = save_long_term_memory(&mut agent, &mut client, Communication::user(content))
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let records = agent.ltm.list(&namespace(&agent.id)).await.unwrap();
+    assert!(records.iter().all(|record| record.expires_at.is_some()));
+
+    agent
+        .forget(&[ids[1].clone(), "missing".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        load_long_term_memory(agent.ltm.as_ref(), agent.id.clone())
+            .await
+            .unwrap(),
+        vec![
+            Communication::user("Use serde."),
+            Communication::user("Write tests.")
+        ]
+    );
+
+    agent.wipe_memory().await.unwrap();
+    assert!(
+        load_long_term_memory(agent.ltm.as_ref(), agent.id.clone())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_agents_share_a_memory() {
+    let store: Arc<dyn MemoryStore> = Arc::new(FileStore::new(scratch("shared")));
+    let mut client = ClientType::Mock(MockClient::new());
+    let mut backend = AgentGPT::new_borrowed("Write the API", "Backend");
+    let mut frontend = AgentGPT::new_borrowed("Write the UI", "Frontend");
+    for agent in [&mut backend, &mut frontend] {
+        agent.ltm = store.clone();
+        agent.recall = RecallPolicy::default();
+        agent.shared_memory = vec![Cow::Borrowed("shop")];
+    }
+
+    save_long_term_memory(
+        &mut backend,
+        &mut client,
+        Communication::assistant("The API listens on port 8000."),
+    )
+    .await
+    .unwrap();
+    save_shared_memory(
+        &mut backend,
+        &mut client,
+        "shop",
+        Communication::user("The shop sells books."),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        store.list(&shared_namespace("shop")).await.unwrap().len(),
+        1
+    );
+    assert_eq!(
+        frontend.memory_namespaces(),
+        [namespace(&frontend.id), shared_namespace("shop")]
+    );
+
+    let mut contexts = Vec::new();
+    for agent in [&mut frontend, &mut backend] {
+        let context =
+            relevant_memory_context(agent, &mut client, "What does the shop sell?", 5).await;
+        contexts.push(context);
+    }
+    assert_eq!(
+        contexts,
+        [
+            "user: The shop sells books.",
+            "assistant: The API listens on port 8000.\nuser: The shop sells books."
+        ]
+    );
+}
+
+#[test]
+fn test_recall_policy_ranking() {
+    let found = |vector: Vec<f32>, content: &'static str, score: f32, days: i64| {
+        let mut record = MemoryRecord::new(vector, Communication::user(content));
+        record.created_at -= chrono::Duration::days(days);
+        MemoryMatch { record, score }
+    };
+    let matches = vec![
+        found(vec![0.0, 1.0], "fresh", 0.5, 0),
+        found(vec![1.0, 0.0], "old", 0.9, 30),
+        found(vec![1.0, 0.0], "old copy", 0.89, 30),
+    ];
+    let contents = |ranked: Vec<MemoryMatch>| -> Vec<String> {
+        ranked
+            .into_iter()
+            .map(|found| found.record.communication.content.into_owned())
+            .collect()
+    };
+
+    let policy = RecallPolicy::default();
+    assert_eq!(
+        contents(policy.rank(matches.clone(), 2)),
+        ["old", "old copy"]
+    );
+
+    let recent = policy.recency(1.0, Duration::from_secs(24 * 3600));
+    let ranked = recent.rank(matches.clone(), 2);
+    assert_eq!(ranked[0].record.communication.content, "fresh");
+    assert!(ranked[0].score > 1.49);
+    assert!(ranked[1].score < 0.91);
+
+    let diverse = policy.diversity(0.5);
+    assert_eq!(contents(diverse.rank(matches.clone(), 2)), ["old", "fresh"]);
+    assert_eq!(diverse.rank(matches, 5).len(), 3);
+}
+
+#[tokio::test]
+async fn test_file_store_persists_its_index() {
+    let dir = scratch("index");
+    let store = FileStore::new(&dir).with_index(HnswConfig::new().metric(Metric::Euclidean));
+    let records: Vec<MemoryRecord> = (0..50)
+        .map(|i| MemoryRecord::new(vec![i as f32, 0.0], Communication::user(format!("{i}"))))
+        .collect();
+    store.upsert("agent-1", records).await.unwrap();
+    assert!(dir.join("agent-1.hnsw.json").exists());
+
+    let reopened = FileStore::new(&dir).with_index(HnswConfig::new().metric(Metric::Euclidean));
+    let matches = reopened.query("agent-1", &[20.2, 0.0], 3).await.unwrap();
+    let contents: Vec<&str> = matches
+        .iter()
+        .map(|found| found.record.communication.content.as_ref())
+        .collect();
+    assert_eq!(contents, ["20", "21", "19"]);
+    assert!((matches[0].score + 0.2).abs() < 1e-4);
+
+    // An index built with other settings is rebuilt rather than trusted.
+    let cosine = FileStore::new(&dir);
+    let matches = cosine.query("agent-1", &[1.0, 0.0], 60).await.unwrap();
+    assert_eq!(matches.len(), 50);
+    assert!((matches[0].score - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_hnsw_index_recall() {
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut next = move || -> Vec<f32> {
+        (0..16)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+            })
+            .collect()
+    };
+    let vectors: Vec<Vec<f32>> = (0..1000).map(|_| next()).collect();
+
+    for metric in [Metric::Cosine, Metric::Dot, Metric::Euclidean] {
+        let mut index = HnswIndex::new(HnswConfig::new().metric(metric));
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(i.to_string(), vector).unwrap();
+        }
+        for i in (0..1000).step_by(2) {
+            index.remove(&i.to_string());
+        }
+        assert_eq!(index.len(), 500);
+
+        let mut hits = 0;
+        for _ in 0..20 {
+            let query = next();
+            let mut exact: Vec<(f32, usize)> = (1..1000)
+                .step_by(2)
+                .map(|i| (metric.score(&query, &vectors[i]), i))
+                .collect();
+            exact.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let truth: Vec<String> = exact.iter().take(10).map(|(_, i)| i.to_string()).collect();
+
+            let found = index.search(&query, 10).unwrap();
+            assert_eq!(found.len(), 10);
+            assert!(found.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+            hits += found
+                .iter()
+                .filter(|(id, _)| truth.iter().any(|t| t == id))
+                .count();
+        }
+        assert!(hits >= 190, "{metric:?} recall@10 is {hits}/200");
+    }
+}
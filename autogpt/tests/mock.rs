@@ -0,0 +1,216 @@
+mod common;
+
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::agents::backend::BackendGPT;
+use autogpt::agents::optimizer::OptimizerGPT;
+use autogpt::common::utils::{ClientType, Status};
+use autogpt::providers::mock::{Interaction, MOCK_EMBEDDING_DIMENSIONS, MockClient};
+use autogpt::traits::agent::Agent;
+use autogpt::traits::functions::{AsyncFunctions, Functions};
+use autogpt::traits::provider::LlmProvider;
+use common::{BACKEND, offline, task};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn test_mock_scripted_replies() {
+    offline();
+
+    let mock = MockClient::new()
+        .on("diagram", "print('diagram')")
+        .then("first")
+        .then("second")
+        .fallback("default");
+    let mut client = ClientType::Mock(mock.clone());
+
+    assert_eq!(client.chat("First request").await.unwrap(), "first");
+    assert_eq!(
+        client.chat("Draw a diagram").await.unwrap(),
+        "print('diagram')"
+    );
+    assert_eq!(client.chat("Second request").await.unwrap(), "second");
+    assert_eq!(client.chat("Third request").await.unwrap(), "default");
+    assert_eq!(
+        client.chat("Draw a diagram").await.unwrap(),
+        "print('diagram')"
+    );
+
+    assert_eq!(
+        mock.calls(),
+        vec![
+            "First request",
+            "Draw a diagram",
+            "Second request",
+            "Third request",
+            "Draw a diagram",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_mock_exhausted_script_fails() {
+    offline();
+
+    let mut client = ClientType::Mock(MockClient::new().then("only"));
+
+    assert_eq!(client.chat("One").await.unwrap(), "only");
+    assert!(client.chat("Two").await.is_err());
+}
+
+#[tokio::test]
+async fn test_mock_stream_yields_words() {
+    offline();
+
+    let mock = MockClient::new().then("Hello there, world!");
+    let mut client = ClientType::Mock(mock.clone());
+
+    let deltas: Vec<String> = client
+        .stream("Greet me")
+        .await
+        .unwrap()
+        .map(|delta| delta.unwrap())
+        .collect()
+        .await;
+
+    assert_eq!(deltas, vec!["Hello ", "there, ", "world!"]);
+    assert_eq!(mock.calls(), vec!["Greet me"]);
+}
+
+#[tokio::test]
+async fn test_mock_embeddings_are_deterministic() {
+    offline();
+
+    let mut client = ClientType::Mock(MockClient::new());
+
+    let first = client.embed("Build a todo API").await.unwrap();
+    let second = client.embed("build a TODO api").await.unwrap();
+    let other = client.embed("Paint the fence").await.unwrap();
+
+    assert_eq!(first.len(), MOCK_EMBEDDING_DIMENSIONS);
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[tokio::test]
+async fn test_mock_record_then_replay() {
+    offline();
+
+    let cassette = std::env::temp_dir().join(format!(
+        "autogpt-cassette-{}-{}.json",
+        std::process::id(),
+        line!()
+    ));
+
+    let scripted = MockClient::new().then("Hello!").then("Goodbye!");
+    let recorder = MockClient::record(ClientType::Mock(scripted), &cassette);
+    let mut client = ClientType::Mock(recorder.clone());
+
+    assert_eq!(client.chat("Say hello").await.unwrap(), "Hello!");
+    assert_eq!(client.chat("Say goodbye").await.unwrap(), "Goodbye!");
+    assert_eq!(
+        recorder.interactions(),
+        vec![
+            Interaction {
+                request: "Say hello".into(),
+                response: "Hello!".into(),
+            },
+            Interaction {
+                request: "Say goodbye".into(),
+                response: "Goodbye!".into(),
+            },
+        ]
+    );
+
+    let mut replay = ClientType::Mock(MockClient::replay(&cassette).unwrap());
+
+    assert_eq!(replay.chat("Say goodbye").await.unwrap(), "Goodbye!");
+    assert_eq!(replay.chat("Say hello").await.unwrap(), "Hello!");
+    assert!(replay.chat("Say hello").await.is_err());
+
+    let _ = std::fs::remove_file(&cassette);
+}
+
+#[tokio::test]
+async fn test_backend_fix_code_bugs_with_mock() {
+    let workspace = offline();
+    let _guard = BACKEND.lock().await;
+
+    let mock = MockClient::new().then("```python\nprint('fixed')\n```");
+    let mut backend = BackendGPT::new("Fix the server", "BackendGPT", "python")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let mut tasks = task("Hello world server");
+    tasks.backend_code = Some("print('broken'".into());
+    backend.update_bugs(Some("SyntaxError: '(' was never closed".into()));
+
+    let code = backend.fix_code_bugs(&mut tasks).await.unwrap();
+
+    assert_eq!(code.trim(), "print('fixed')");
+    assert_eq!(
+        tasks.backend_code.as_deref().map(str::trim),
+        Some("print('fixed')")
+    );
+    assert_eq!(backend.get_agent().status(), &Status::Completed);
+
+    let written = std::fs::read_to_string(workspace.join("backend").join("main.py")).unwrap();
+    assert_eq!(written.trim(), "print('fixed')");
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].contains("print('broken'"));
+    assert!(calls[0].contains("was never closed"));
+}
+
+#[tokio::test]
+async fn test_architect_diagram_with_mock() {
+    offline();
+
+    let mock = MockClient::new().on("Hello world server", "```python\nprint('diagram')\n```");
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let mut tasks = task("Hello world server");
+    let diagram = architect.generate_diagram(&mut tasks).await.unwrap();
+
+    assert!(diagram.contains("print('diagram')"));
+    assert_eq!(mock.calls().len(), 1);
+    assert!(architect.get_agent().memory().len() >= 2);
+}
+
+#[tokio::test]
+async fn test_optimizer_splits_modules_with_mock() {
+    let workspace = offline().join("optimizer");
+    std::fs::create_dir_all(&workspace).unwrap();
+    std::fs::write(
+        workspace.join("main.py"),
+        "def add(a, b):\n    return a + b\n",
+    )
+    .unwrap();
+
+    let mock = MockClient::new()
+        .on(
+            "Filename: math_utils.py",
+            "def add(a, b):\n    return a + b\n",
+        )
+        .then("math_utils.py");
+    let mut optimizer = OptimizerGPT::new("Modularize the code", "OptimizerGPT", "python")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+    optimizer.workspace = workspace.to_string_lossy().to_string().into();
+
+    let mut tasks = task("Split helpers into modules");
+    optimizer
+        .execute(&mut tasks, false, false, 1)
+        .await
+        .unwrap();
+
+    assert!(workspace.join("math_utils.py").exists());
+    assert_eq!(tasks.backend_code.as_deref(), Some("import math_utils"));
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("main.py")).unwrap(),
+        "import math_utils"
+    );
+    assert_eq!(mock.calls().len(), 2);
+    assert_eq!(optimizer.get_agent().status(), &Status::Completed);
+}
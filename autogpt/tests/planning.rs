@@ -0,0 +1,164 @@
+mod common;
+
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::planning::{GoalPlan, PlannedGoal};
+use autogpt::common::utils::{ClientType, Planner};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::Functions;
+use common::offline;
+
+fn plan(goals: Vec<PlannedGoal>) -> GoalPlan {
+    GoalPlan { goals }
+}
+
+#[test]
+fn test_topological_order_follows_dependencies_then_priority() {
+    let planner = Planner::from_plan(plan(vec![
+        PlannedGoal::new("Deploy", 1, vec![2, 3]),
+        PlannedGoal::new("Build", 1, vec![4]),
+        PlannedGoal::new("Test", 1, vec![4, 4]),
+        PlannedGoal::new("Design", 3, vec![]),
+        PlannedGoal::new("Write the changelog", 2, vec![]),
+    ]))
+    .unwrap();
+
+    assert_eq!(planner.topological_order().unwrap(), vec![4, 3, 1, 2, 0]);
+    assert_eq!(planner.current_plan[2].dependencies, vec![3]);
+    assert_eq!(
+        planner.describe(),
+        "1. [ ] Deploy (after 2, 3)\n2. [ ] Build (after 4)\n3. [ ] Test (after 4)\n4. [ ] Design\n5. [ ] Write the changelog"
+    );
+}
+
+#[test]
+fn test_next_goal_waits_for_dependencies() {
+    let mut planner = Planner::from_plan(plan(vec![
+        PlannedGoal::new("Deploy", 1, vec![2]),
+        PlannedGoal::new("Build", 2, vec![]),
+        PlannedGoal::new("Document", 3, vec![]),
+    ]))
+    .unwrap();
+
+    let mut order = Vec::new();
+    while let Some(position) = planner.next_goal() {
+        order.push(position);
+        planner.complete(position);
+    }
+    assert_eq!(order, vec![1, 0, 2]);
+    assert!(planner.is_complete());
+}
+
+#[test]
+fn test_invalid_plans_are_rejected() {
+    let cycle = Planner::from_plan(plan(vec![
+        PlannedGoal::new("A", 1, vec![3]),
+        PlannedGoal::new("B", 1, vec![1]),
+        PlannedGoal::new("C", 1, vec![2]),
+        PlannedGoal::new("D", 1, vec![]),
+    ]))
+    .unwrap_err();
+    assert_eq!(
+        cycle.to_string(),
+        "The dependencies of goals 1, 2, 3 form a cycle."
+    );
+
+    for depends_on in [vec![0], vec![3], vec![1]] {
+        let err = Planner::from_plan(plan(vec![
+            PlannedGoal::new("A", 1, depends_on),
+            PlannedGoal::new("B", 1, vec![]),
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("not another goal of the plan"));
+    }
+    assert!(Planner::from_plan(GoalPlan::default()).is_err());
+
+    let parsed: GoalPlan = serde_json::from_str(r#"{"goals": [{"description": "Only"}]}"#).unwrap();
+    assert_eq!(parsed.goals[0], PlannedGoal::new("Only", 0, vec![]));
+}
+
+#[test]
+fn test_replan_keeps_completed_goals() {
+    let mut planner = Planner::from_plan(plan(vec![
+        PlannedGoal::new("Fetch", 1, vec![]),
+        PlannedGoal::new("Parse", 2, vec![1]),
+        PlannedGoal::new("Report", 3, vec![2]),
+    ]))
+    .unwrap();
+    planner.complete(0);
+
+    let before = planner.clone();
+    assert!(
+        planner
+            .replan(plan(vec![PlannedGoal::new("Loop", 1, vec![1])]))
+            .is_err()
+    );
+    assert_eq!(planner, before);
+
+    planner
+        .replan(plan(vec![
+            PlannedGoal::new("Report", 2, vec![2]),
+            PlannedGoal::new("Parse leniently", 1, vec![]),
+        ]))
+        .unwrap();
+    assert_eq!(
+        planner.describe(),
+        "1. [x] Fetch\n2. [ ] Report (after 3)\n3. [ ] Parse leniently"
+    );
+    assert_eq!(planner.next_goal(), Some(2));
+}
+
+#[tokio::test]
+async fn test_autonomous_agent_plans_and_replans_goals() {
+    offline();
+
+    let mock = MockClient::new()
+        .then(r#"{"goals": [{"description": "A", "depends_on": [2]}, {"description": "B", "depends_on": [1]}]}"#)
+        .then(
+            r#"{"goals": [
+                {"description": "Summarize the data", "priority": 1, "depends_on": [2]},
+                {"description": "Fetch the data", "priority": 2}
+            ]}"#,
+        )
+        .then(r#"{"thought": "Fetched.", "action": "finish", "answer": "rows"}"#)
+        .then(r#"{"thought": "Search it.", "action": "search", "input": {}}"#)
+        .then(r#"{"goals": [{"description": "Summarize the rows from memory"}]}"#)
+        .then(r#"{"thought": "Done.", "action": "finish", "answer": "summary"}"#);
+    let mut agent = AutonomousGPT::new("Report on data", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_max_steps(1);
+
+    let answer = agent.pursue("Summarize the sales").await.unwrap();
+
+    assert_eq!(answer, "summary");
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 6);
+    assert!(calls[1].contains("The plan is invalid: The dependencies of goals 1, 2 form a cycle."));
+    assert!(calls[2].contains("Current goal: Fetch the data"));
+    assert!(calls[3].contains("1. [ ] Summarize the data (after 2)\n2. [x] Fetch the data"));
+    assert!(calls[4].contains("Goal 1 failed: The agent did not finish within 1 steps"));
+    assert!(calls[5].contains("Current goal: Summarize the rows from memory"));
+
+    let plan = &agent.get_agent().planner.as_ref().unwrap().current_plan;
+    let goals: Vec<_> = plan
+        .iter()
+        .map(|goal| (goal.description.as_str(), goal.completed))
+        .collect();
+    assert_eq!(
+        goals,
+        [
+            ("Fetch the data", true),
+            ("Summarize the rows from memory", true)
+        ]
+    );
+
+    let mut agent = agent
+        .with_client(ClientType::Mock(
+            MockClient::new()
+                .then(r#"{"goals": [{"description": "Search"}]}"#)
+                .fallback(r#"{"thought": "Search it.", "action": "search", "input": {}}"#),
+        ))
+        .with_max_replans(0);
+    let err = agent.pursue("Find the answer").await.unwrap_err();
+    assert!(err.to_string().contains("Goal 1 `Search` failed"));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use autogpt::agents::backend::BackendGPT;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::agent::Agent;
+use autogpt::traits::functions::Functions;
+use autogpt::traits::provider::{LlmProvider, transcript};
+use common::{BACKEND, offline, task};
+
+#[derive(Debug, Default)]
+pub struct EchoProvider {
+    calls: usize,
+}
+
+#[async_trait]
+impl LlmProvider for EchoProvider {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        self.calls += 1;
+        Ok(format!("{}: {}", self.calls, request))
+    }
+
+    async fn embed(&mut self, content: &str) -> Result<Vec<f64>> {
+        Ok(vec![content.len() as f64])
+    }
+}
+
+#[tokio::test]
+async fn test_custom_provider_dispatch() {
+    let mut client = ClientType::custom(EchoProvider::default());
+
+    assert_eq!(client.chat("hello").await.unwrap(), "1: hello");
+    assert_eq!(client.chat("again").await.unwrap(), "2: again");
+    assert_eq!(client.embed("four").await.unwrap(), vec![4.0]);
+}
+
+#[tokio::test]
+async fn test_custom_provider_unsupported_capabilities() {
+    let mut client = ClientType::custom(EchoProvider::default());
+
+    assert!(client.image("a cat").await.is_err());
+    assert!(client.stream("hello").await.is_err());
+    assert!(
+        client
+            .describe_image("What is this?", &[0xff, 0xd8], &LlmConfig::default())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_custom_provider_clones_share_state() {
+    let mut client = ClientType::custom(EchoProvider::default());
+    let mut cloned = client.clone();
+
+    client.chat("first").await.unwrap();
+    assert_eq!(cloned.chat("second").await.unwrap(), "2: second");
+}
+
+#[tokio::test]
+async fn test_custom_provider_completes_flattened_conversation() {
+    let mut client = ClientType::custom(EchoProvider::default());
+
+    let reply = client
+        .complete(&[
+            Communication::system("Be brief."),
+            Communication::user("Hi"),
+            Communication::assistant("Hello!"),
+            Communication::user("Bye"),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        reply,
+        "1: System: Be brief.\n\nUser: Hi\n\nAssistant: Hello!\n\nUser: Bye"
+    );
+}
+
+#[test]
+fn test_transcript_of_single_user_message_is_the_message() {
+    assert_eq!(transcript(&[Communication::user("Just this")]), "Just this");
+    assert_eq!(
+        transcript(&[Communication::system("Only rules")]),
+        "System: Only rules"
+    );
+}
+
+#[tokio::test]
+async fn test_backend_sends_memory_as_conversation() {
+    offline();
+    let _guard = BACKEND.lock().await;
+
+    let mock = MockClient::new()
+        .then("print('first')")
+        .then("print('second')");
+    let mut backend = BackendGPT::new("Build the server", "BackendGPT", "python")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let mut tasks = task("Todo list API");
+    backend.generate_backend_code(&mut tasks).await.unwrap();
+    backend.generate_backend_code(&mut tasks).await.unwrap();
+
+    let calls = mock.calls();
+    assert!(calls[0].starts_with("System: "));
+    assert!(calls[0].ends_with("User: Task Description: Todo list API"));
+    assert!(calls[1].contains("Assistant: print('first')"));
+    assert!(!calls[1].contains("Previous Conversation"));
+    assert_eq!(backend.get_agent().memory().len(), 4);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use autogpt::agents::agent::AgentGPT;
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::reflection::{Critic, Evaluation};
+use autogpt::common::utils::{ClientType, Communication, Reflection};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::{AsyncFunctions, Functions};
+use common::{offline, task};
+
+#[tokio::test]
+async fn test_reflection_keeps_lessons_of_evaluators() {
+    offline();
+
+    let mock = MockClient::new().then("The parser looks fine.").then(
+        r#"{"summary": "Comments are not skipped.", "lessons": ["Skip lines starting with #."]}"#,
+    );
+    let mut client = ClientType::Mock(mock.clone());
+    let mut agent = AgentGPT::new_borrowed("Write a config parser", "Developer");
+    agent.memory.clear();
+    agent.add_communication(Communication::user("Parse key=value lines.".to_string()));
+    agent.add_communication(Communication::assistant("def parse(text): ...".to_string()));
+    agent.reflection = Some(Reflection::new(Critic::new()));
+
+    let evaluation = agent.reflect(&mut client).await.unwrap();
+
+    assert_eq!(evaluation.summary, "Comments are not skipped.");
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls[0].contains("Objective: Write a config parser"));
+    assert!(calls[0].contains("--- Output 1 ---\ndef parse(text): ..."));
+    assert!(calls[1].contains("does not follow the JSON schema"));
+    let logs = &agent.reflection.as_ref().unwrap().recent_logs;
+    assert_eq!(logs.last().unwrap(), "Lesson: Skip lines starting with #.");
+    assert_eq!(
+        agent.memory.last().unwrap().content,
+        "Lessons for the next attempt:\n- Skip lines starting with #."
+    );
+
+    agent.reflection = Some(Reflection::from_async(|agent: AgentGPT| async move {
+        Ok(Evaluation {
+            summary: format!("{} messages.", agent.memory.len()),
+            lessons: vec![],
+        })
+    }));
+    let evaluation = agent.reflect(&mut client).await.unwrap();
+    assert_eq!(evaluation.summary, "3 messages.");
+    assert_eq!(agent.memory.len(), 3);
+
+    agent.reflection = Some(Reflection::default());
+    let evaluation = agent.reflect(&mut client).await.unwrap();
+    assert!(evaluation.summary.contains("Total Goals: 0"));
+    assert_eq!(mock.calls().len(), 2);
+}
+
+#[tokio::test]
+async fn test_autonomous_agent_reflects_before_retrying() {
+    offline();
+
+    let mock = MockClient::new()
+        .then(r#"{"thought": "Search it.", "action": "search", "input": {}}"#)
+        .then(r#"{"summary": "No tool was available.", "lessons": ["Answer from your own knowledge."]}"#)
+        .then(r#"{"thought": "Recall it.", "action": "finish", "answer": "Paris"}"#);
+    let mut agent = AutonomousGPT::new("Answer questions", "AutonomousGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_max_steps(1)
+        .with_reflection(Reflection::new(Critic::new()));
+
+    agent
+        .execute(&mut task("What is the capital of France?"), false, false, 2)
+        .await
+        .unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    assert!(calls[1].contains("Step 1: search -> Error: Unknown tool `search`"));
+    assert!(calls[2].contains("Lessons for the next attempt:\n- Answer from your own knowledge."));
+    assert_eq!(agent.get_agent().memory.last().unwrap().content, "Paris");
+}
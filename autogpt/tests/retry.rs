@@ -0,0 +1,164 @@
+mod common;
+
+use autogpt::agents::backend::BackendGPT;
+use autogpt::common::retry::{ProviderError, RetryPolicy, is_transient, parse_retry_after};
+use autogpt::common::utils::ClientType;
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::agent::Agent;
+use autogpt::traits::functions::Functions;
+use autogpt::traits::provider::LlmProvider;
+use common::{BACKEND, offline, task};
+use std::time::Duration;
+
+fn fast() -> RetryPolicy {
+    RetryPolicy::default()
+        .initial_delay(Duration::from_millis(1))
+        .jitter(false)
+}
+
+/// Sends one prompt, retrying it with `policy` like the agents do.
+async fn chat(policy: RetryPolicy, client: &mut ClientType) -> anyhow::Result<String> {
+    let mut attempt = 1;
+    loop {
+        match client.chat("Say hello").await {
+            Ok(reply) => return Ok(reply),
+            Err(err) => attempt = policy.backoff("generate", attempt, err).await?,
+        }
+    }
+}
+
+#[test]
+fn test_parse_retry_after() {
+    assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+    assert_eq!(parse_retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon"), None);
+}
+
+#[test]
+fn test_transient_errors() {
+    assert!(is_transient(
+        &ProviderError::new(429, "Too many requests").into()
+    ));
+    assert!(is_transient(&ProviderError::new(503, "Unavailable").into()));
+    assert!(!is_transient(
+        &ProviderError::new(400, "Bad request").into()
+    ));
+    assert!(!is_transient(
+        &ProviderError::new(401, "Unauthorized").into()
+    ));
+    assert!(is_transient(&anyhow::anyhow!(
+        "Gemini: Failed to generate content: RESOURCE_EXHAUSTED"
+    )));
+    assert!(is_transient(&anyhow::anyhow!("Model is overloaded")));
+    assert!(!is_transient(&anyhow::anyhow!(
+        "Mock: No scripted response left for prompt."
+    )));
+}
+
+#[test]
+fn test_exponential_backoff() {
+    let policy = RetryPolicy::default()
+        .initial_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(350))
+        .jitter(false);
+
+    assert_eq!(policy.delay(1), Duration::from_millis(100));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(3), Duration::from_millis(350));
+    assert_eq!(policy.delay(40), Duration::from_millis(350));
+
+    let jittered = policy.jitter(true);
+    for _ in 0..20 {
+        let delay = jittered.delay(2);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+}
+
+#[test]
+fn test_next_delay_honors_retry_after_and_attempts() {
+    let policy = fast().max_attempts(2);
+    let limited: anyhow::Error = ProviderError::new(429, "Slow down")
+        .retry_after(Duration::from_secs(3))
+        .into();
+
+    assert_eq!(policy.next_delay(1, &limited), Some(Duration::from_secs(3)));
+    assert_eq!(policy.next_delay(2, &limited), None);
+    assert_eq!(RetryPolicy::none().next_delay(1, &limited), None);
+
+    let capped = policy.max_delay(Duration::from_millis(10));
+    assert_eq!(
+        capped.next_delay(1, &limited),
+        Some(Duration::from_millis(10))
+    );
+}
+
+#[tokio::test]
+async fn test_run_retries_transient_failures() {
+    let mock = MockClient::new()
+        .fail(ProviderError::new(503, "Service unavailable"))
+        .fail(ProviderError::new(429, "Rate limited").retry_after(Duration::from_millis(5)))
+        .then("Hello!");
+    let mut client = ClientType::Mock(mock.clone());
+
+    let reply = chat(fast(), &mut client).await.unwrap();
+
+    assert_eq!(reply, "Hello!");
+    assert_eq!(mock.calls().len(), 3);
+}
+
+#[tokio::test]
+async fn test_run_gives_up() {
+    let mock = MockClient::new()
+        .fail(ProviderError::new(500, "Internal error"))
+        .fail(ProviderError::new(502, "Bad gateway"))
+        .then("Too late");
+    let mut client = ClientType::Mock(mock.clone());
+
+    let err = chat(fast().max_attempts(2), &mut client).await.unwrap_err();
+
+    assert!(err.to_string().contains("Bad gateway"));
+    assert_eq!(mock.calls().len(), 2);
+}
+
+#[tokio::test]
+async fn test_run_does_not_retry_permanent_failures() {
+    let mock = MockClient::new()
+        .fail(ProviderError::new(401, "Invalid API key"))
+        .then("Unreachable");
+    let mut client = ClientType::Mock(mock.clone());
+
+    let result = chat(fast(), &mut client).await;
+
+    assert!(result.is_err());
+    assert_eq!(mock.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn test_backend_retries_unavailable_provider() {
+    offline();
+    let _guard = BACKEND.lock().await;
+
+    let mock = MockClient::new()
+        .fail(ProviderError::new(503, "Service unavailable"))
+        .then("print('hello')");
+    let mut backend = BackendGPT::new("Build the server", "BackendGPT", "python")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let mut tasks = task("Hello world server");
+    let code = backend.generate_backend_code(&mut tasks).await.unwrap();
+
+    assert_eq!(code.trim(), "print('hello')");
+    assert_eq!(mock.calls().len(), 2);
+    assert!(
+        backend
+            .get_agent()
+            .memory()
+            .iter()
+            .all(|message| !message.content.contains("Service unavailable"))
+    );
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use autogpt::agents::autonomous::AutonomousGPT;
+use autogpt::common::scheduler::{Scheduler, SharedAgent};
+use autogpt::common::utils::{ClientType, Recurrence, Task, TaskScheduler};
+use autogpt::providers::mock::MockClient;
+use chrono::{DateTime, Duration, TimeDelta, TimeZone, Utc};
+use common::{offline, task};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 3, hour, minute, 0).unwrap()
+}
+
+#[test]
+fn test_recurrence_next_time() {
+    let weekdays = Recurrence::cron("30 9 * * Mon-Fri").unwrap();
+    assert_eq!(weekdays.next_after(at(8, 0)).unwrap(), at(9, 30));
+    assert_eq!(
+        weekdays.next_after(at(9, 30)).unwrap(),
+        Utc.with_ymd_and_hms(2025, 1, 6, 9, 30, 0).unwrap()
+    );
+
+    let seconds = Recurrence::cron("*/10 * * * * *").unwrap();
+    assert_eq!(
+        seconds.next_after(at(8, 0)).unwrap(),
+        at(8, 0) + Duration::seconds(10)
+    );
+
+    assert!(Recurrence::cron("every monday").is_err());
+    assert!(Recurrence::cron("0 0 0 1 1 * 2020").is_err());
+
+    let every = Recurrence::every(std::time::Duration::from_millis(1500));
+    assert_eq!(every, Recurrence::Every(2));
+    assert_eq!(
+        every.next_after(at(8, 0)).unwrap(),
+        at(8, 0) + Duration::seconds(2)
+    );
+    assert!(Recurrence::Every(0).next_after(at(8, 0)).is_err());
+}
+
+#[test]
+fn test_take_due_reschedules_recurring_tasks() {
+    let mut scheduler = TaskScheduler::default();
+    let hourly = scheduler
+        .add_recurring(
+            "ReporterGPT",
+            Recurrence::cron("0 * * * *").unwrap(),
+            Task::from_payload("Report"),
+            at(8, 30),
+        )
+        .unwrap();
+    let once = scheduler.add("ReporterGPT", at(8, 45), Task::from_payload("Remind"));
+    let later = scheduler.add("ReporterGPT", at(12, 0), Task::from_payload("Summarize"));
+    let times: Vec<_> = scheduler.list().iter().map(|task| task.time).collect();
+    assert_eq!(times, [at(8, 45), at(9, 0), at(12, 0)]);
+
+    assert!(scheduler.take_due(at(8, 0)).is_empty());
+
+    let due = scheduler.take_due(at(10, 15));
+    let ids: Vec<_> = due.iter().map(|task| task.id).collect();
+    assert_eq!(ids, [once, hourly]);
+    assert_eq!(due[1].task.description, "Report");
+
+    let pending: Vec<_> = scheduler
+        .list()
+        .iter()
+        .map(|task| (task.id, task.time))
+        .collect();
+    assert_eq!(pending, [(hourly, at(11, 0)), (later, at(12, 0))]);
+    assert_eq!(scheduler.next_time(), Some(at(11, 0)));
+
+    assert_eq!(
+        scheduler.cancel(later).unwrap().task.description,
+        "Summarize"
+    );
+    assert!(scheduler.cancel(later).is_none());
+    let next = scheduler.add("ReporterGPT", at(13, 0), Task::from_payload("Archive"));
+    assert!(next > later);
+}
+
+#[test]
+fn test_schedule_is_saved_and_loaded() {
+    let dir = std::env::temp_dir().join(format!("autogpt-schedule-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("schedule.json");
+    assert_eq!(
+        TaskScheduler::load(&path).unwrap(),
+        TaskScheduler::default()
+    );
+
+    let mut scheduler = TaskScheduler::default();
+    scheduler.add("ReporterGPT", at(9, 0), Task::from_payload("Report"));
+    let cancelled = scheduler
+        .add_recurring(
+            "BackupGPT",
+            Recurrence::Every(60),
+            Task::from_payload("Back up"),
+            at(8, 0),
+        )
+        .unwrap();
+    scheduler.cancel(cancelled);
+    scheduler.save(&path).unwrap();
+
+    let mut loaded = TaskScheduler::load(&path).unwrap();
+    assert_eq!(loaded, scheduler);
+    assert_ne!(
+        loaded.add("ReporterGPT", at(10, 0), Task::from_payload("Again")),
+        cancelled
+    );
+
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(TaskScheduler::load(&path).is_err());
+}
+
+#[tokio::test]
+async fn test_scheduler_runs_tasks_on_their_agent() {
+    let store = offline().join("schedule.json");
+    let _ = std::fs::remove_file(&store);
+
+    let mock =
+        MockClient::new().fallback(r#"{"thought": "Done.", "action": "finish", "answer": "done"}"#);
+    let agent = AutonomousGPT::new("Report", "ReporterGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+    let agents: Vec<SharedAgent> = vec![Arc::new(Mutex::new(Box::new(agent)))];
+    let scheduler = Scheduler::new(agents.clone())
+        .await
+        .with_store(&store)
+        .unwrap();
+
+    let past = Utc::now() - TimeDelta::minutes(1);
+    let once = scheduler
+        .add("ReporterGPT", past, task("Send the daily report"))
+        .await
+        .unwrap();
+    let hourly = scheduler
+        .add_recurring(
+            "ReporterGPT",
+            Recurrence::cron("0 * * * *").unwrap(),
+            task("Check the inbox"),
+        )
+        .await
+        .unwrap();
+    assert!(
+        scheduler
+            .add("MissingGPT", past, task("Nobody runs this"))
+            .await
+            .is_err()
+    );
+
+    let restarted = Scheduler::new(agents.clone())
+        .await
+        .with_store(&store)
+        .unwrap();
+    let ids: Vec<_> = restarted.list().await.iter().map(|task| task.id).collect();
+    assert_eq!(ids, [once, hourly]);
+
+    assert_eq!(restarted.run_pending().await.unwrap(), 1);
+    assert!(mock.calls()[0].contains("Send the daily report"));
+    assert_eq!(restarted.list().await.len(), 1);
+    assert!(restarted.cancel(hourly).await.unwrap());
+    assert!(!restarted.cancel(hourly).await.unwrap());
+    assert!(
+        Scheduler::new(agents.clone())
+            .await
+            .with_store(&store)
+            .unwrap()
+            .list()
+            .await
+            .is_empty()
+    );
+
+    let runtime = restarted.spawn();
+    restarted
+        .add_after(
+            "ReporterGPT",
+            std::time::Duration::from_millis(50),
+            task("Follow up"),
+        )
+        .await
+        .unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while mock.calls().len() < 2 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    runtime.abort();
+
+    assert!(mock.calls()[1].contains("Follow up"));
+    assert!(restarted.list().await.is_empty());
+    let agent = agents[0].lock().await;
+    assert_eq!(agent.get_agent().memory.last().unwrap().content, "done");
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::common::structured::{instructions, parse, schema};
+use autogpt::common::utils::{ClientType, Scope, extract_json_string};
+use autogpt::providers::mock::MockClient;
+use common::{offline, task};
+use std::borrow::Cow;
+
+#[test]
+fn test_parse_tolerates_fences_and_prose() {
+    let expected = Scope {
+        crud: true,
+        auth: false,
+        external: true,
+    };
+    let json = r#"{"crud": true, "auth": false, "external": true}"#;
+
+    assert_eq!(parse::<Scope>(json).unwrap(), expected);
+    assert_eq!(
+        parse::<Scope>(&format!("```json\n{json}\n```")).unwrap(),
+        expected
+    );
+    assert_eq!(
+        parse::<Scope>(&format!("Here is the scope: {json}. Let me know!")).unwrap(),
+        expected
+    );
+
+    let urls: Vec<Cow<'static, str>> =
+        parse("The endpoints are [\"https://a.dev\", \"https://b.dev\"].").unwrap();
+    assert_eq!(urls, vec!["https://a.dev", "https://b.dev"]);
+}
+
+#[test]
+fn test_parse_reports_schema_mismatch() {
+    let err = parse::<Scope>("Sure! {\"crud\": true}").unwrap_err();
+    assert!(err.to_string().contains("missing field `auth`"), "{err}");
+
+    assert!(parse::<Scope>("I cannot answer that.").is_err());
+}
+
+#[test]
+fn test_schema_is_sent_in_instructions() {
+    let schema = schema::<Scope>();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["properties"]["crud"]["type"], "boolean");
+    assert!(instructions(&schema).contains(&schema.to_string()));
+}
+
+#[test]
+fn test_extract_json_string_finds_any_object() {
+    let text = "Result:\n{\"auth\": true, \"note\": \"use {braces}\"} and more {}";
+    assert_eq!(
+        extract_json_string(text).as_deref(),
+        Some("{\"auth\": true, \"note\": \"use {braces}\"}")
+    );
+    assert_eq!(extract_json_string("{\"unterminated\": true"), None);
+    assert_eq!(extract_json_string("no json"), None);
+}
+
+#[tokio::test]
+async fn test_architect_repairs_malformed_scope() {
+    offline();
+
+    let mock = MockClient::new()
+        .then("Sure, the project needs CRUD.")
+        .then("{\"crud\": true}")
+        .then("```json\n{\"crud\": true, \"auth\": true, \"external\": false}\n```");
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let scope = architect
+        .get_scope(&mut task("Todo app with accounts"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        scope,
+        Scope {
+            crud: true,
+            auth: true,
+            external: false,
+        }
+    );
+    assert_eq!(mock.calls().len(), 3);
+    assert!(mock.calls()[2].contains("missing field `auth`"));
+    assert!(mock.configs().iter().all(|config| config.schema.is_some()));
+}
+
+#[tokio::test]
+async fn test_architect_gives_up_after_repairs() {
+    offline();
+
+    let mock = MockClient::new().fallback("I cannot answer that.");
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let err = architect
+        .get_scope(&mut task("Todo app with accounts"))
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("after 2 repairs"), "{err}");
+    assert_eq!(mock.calls().len(), 3);
+}
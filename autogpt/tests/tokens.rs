@@ -0,0 +1,235 @@
+use autogpt::agents::agent::AgentGPT;
+use autogpt::agents::manager::ManagerGPT;
+use autogpt::common::compaction::CompactionPolicy;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::tokens::{
+    DEFAULT_CONTEXT_WINDOW, TokenBudget, TruncationPolicy, estimate_message_tokens,
+    estimate_tokens, model_context_window,
+};
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::local::LocalClient;
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::provider::LlmProvider;
+use std::sync::Once;
+
+static MOCK_PROVIDER: Once = Once::new();
+
+fn conversation() -> Vec<Communication> {
+    vec![
+        Communication::system("You write Python."),
+        Communication::user(format!("Task: {}", "a".repeat(96))),
+        Communication::assistant("b".repeat(100)),
+        Communication::user("c".repeat(100)),
+        Communication::assistant("d".repeat(100)),
+        Communication::user("Now add tests."),
+    ]
+}
+
+#[test]
+fn test_estimate_tokens() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+    assert_eq!(
+        estimate_message_tokens(&[Communication::user("abcd")]),
+        estimate_tokens("abcd") + 4
+    );
+}
+
+#[test]
+fn test_model_context_window() {
+    assert_eq!(model_context_window("gemini-2.0-flash"), 1_048_576);
+    assert_eq!(model_context_window("gpt-4o"), 128_000);
+    assert_eq!(model_context_window("claude-3-7-sonnet-latest"), 200_000);
+    assert_eq!(model_context_window("llama3.2"), 131_072);
+    assert_eq!(model_context_window("llama3"), 8_192);
+    assert_eq!(
+        model_context_window("my-custom-model"),
+        DEFAULT_CONTEXT_WINDOW
+    );
+}
+
+#[test]
+fn test_budget_limit() {
+    let budget = TokenBudget::default();
+    assert_eq!(budget.limit(8_192), 8_192 - 1_024);
+    assert_eq!(budget.clone().max_context(2_048).limit(8_192), 1_024);
+    assert_eq!(budget.limit(512), 0);
+}
+
+#[test]
+fn test_conversation_within_budget_is_untouched() {
+    let messages = conversation();
+    let budget = TokenBudget::default();
+
+    assert_eq!(budget.truncate(&messages, DEFAULT_CONTEXT_WINDOW), messages);
+}
+
+#[test]
+fn test_drop_oldest_keeps_system_and_latest() {
+    let messages = conversation();
+    let budget = TokenBudget::new(TruncationPolicy::DropOldest)
+        .max_context(70)
+        .reserved(0);
+
+    let kept = budget.truncate(&messages, DEFAULT_CONTEXT_WINDOW);
+
+    assert_eq!(
+        kept,
+        vec![
+            messages[0].clone(),
+            messages[4].clone(),
+            messages[5].clone()
+        ]
+    );
+    assert!(estimate_message_tokens(&kept) <= 70);
+}
+
+#[test]
+fn test_keep_last_keeps_task() {
+    let messages = conversation();
+    let budget = TokenBudget::new(TruncationPolicy::KeepLast(1)).reserved(0);
+
+    let kept = budget.truncate(&messages, DEFAULT_CONTEXT_WINDOW);
+
+    assert_eq!(
+        kept,
+        vec![
+            messages[0].clone(),
+            messages[1].clone(),
+            messages[4].clone(),
+            messages[5].clone()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_summarize_replaces_overflow() {
+    let mock = MockClient::new()
+        .context_length(80)
+        .on("Summarize", "Writing a Python tool.");
+    let mut client = ClientType::Mock(mock.clone());
+    let messages = conversation();
+    let budget = TokenBudget::new(TruncationPolicy::Summarize).reserved(0);
+
+    let kept = budget
+        .fit(&mut client, &messages, &LlmConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(kept[0], messages[0]);
+    assert_eq!(kept[1].role, "system");
+    assert!(kept[1].content.ends_with("Writing a Python tool."));
+    assert_eq!(kept.last(), messages.last());
+    assert!(estimate_message_tokens(&kept) <= 80);
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].contains(&"b".repeat(100)));
+    assert!(!calls[0].contains(&"c".repeat(100)));
+}
+
+#[tokio::test]
+async fn test_summarize_failure_falls_back_to_dropping() {
+    let mut client = ClientType::Mock(MockClient::new().context_length(70));
+    let messages = conversation();
+    let budget = TokenBudget::new(TruncationPolicy::Summarize).reserved(0);
+
+    let kept = budget
+        .fit(&mut client, &messages, &LlmConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        kept,
+        vec![
+            messages[0].clone(),
+            messages[4].clone(),
+            messages[5].clone()
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_agent_meters_its_summaries() {
+    let mock = MockClient::new()
+        .context_length(80)
+        .on("Summarize", "Writing a Python tool.");
+    let mut client = ClientType::Mock(mock);
+    let mut agent = AgentGPT::new_borrowed("Write a tool", "Developer");
+    agent.budget = TokenBudget::new(TruncationPolicy::Summarize).reserved(0);
+
+    let kept = agent
+        .fit_to_budget(&mut client, &conversation(), &LlmConfig::default())
+        .await
+        .unwrap();
+
+    assert!(kept[1].content.ends_with("Writing a Python tool."));
+    assert_eq!(agent.usage.report().requests(), 1);
+}
+
+#[tokio::test]
+async fn test_budget_follows_the_configured_model_and_reply() {
+    let mut client = ClientType::Local(LocalClient::new("http://localhost:11434/v1", "gpt-4o"));
+    let budget = TokenBudget::default();
+    let default = LlmConfig::default();
+    let gpt4 = LlmConfig::default().model("gpt-4").max_tokens(2_000);
+
+    assert_eq!(client.context_window(&default).await, 128_000);
+    assert_eq!(client.context_window(&gpt4).await, 8_192);
+    assert_eq!(budget.for_config(&default).reserved, 1_024);
+    assert_eq!(budget.for_config(&gpt4).reserved, 2_000);
+
+    let messages = vec![
+        Communication::user("a".repeat(4 * 7_000)),
+        Communication::user("What now?"),
+    ];
+    assert_eq!(
+        budget.fit(&mut client, &messages, &default).await.unwrap(),
+        messages
+    );
+    assert_eq!(
+        budget.fit(&mut client, &messages, &gpt4).await.unwrap(),
+        messages[1..]
+    );
+}
+
+#[tokio::test]
+async fn test_agent_generates_within_its_budget() {
+    let mock = MockClient::new()
+        .context_length(80)
+        .on("Summarize", "Earlier turns.")
+        .fallback("Done.");
+    let mut client = ClientType::Mock(mock.clone());
+    let mut agent = AgentGPT::new_borrowed("Write a tool", "Developer");
+    agent.budget = TokenBudget::default().reserved(0);
+    agent.compaction = Some(CompactionPolicy::default().max_tokens(10).keep_recent(1));
+    agent.memory = conversation()[1..3].to_vec();
+
+    let reply = agent
+        .generate_messages_with(&mut client, &conversation(), &LlmConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "Done.");
+    assert!(agent.memory[0].content.ends_with("Earlier turns."));
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(!calls[1].contains(&"b".repeat(100)));
+    assert_eq!(agent.usage.report().requests(), 2);
+}
+
+#[tokio::test]
+async fn test_manager_prompts_go_through_its_agent() {
+    MOCK_PROVIDER.call_once(|| unsafe { std::env::set_var("AI_PROVIDER", "mock") });
+    let mock = MockClient::new().fallback("```\nrust\n```");
+    let mut manager = ManagerGPT::new("Manage projects", "Manager", "Build a CLI", "rust")
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_config(LlmConfig::default().max_tokens(64));
+
+    let reply = manager.execute_prompt("Pick a language".to_string()).await;
+
+    assert_eq!(reply.unwrap().trim(), "rust");
+    assert_eq!(mock.configs()[0].max_tokens, Some(64));
+    assert_eq!(manager.usage().requests(), 1);
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use anyhow::{Result, anyhow};
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::tools::{ToolCall, ToolExchange, find_tool, tool_name, tool_transcript};
+use autogpt::common::utils::{ClientType, Communication, Tool, ToolName};
+use autogpt::prelude::async_trait;
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::Functions;
+use autogpt::traits::provider::LlmProvider;
+use autogpt::traits::tool::ToolHandler;
+use common::{offline, task};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A provider without native tool calling, answering with the last message it received.
+#[derive(Debug)]
+struct EchoProvider;
+
+#[async_trait]
+impl LlmProvider for EchoProvider {
+    async fn chat(&mut self, request: &str) -> Result<String> {
+        Ok(request.lines().last().unwrap_or_default().to_string())
+    }
+}
+
+/// A stateful tool adding its arguments to a running total, failing on negative amounts.
+#[derive(Debug, Default)]
+struct Counter {
+    total: AtomicU64,
+}
+
+#[async_trait]
+impl ToolHandler for Counter {
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "amount": { "type": "integer" } },
+            "required": ["amount"],
+        })
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value> {
+        let amount = arguments["amount"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("`amount` must be a positive integer"))?;
+        tokio::task::yield_now().await;
+        let total = self.total.fetch_add(amount, Ordering::SeqCst) + amount;
+        Ok(json!({ "total": total }))
+    }
+}
+
+#[test]
+fn test_tool_names_are_valid_function_names() {
+    assert_eq!(tool_name(&ToolName::Calc), "calc");
+    assert_eq!(tool_name(&ToolName::TLDR), "tldr");
+    assert_eq!(
+        tool_name(&ToolName::Plugin("Web Lookup.v2".to_string())),
+        "web_lookup_v2"
+    );
+
+    let tools = [Tool {
+        name: ToolName::Plugin("lookup".to_string()),
+        ..Default::default()
+    }];
+    assert!(find_tool(&tools, &ToolCall::new("1", "lookup", json!({}))).is_some());
+    assert!(find_tool(&tools, &ToolCall::new("1", "search", json!({}))).is_none());
+}
+
+#[test]
+fn test_call_input_tolerates_schema_drift() {
+    let input = |arguments| ToolCall::new("1", "calc", arguments).input();
+
+    assert_eq!(input(json!({ "input": "1 + 1" })), "1 + 1");
+    assert_eq!(input(json!({ "input": 2 })), "2");
+    assert_eq!(input(json!("1 + 1")), "1 + 1");
+    assert_eq!(
+        input(json!({ "expression": "1 + 1" })),
+        "{\"expression\":\"1 + 1\"}"
+    );
+}
+
+#[tokio::test]
+async fn test_default_provider_answers_from_transcript() {
+    let exchanges = [ToolExchange {
+        text: "Let me compute.".to_string(),
+        calls: vec![ToolCall::new("1", "calc", json!({ "input": "1 + 1" }))],
+        results: vec!["2".to_string()],
+    }];
+    let messages = [Communication::user("What is 1 + 1?")];
+
+    let transcript = tool_transcript(&messages, &exchanges);
+    assert_eq!(transcript.len(), 3);
+    assert_eq!(
+        transcript[1].content,
+        "Let me compute.\nCalling `calc` with {\"input\":\"1 + 1\"}"
+    );
+    assert_eq!(transcript[2].content, "Result of `calc`: 2");
+
+    let mut client = ClientType::custom(EchoProvider);
+    let reply = client
+        .complete_with_tools(&messages, &exchanges, &[], &LlmConfig::new())
+        .await
+        .unwrap();
+
+    assert!(reply.calls.is_empty());
+    assert_eq!(reply.completion.text, "User: Result of `calc`: 2");
+    assert_eq!(reply.completion.model.as_deref(), Some("custom"));
+}
+
+#[tokio::test]
+async fn test_handlers_keep_state_and_report_errors() {
+    let tool = Tool::new(
+        ToolName::Plugin("counter".to_string()),
+        "Counts.",
+        Counter::default(),
+    );
+    let copy = tool.clone();
+
+    assert_eq!(tool.definition().parameters["required"], json!(["amount"]));
+    assert_eq!(
+        tool.call(json!({ "amount": 2 })).await.unwrap(),
+        json!({ "total": 2 })
+    );
+    assert_eq!(copy.respond(json!({ "amount": 3 })).await, "{\"total\":5}");
+    assert!(tool.call(json!({ "amount": -1 })).await.is_err());
+    assert_eq!(
+        tool.respond(json!({ "amount": -1 })).await,
+        "Error: `amount` must be a positive integer"
+    );
+}
+
+#[tokio::test]
+async fn test_function_tools_take_a_single_input() {
+    let tool = Tool::from_fn(ToolName::Notes, "Shouts.", |input| input.to_uppercase());
+
+    assert_eq!(tool.definition().parameters["required"], json!(["input"]));
+    assert_eq!(tool.run("hi").await, "HI");
+    assert_eq!(
+        tool.call(json!({ "input": "hi" })).await.unwrap(),
+        json!("HI")
+    );
+    assert_eq!(Tool::default().run("hi").await, "default tool output");
+}
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn test_function_pointer_tools_still_build() {
+    fn shout(input: &str) -> String {
+        input.to_uppercase()
+    }
+
+    let tool = Tool::with_invoke(ToolName::Notes, "Shouts.", shout);
+
+    assert_eq!(tool.definition().name, "notes");
+    assert_eq!(tool.run("hi").await, "HI");
+}
+
+#[tokio::test]
+async fn test_architect_calls_its_tools() {
+    offline();
+
+    let mock = MockClient::new()
+        .then_call("calc", "6 * 7")
+        .then("```python\nprint(42)\n```");
+    let calc = Tool::from_fn(ToolName::Calc, "Evaluates arithmetic.", |input| {
+        format!("{input} = 42")
+    });
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_tools(vec![calc]);
+
+    let diagram = architect
+        .generate_diagram(&mut task("Compute the answer"))
+        .await
+        .unwrap();
+
+    assert_eq!(diagram, "print(42)");
+    assert_eq!(mock.calls().len(), 2);
+    assert!(mock.calls()[1].contains("Result of `calc`: 6 * 7 = 42"));
+    assert_eq!(architect.get_agent().usage.report().requests(), 2);
+}
+
+#[tokio::test]
+async fn test_architect_sees_tool_errors() {
+    offline();
+
+    /// A tool that is always down.
+    struct Unavailable;
+
+    #[async_trait]
+    impl ToolHandler for Unavailable {
+        async fn call(&self, _arguments: Value) -> Result<Value> {
+            Err(anyhow!("service unavailable"))
+        }
+    }
+
+    let mock = MockClient::new()
+        .then_call("search", "rust")
+        .then_call("lookup", "rust")
+        .then("```python\nprint('offline')\n```");
+    let search = Tool::new(ToolName::Search, "Searches the web.", Unavailable);
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()))
+        .with_tools(vec![search]);
+
+    let diagram = architect
+        .generate_diagram(&mut task("Research the answer"))
+        .await
+        .unwrap();
+
+    assert_eq!(diagram, "print('offline')");
+    assert_eq!(mock.calls().len(), 3);
+    assert!(mock.calls()[1].contains("Result of `search`: Error: service unavailable"));
+    assert!(mock.calls()[2].contains("Result of `lookup`: Error: Unknown tool `lookup`."));
+}
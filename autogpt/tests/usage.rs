@@ -0,0 +1,146 @@
+mod common;
+
+use autogpt::agents::architect::ArchitectGPT;
+use autogpt::common::config::LlmConfig;
+use autogpt::common::usage::{Price, PriceTable, Usage, UsageReport, UsageTracker};
+use autogpt::common::utils::{ClientType, Communication};
+use autogpt::providers::mock::MockClient;
+use autogpt::traits::functions::Functions;
+use autogpt::traits::provider::LlmProvider;
+use common::{offline, task};
+use futures::StreamExt;
+
+#[test]
+fn test_report_aggregates_per_model() {
+    let mut report = UsageReport::default();
+    report.record("gpt-4o", Usage::new(100, 20));
+    report.record("gpt-4o", Usage::new(50, 10));
+    report.record("llama3.2", Usage::new(10, 5));
+
+    assert_eq!(report.requests(), 3);
+    assert_eq!(report.total(), Usage::new(160, 35));
+    assert_eq!(report.models["gpt-4o"].requests, 2);
+    assert_eq!(report.models["gpt-4o"].usage, Usage::new(150, 30));
+
+    let merged = report.clone().merge(&report);
+    assert_eq!(merged.requests(), 6);
+    assert_eq!(merged.total(), Usage::new(320, 70));
+}
+
+#[test]
+fn test_report_since_earlier_snapshot() {
+    let tracker = UsageTracker::new();
+    tracker.record("gpt-4o", Usage::new(100, 20));
+    let before = tracker.report();
+
+    tracker.record("gpt-4o", Usage::new(30, 5));
+    tracker.record("gemini-2.0-flash", Usage::new(7, 3));
+    let run = tracker.report().since(&before);
+
+    assert_eq!(run.requests(), 2);
+    assert_eq!(run.models["gpt-4o"].usage, Usage::new(30, 5));
+    assert_eq!(run.models["gemini-2.0-flash"].usage, Usage::new(7, 3));
+    assert!(tracker.report().since(&tracker.report()).is_empty());
+}
+
+#[test]
+fn test_price_table_prefers_specific_entries() {
+    let prices = PriceTable::default();
+
+    assert_eq!(prices.lookup("gpt-4o-mini"), Some(Price::new(0.15, 0.6)));
+    assert_eq!(
+        prices.lookup("gpt-4o-2024-08-06"),
+        Some(Price::new(2.5, 10.0))
+    );
+    assert_eq!(prices.lookup("llama3.2"), None);
+
+    let prices = prices.price("llama", Price::new(0.0, 0.0));
+    assert_eq!(prices.cost("llama3.2", Usage::new(1_000, 1_000)), Some(0.0));
+}
+
+#[test]
+fn test_summary_lists_models_and_cost() {
+    let prices = PriceTable::new().price("gpt-4o", Price::new(2.5, 10.0));
+    let mut report = UsageReport::default();
+    report.record("gpt-4o", Usage::new(200_000, 50_000));
+    report.record("llama3.2", Usage::new(10, 5));
+
+    assert_eq!(report.cost(&prices), 1.0);
+    assert_eq!(
+        report.summary(&prices),
+        "gpt-4o: 1 request, 200000 prompt + 50000 completion tokens, $1.0000\n\
+         llama3.2: 1 request, 10 prompt + 5 completion tokens, unpriced\n\
+         Total: 2 requests, 200010 prompt + 50005 completion tokens, $1.0000"
+    );
+}
+
+#[tokio::test]
+async fn test_client_estimates_missing_usage() {
+    let mut client = ClientType::Mock(MockClient::new().then("abcdefgh"));
+    let messages = [Communication::user("abcd")];
+
+    let completion = client
+        .completion(&messages, &LlmConfig::new())
+        .await
+        .unwrap();
+
+    assert_eq!(completion.text, "abcdefgh");
+    assert_eq!(completion.model.as_deref(), Some("mock"));
+    assert_eq!(
+        completion.usage,
+        Some(Usage::estimate(&messages, "abcdefgh"))
+    );
+}
+
+#[tokio::test]
+async fn test_tracker_meters_stream() {
+    let tracker = UsageTracker::new();
+    let mut client = ClientType::Mock(MockClient::new().then("one two three"));
+    let deltas = client.stream("Count to three").await.unwrap();
+
+    let text: Vec<String> = tracker
+        .meter("mock", "Count to three", deltas)
+        .map(|delta| delta.unwrap())
+        .collect()
+        .await;
+
+    assert_eq!(text.concat(), "one two three");
+    let report = tracker.report();
+    assert_eq!(report.models["mock"].requests, 1);
+    assert_eq!(
+        report.models["mock"].usage,
+        Usage::estimate(&[Communication::user("Count to three")], "one two three")
+    );
+}
+
+#[test]
+fn test_invalid_price_file_keeps_the_defaults() {
+    let path = std::env::temp_dir().join(format!("autogpt-prices-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{ "gpt-4o": "cheap" }"#).unwrap();
+    unsafe { std::env::set_var("AUTOGPT_PRICES", &path) };
+
+    let prices = PriceTable::from_env();
+    unsafe { std::env::remove_var("AUTOGPT_PRICES") };
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(prices, PriceTable::default());
+}
+
+#[tokio::test]
+async fn test_architect_records_usage() {
+    offline();
+
+    let mock = MockClient::new().fallback("```python\nprint('diagram')\n```");
+    let mut architect = ArchitectGPT::new("Design the system", "ArchitectGPT")
+        .await
+        .with_client(ClientType::Mock(mock.clone()));
+
+    let mut tasks = task("Hello world server");
+    architect.generate_diagram(&mut tasks).await.unwrap();
+
+    let usage = architect.get_agent().usage.report();
+    assert_eq!(usage.requests(), mock.calls().len() as u64);
+    assert!(usage.models.contains_key("mock"));
+    assert!(usage.total().prompt_tokens > 0);
+    assert!(usage.total().completion_tokens > 0);
+}